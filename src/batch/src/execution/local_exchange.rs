@@ -13,19 +13,28 @@
 // limitations under the License.
 
 use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::Instant;
 
 use risingwave_common::array::DataChunk;
 use risingwave_common::error::Result;
-use risingwave_rpc_client::ExchangeSource;
+use risingwave_rpc_client::{ExchangeMetrics, ExchangeSource};
 
 use crate::task::{BatchEnvironment, TaskId, TaskOutput, TaskOutputId};
 
+/// Label of the metrics of all local exchange sources. Unlike the address of a remote source, the
+/// task output isn't used, for it's unique to each query and would make the label unbounded.
+const LOCAL_EXCHANGE_METRICS_LABEL: &str = "local";
+
 /// Exchange data from a local task execution.
 pub struct LocalExchangeSource {
     task_output: TaskOutput,
 
     /// Id of task which contains the `ExchangeExecutor` of this source.
     task_id: TaskId,
+
+    /// Metrics of the received data, labeled by [`LOCAL_EXCHANGE_METRICS_LABEL`].
+    metrics: Arc<ExchangeMetrics>,
 }
 
 impl LocalExchangeSource {
@@ -34,6 +43,7 @@ impl LocalExchangeSource {
         Ok(Self {
             task_output,
             task_id,
+            metrics: env.stats().exchange_metrics.clone(),
         })
     }
 }
@@ -49,7 +59,13 @@ impl Debug for LocalExchangeSource {
 #[async_trait::async_trait]
 impl ExchangeSource for LocalExchangeSource {
     async fn take_data(&mut self) -> Result<Option<DataChunk>> {
+        let start_time = Instant::now();
         let ret = self.task_output.direct_take_data().await?;
+        self.metrics.report_take_data(
+            LOCAL_EXCHANGE_METRICS_LABEL,
+            ret.as_ref(),
+            start_time.elapsed(),
+        );
         if let Some(data) = ret {
             let data = data.compact()?;
             trace!(
@@ -67,13 +83,16 @@ impl ExchangeSource for LocalExchangeSource {
 
 #[cfg(test)]
 mod tests {
+    use std::future::Future;
     use std::net::SocketAddr;
-    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
     use std::sync::Arc;
     use std::thread::sleep;
     use std::time::Duration;
 
-    use risingwave_pb::data::DataChunk;
+    use futures::StreamExt;
+    use risingwave_pb::data::stream_message::StreamMessage as ProstStreamMessage;
+    use risingwave_pb::data::{Barrier, DataChunk, StreamChunk, StreamMessage};
     use risingwave_pb::plan::{TaskId, TaskOutputId};
     use risingwave_pb::task_service::exchange_service_server::{
         ExchangeService, ExchangeServiceServer,
@@ -81,10 +100,27 @@ mod tests {
     use risingwave_pb::task_service::{
         GetDataRequest, GetDataResponse, GetStreamRequest, GetStreamResponse,
     };
-    use risingwave_rpc_client::{ExchangeSource, GrpcExchangeSource};
-    use tokio_stream::wrappers::ReceiverStream;
+    use risingwave_rpc_client::{
+        ComputeClientPool, ExchangeMetrics, ExchangeRetryConfig, ExchangeSource,
+        GrpcExchangeSource, GrpcStreamSource,
+    };
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
     use tonic::{Request, Response, Status};
 
+    /// Calls `f` exactly `n` times with the iteration index, threading `state` through the calls,
+    /// and returns the final state. The state lets each call use a `&mut` borrow across `.await`.
+    async fn repeat_n<S, F, Fut>(n: usize, mut state: S, mut f: F) -> S
+    where
+        F: FnMut(usize, S) -> Fut,
+        Fut: Future<Output = S>,
+    {
+        for i in 0..n {
+            state = f(i, state).await;
+        }
+        state
+    }
+
     struct FakeExchangeService {
         rpc_called: Arc<AtomicBool>,
     }
@@ -100,22 +136,47 @@ mod tests {
         ) -> Result<Response<Self::GetDataStream>, Status> {
             let (tx, rx) = tokio::sync::mpsc::channel(10);
             self.rpc_called.store(true, Ordering::SeqCst);
-            for _ in 0..3 {
+            repeat_n(3, tx, |_, tx| async move {
                 tx.send(Ok(GetDataResponse {
                     status: None,
                     record_batch: Some(DataChunk::default()),
                 }))
                 .await
                 .unwrap();
-            }
+                tx
+            })
+            .await;
             Ok(Response::new(ReceiverStream::new(rx)))
         }
 
         async fn get_stream(
             &self,
-            _request: Request<GetStreamRequest>,
+            request: Request<GetStreamRequest>,
         ) -> Result<Response<Self::GetStreamStream>, Status> {
-            unimplemented!()
+            let (tx, rx) = tokio::sync::mpsc::channel(10);
+            self.rpc_called.store(true, Ordering::SeqCst);
+            // Fail the streams from any fragment other than 0.
+            if request.into_inner().up_fragment_id != 0 {
+                tx.send(Err(Status::internal("upstream failed")))
+                    .await
+                    .unwrap();
+                return Ok(Response::new(ReceiverStream::new(rx)));
+            }
+            let messages = [
+                ProstStreamMessage::StreamChunk(StreamChunk::default()),
+                ProstStreamMessage::StreamChunk(StreamChunk::default()),
+                ProstStreamMessage::Barrier(Barrier::default()),
+            ];
+            for message in messages {
+                tx.send(Ok(GetStreamResponse {
+                    message: Some(StreamMessage {
+                        stream_message: Some(message),
+                    }),
+                }))
+                .await
+                .unwrap();
+            }
+            Ok(Response::new(ReceiverStream::new(rx)))
         }
     }
 
@@ -145,19 +206,31 @@ mod tests {
         sleep(Duration::from_secs(1));
         assert!(server_run.load(Ordering::SeqCst));
 
-        let mut src = GrpcExchangeSource::create(
+        let metrics = Arc::new(ExchangeMetrics::unused());
+        let src = GrpcExchangeSource::create(
             addr.into(),
             TaskOutputId {
                 task_id: Some(TaskId::default()),
                 ..Default::default()
             },
+            metrics.clone(),
         )
         .await
         .unwrap();
-        for _ in 0..3 {
+        let chunk_count = || {
+            metrics
+                .exchange_recv_chunk_counts
+                .with_label_values(&[&addr.to_string()])
+                .get()
+        };
+        let mut src = repeat_n(3, src, |i, mut src| async move {
+            assert_eq!(chunk_count(), i as u64);
             assert!(src.take_data().await.unwrap().is_some());
-        }
+            src
+        })
+        .await;
         assert!(src.take_data().await.unwrap().is_none());
+        assert_eq!(chunk_count(), 3);
         assert!(rpc_called.load(Ordering::SeqCst));
 
         // Gracefully terminate the server.
@@ -165,10 +238,188 @@ mod tests {
         join_handle.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_repeat_n() {
+        let calls = repeat_n(3, vec![], |i, mut calls| async move {
+            calls.push(i);
+            calls
+        })
+        .await;
+        assert_eq!(calls, vec![0, 1, 2]);
+
+        let calls = repeat_n(0, 0, |_, calls| async move { calls + 1 }).await;
+        assert_eq!(calls, 0);
+    }
+
     #[tokio::test]
     async fn test_unconnectable_node() {
         let addr = "127.0.0.1:1001".parse().unwrap();
-        let res = GrpcExchangeSource::create(addr, TaskOutputId::default()).await;
+        let res = GrpcExchangeSource::create_with_retry(
+            addr,
+            TaskOutputId::default(),
+            ExchangeRetryConfig {
+                max_attempts: 3,
+                base_delay: Duration::from_millis(10),
+                ..Default::default()
+            },
+            Arc::new(ExchangeMetrics::unused()),
+        )
+        .await;
         assert!(res.is_err());
     }
+
+    #[tokio::test]
+    async fn test_connect_timeout() {
+        // A non-routable address, where connecting hangs until the timeout.
+        let addr = "10.255.255.1:5688".parse().unwrap();
+        let start = std::time::Instant::now();
+        let err = GrpcExchangeSource::create_with_retry(
+            addr,
+            TaskOutputId::default(),
+            ExchangeRetryConfig {
+                max_attempts: 1,
+                connect_timeout: Duration::from_millis(100),
+                ..Default::default()
+            },
+            Arc::new(ExchangeMetrics::unused()),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(err.to_string().contains("10.255.255.1:5688"), "{}", err);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_node_connectable_after_retry() {
+        let rpc_called = Arc::new(AtomicBool::new(false));
+        let addr: SocketAddr = "127.0.0.1:12346".parse().unwrap();
+
+        // Start the server only after the first attempts have failed.
+        let (shutdown_send, mut shutdown_recv) = tokio::sync::mpsc::unbounded_channel();
+        let exchange_svc = ExchangeServiceServer::new(FakeExchangeService {
+            rpc_called: rpc_called.clone(),
+        });
+        let join_handle = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            tonic::transport::Server::builder()
+                .add_service(exchange_svc)
+                .serve_with_shutdown(addr, async move {
+                    shutdown_recv.recv().await;
+                })
+                .await
+                .unwrap();
+        });
+
+        let src = GrpcExchangeSource::create_with_retry(
+            addr.into(),
+            TaskOutputId {
+                task_id: Some(TaskId::default()),
+                ..Default::default()
+            },
+            ExchangeRetryConfig {
+                max_attempts: 10,
+                base_delay: Duration::from_millis(100),
+                ..Default::default()
+            },
+            Arc::new(ExchangeMetrics::unused()),
+        )
+        .await
+        .unwrap();
+        let mut src = repeat_n(3, src, |_, mut src| async move {
+            assert!(src.take_data().await.unwrap().is_some());
+            src
+        })
+        .await;
+        assert!(src.take_data().await.unwrap().is_none());
+        assert!(rpc_called.load(Ordering::SeqCst));
+
+        shutdown_send.send(()).unwrap();
+        join_handle.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_exchange_sources_share_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let incoming = {
+            let connections = connections.clone();
+            TcpListenerStream::new(listener).inspect(move |_| {
+                connections.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+
+        let (shutdown_send, mut shutdown_recv) = tokio::sync::mpsc::unbounded_channel();
+        let exchange_svc = ExchangeServiceServer::new(FakeExchangeService {
+            rpc_called: Arc::new(AtomicBool::new(false)),
+        });
+        let join_handle = tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(exchange_svc)
+                .serve_with_incoming_shutdown(incoming, async move {
+                    shutdown_recv.recv().await;
+                })
+                .await
+                .unwrap();
+        });
+
+        let pool = ComputeClientPool::new(16);
+        for _ in 0..2 {
+            let mut src = GrpcExchangeSource::create_from_pool(
+                &pool,
+                addr.into(),
+                TaskOutputId {
+                    task_id: Some(TaskId::default()),
+                    ..Default::default()
+                },
+                ExchangeRetryConfig::default(),
+                Arc::new(ExchangeMetrics::unused()),
+            )
+            .await
+            .unwrap();
+            while src.take_data().await.unwrap().is_some() {}
+        }
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        shutdown_send.send(()).unwrap();
+        join_handle.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_stream_source() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let (shutdown_send, mut shutdown_recv) = tokio::sync::mpsc::unbounded_channel();
+        let exchange_svc = ExchangeServiceServer::new(FakeExchangeService {
+            rpc_called: Arc::new(AtomicBool::new(false)),
+        });
+        let join_handle = tokio::spawn(async move {
+            tonic::transport::Server::builder()
+                .add_service(exchange_svc)
+                .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async move {
+                    shutdown_recv.recv().await;
+                })
+                .await
+                .unwrap();
+        });
+
+        let mut src = GrpcStreamSource::create(addr.into(), 0, 1).await.unwrap();
+        let mut messages = vec![];
+        while let Some(message) = src.take_message().await.unwrap() {
+            messages.push(message.stream_message.unwrap());
+        }
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(messages[0], ProstStreamMessage::StreamChunk(_)));
+        assert!(matches!(messages[1], ProstStreamMessage::StreamChunk(_)));
+        assert!(matches!(messages[2], ProstStreamMessage::Barrier(_)));
+
+        // Errors from the upstream are propagated.
+        let mut src = GrpcStreamSource::create(addr.into(), 1, 1).await.unwrap();
+        assert!(src.take_message().await.is_err());
+
+        shutdown_send.send(()).unwrap();
+        join_handle.await.unwrap();
+    }
 }
@@ -74,8 +74,8 @@ impl Executor for DeleteExecutor {
         let mut notifiers = Vec::new();
 
         while let Some(child_chunk) = self.child.next().await? {
+            let child_chunk = child_chunk.compact()?;
             let len = child_chunk.cardinality();
-            assert!(child_chunk.visibility().is_none());
 
             let chunk = StreamChunk::from_parts(vec![Op::Delete; len], child_chunk);
 
@@ -250,4 +250,81 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_delete_after_filter() -> Result<()> {
+        use risingwave_expr::expr::expr_binary_nonnull::new_binary_expr;
+        use risingwave_expr::expr::InputRefExpression;
+        use risingwave_pb::expr::expr_node::Type;
+
+        use crate::executor::filter::FilterExecutor;
+
+        let source_manager = Arc::new(MemSourceManager::new());
+        let schema = schema_test_utils::ii();
+        let table_columns: Vec<_> = schema
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| ColumnDesc {
+                data_type: f.data_type.clone(),
+                column_id: ColumnId::from(i as i32),
+                name: f.name.clone(),
+                field_descs: vec![],
+                type_name: "".to_string(),
+            })
+            .collect();
+        let table_id = TableId::new(0);
+        source_manager.create_table_source_v2(&table_id, table_columns)?;
+        let source_desc = source_manager.get_source(&table_id)?;
+        let source = source_desc.source.as_table_v2().unwrap();
+        let mut reader = source.stream_reader(TableV2ReaderContext, vec![0.into(), 1.into()])?;
+
+        // Most rows pass the filter, and only those are deleted.
+        let col1 = column_nonnull! { I64Array, [1, 3, 5, 7, 9] };
+        let col2 = column_nonnull! { I64Array, [1, 3, 6, 7, 9] };
+        let data_chunk = DataChunk::builder().columns(vec![col1, col2]).build();
+        let filter = FilterExecutor::new(
+            new_binary_expr(
+                Type::Equal,
+                DataType::Boolean,
+                Box::new(InputRefExpression::new(DataType::Int64, 0)),
+                Box::new(InputRefExpression::new(DataType::Int64, 1)),
+            ),
+            Box::new(MockExecutor::with_chunk(data_chunk, schema)),
+            "FilterExecutor".to_string(),
+        );
+
+        let mut delete_executor =
+            DeleteExecutor::new(table_id, source_manager.clone(), Box::new(filter));
+        let handle = tokio::spawn(async move {
+            delete_executor.open().await.unwrap();
+            let result = delete_executor.next().await.unwrap().unwrap();
+            delete_executor.close().await.unwrap();
+            assert_eq!(
+                result
+                    .column_at(0)
+                    .array()
+                    .as_int64()
+                    .iter()
+                    .collect::<Vec<_>>(),
+                vec![Some(4)] // deleted rows
+            );
+        });
+
+        reader.open().await?;
+        let chunk = reader.next().await?;
+        assert_eq!(chunk.ops().to_vec(), vec![Op::Delete; 4]);
+        assert_eq!(
+            chunk.columns()[0]
+                .array()
+                .as_int64()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![Some(1), Some(3), Some(7), Some(9)]
+        );
+
+        handle.await.unwrap();
+
+        Ok(())
+    }
 }
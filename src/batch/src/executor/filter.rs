@@ -14,6 +14,7 @@
 
 use risingwave_common::array::ArrayImpl::Bool;
 use risingwave_common::array::DataChunk;
+use risingwave_common::buffer::Bitmap;
 use risingwave_common::catalog::Schema;
 use risingwave_common::error::ErrorCode::InternalError;
 use risingwave_common::error::Result;
@@ -26,11 +27,20 @@ use risingwave_pb::plan::plan_node::NodeBody;
 use super::{BoxedExecutor, BoxedExecutorBuilder};
 use crate::executor::{Executor, ExecutorBuilder};
 
+/// Chunks with a lower ratio of rows passing the filter are compacted, and the others are returned
+/// as-is with the result of the filter as visibility, so that the rows are skipped lazily by
+/// downstream executors instead of being copied.
+const FILTER_COMPACT_THRESHOLD: f64 = 0.5;
+
 pub(super) struct FilterExecutor {
     expr: BoxedExpression,
     child: BoxedExecutor,
     chunk_builder: DataChunkBuilder,
     last_input: Option<SlicedDataChunk>,
+    /// A chunk returned as-is, after the rows buffered in `chunk_builder` before it are returned.
+    pending_output: Option<DataChunk>,
+    /// See [`FILTER_COMPACT_THRESHOLD`].
+    compact_threshold: f64,
     identity: String,
     // FIXME: This is a quick fix as later we would use generator to limit chunk size.
     child_can_be_nexted: bool,
@@ -44,6 +54,10 @@ impl Executor for FilterExecutor {
 
     async fn next(&mut self) -> Result<Option<DataChunk>> {
         loop {
+            if let Some(data_chunk) = self.pending_output.take() {
+                return Ok(Some(data_chunk));
+            }
+
             let tmp_last_input = self.last_input.take();
 
             // We have something left from last poll of child
@@ -58,7 +72,17 @@ impl Executor for FilterExecutor {
             } else {
                 let child_input = self.fetch_one_chunk().await?;
                 if let Some(data_chunk) = child_input {
-                    self.last_input = Some(SlicedDataChunk::new_checked(data_chunk)?);
+                    let selectivity =
+                        data_chunk.cardinality() as f64 / data_chunk.capacity() as f64;
+                    if selectivity < self.compact_threshold {
+                        self.last_input = Some(SlicedDataChunk::new_checked(data_chunk)?);
+                    } else if let Some(buffered) = self.chunk_builder.consume_all()? {
+                        // Keep the order of rows by returning the buffered ones first.
+                        self.pending_output = Some(data_chunk);
+                        return Ok(Some(buffered));
+                    } else {
+                        return Ok(Some(data_chunk));
+                    }
                 } else {
                     // We should return here since nothing come from child.
                     return if let Some(left) = self.chunk_builder.consume_all()? {
@@ -85,26 +109,43 @@ impl Executor for FilterExecutor {
 }
 
 impl FilterExecutor {
-    /// Fetch one chunk from child.
+    pub(super) fn new(expr: BoxedExpression, child: BoxedExecutor, identity: String) -> Self {
+        let chunk_builder =
+            DataChunkBuilder::new(child.schema().data_types(), DEFAULT_CHUNK_BUFFER_SIZE);
+        Self {
+            expr,
+            child,
+            chunk_builder,
+            last_input: None,
+            pending_output: None,
+            compact_threshold: FILTER_COMPACT_THRESHOLD,
+            identity,
+            child_can_be_nexted: true,
+        }
+    }
+
+    /// Fetch one chunk from child, with the result of the filter as its visibility. Chunks of
+    /// which no row passes the filter are skipped.
     async fn fetch_one_chunk(&mut self) -> Result<Option<DataChunk>> {
-        if self.child_can_be_nexted {
+        while self.child_can_be_nexted {
             if let Some(data_chunk) = self.child.next().await? {
                 let data_chunk = data_chunk.compact()?;
                 let vis_array = self.expr.eval(&data_chunk)?;
-                return if let Bool(vis) = vis_array.as_ref() {
-                    let vis = vis.try_into()?;
-                    let data_chunk = data_chunk.with_visibility(vis);
-                    Ok(Some(data_chunk))
+                let vis: Bitmap = if let Bool(vis) = vis_array.as_ref() {
+                    vis.try_into()?
                 } else {
-                    Err(InternalError("Filter can only receive bool array".to_string()).into())
+                    return Err(
+                        InternalError("Filter can only receive bool array".to_string()).into(),
+                    );
                 };
+                if vis.num_high_bits() > 0 {
+                    return Ok(Some(data_chunk.with_visibility(vis)));
+                }
             } else {
                 self.child_can_be_nexted = false;
-                Ok(None)
             }
-        } else {
-            Ok(None)
         }
+        Ok(None)
     }
 }
 
@@ -122,19 +163,8 @@ impl BoxedExecutorBuilder for FilterExecutor {
         if let Some(child_plan) = source.plan_node.get_children().get(0) {
             let child = source.clone_for_plan(child_plan).build()?;
             debug!("Child schema: {:?}", child.schema());
-            let chunk_builder =
-                DataChunkBuilder::new(child.schema().data_types(), DEFAULT_CHUNK_BUFFER_SIZE);
-
             return Ok(Box::new(
-                Self {
-                    expr,
-                    child,
-                    chunk_builder,
-                    last_input: None,
-                    identity: source.plan_node().get_identity().clone(),
-                    child_can_be_nexted: true,
-                }
-                .fuse(),
+                Self::new(expr, child, source.plan_node().get_identity().clone()).fuse(),
             ));
         }
         Err(InternalError("Filter must have one children".to_string()).into())
@@ -147,7 +177,7 @@ mod tests {
 
     use assert_matches::assert_matches;
     use risingwave_common::array::column::Column;
-    use risingwave_common::array::{Array, DataChunk, PrimitiveArray};
+    use risingwave_common::array::{Array, DataChunk, PrimitiveArray, Row};
     use risingwave_common::catalog::{Field, Schema};
     use risingwave_common::types::DataType;
     use risingwave_expr::expr::build_from_prost;
@@ -157,7 +187,8 @@ mod tests {
     use risingwave_pb::expr::{ExprNode, FunctionCall, InputRefExpr};
 
     use super::*;
-    use crate::executor::test_utils::MockExecutor;
+    use crate::executor::test_utils::{run_to_completion, MockExecutor};
+    use crate::executor::union::UnionAllExecutor;
 
     #[tokio::test]
     async fn test_filter_executor() {
@@ -179,6 +210,9 @@ mod tests {
             child: Box::new(mock_executor),
             chunk_builder,
             last_input: None,
+            pending_output: None,
+            // Always compact the output.
+            compact_threshold: f64::INFINITY,
             identity: "FilterExecutor".to_string(),
             child_can_be_nexted: true,
         };
@@ -209,6 +243,180 @@ mod tests {
         filter_executor.close().await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_filter_over_union() {
+        let schema = Schema {
+            fields: vec![
+                Field::unnamed(DataType::Int32),
+                Field::unnamed(DataType::Int32),
+            ],
+        };
+        let inputs = [
+            [&[Some(1), Some(2)], &[Some(1), Some(3)]],
+            [&[Some(5), Some(6)], &[Some(0), Some(6)]],
+        ]
+        .into_iter()
+        .map(|[col1, col2]| {
+            let data_chunk = DataChunk::builder()
+                .columns(vec![
+                    create_column(col1).unwrap(),
+                    create_column(col2).unwrap(),
+                ])
+                .build();
+            Box::new(MockExecutor::with_chunk(data_chunk, schema.clone())) as BoxedExecutor
+        })
+        .collect();
+        let union_executor = UnionAllExecutor::new(inputs, "UnionAllExecutor".to_string()).unwrap();
+        let filter_executor = FilterExecutor {
+            expr: build_from_prost(&make_expression(Type::Equal)).unwrap(),
+            chunk_builder: DataChunkBuilder::new(union_executor.schema().data_types(), 1024),
+            child: Box::new(union_executor),
+            last_input: None,
+            pending_output: None,
+            // Always compact the output.
+            compact_threshold: f64::INFINITY,
+            identity: "FilterExecutor".to_string(),
+            child_can_be_nexted: true,
+        };
+
+        let chunks = run_to_completion(Box::new(filter_executor)).await.unwrap();
+        let rows = chunks
+            .iter()
+            .flat_map(|chunk| chunk.rows().map(Row::from).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                Row(vec![Some(1_i32.into()), Some(1_i32.into())]),
+                Row(vec![Some(6_i32.into()), Some(6_i32.into())]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filter_with_visibility() {
+        let schema = Schema {
+            fields: vec![
+                Field::unnamed(DataType::Int32),
+                Field::unnamed(DataType::Int32),
+            ],
+        };
+        // Chunks with 0, 1, 2, 3 and 4 out of 4 rows passing the filter.
+        let inputs = (0..=4).map(chunk_with_passed).collect::<Vec<_>>();
+
+        let run = |compact_threshold: f64| {
+            let mut mock_executor = MockExecutor::new(schema.clone());
+            for chunk in &inputs {
+                mock_executor.add(chunk.clone());
+            }
+            let mut filter_executor = FilterExecutor::new(
+                build_from_prost(&make_expression(Type::Equal)).unwrap(),
+                Box::new(mock_executor),
+                "FilterExecutor".to_string(),
+            );
+            filter_executor.compact_threshold = compact_threshold;
+            run_to_completion(Box::new(filter_executor))
+        };
+        let rows_of = |chunks: &[DataChunk]| {
+            chunks
+                .iter()
+                .flat_map(|chunk| chunk.rows().map(Row::from).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        };
+
+        let compacted = run(f64::INFINITY).await.unwrap();
+        assert!(compacted.iter().all(|chunk| chunk.visibility().is_none()));
+        assert_eq!(rows_of(&compacted).len(), 1 + 2 + 3 + 4);
+
+        for compact_threshold in [0.0, FILTER_COMPACT_THRESHOLD, 0.75, 1.0] {
+            let chunks = run(compact_threshold).await.unwrap();
+            assert_eq!(rows_of(&chunks), rows_of(&compacted));
+            // The all-false chunk yields nothing.
+            assert!(chunks.iter().all(|chunk| chunk.cardinality() > 0));
+        }
+
+        // Without compaction, every chunk is returned as-is with a visibility.
+        let chunks = run(0.0).await.unwrap();
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| (chunk.cardinality(), chunk.capacity()))
+                .collect::<Vec<_>>(),
+            vec![(1, 4), (2, 4), (3, 4), (4, 4)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filter_all_false() {
+        let schema = Schema {
+            fields: vec![
+                Field::unnamed(DataType::Int32),
+                Field::unnamed(DataType::Int32),
+            ],
+        };
+        let mut mock_executor = MockExecutor::new(schema);
+        mock_executor.add(chunk_with_passed(0));
+        mock_executor.add(chunk_with_passed(0));
+        let mut filter_executor = FilterExecutor::new(
+            build_from_prost(&make_expression(Type::Equal)).unwrap(),
+            Box::new(mock_executor),
+            "FilterExecutor".to_string(),
+        );
+
+        filter_executor.open().await.unwrap();
+        assert!(filter_executor.next().await.unwrap().is_none());
+        // Nothing is buffered for compaction either.
+        assert!(filter_executor.last_input.is_none());
+        assert!(filter_executor
+            .chunk_builder
+            .consume_all()
+            .unwrap()
+            .is_none());
+        filter_executor.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_filter_keeps_visibility() {
+        let schema = Schema {
+            fields: vec![
+                Field::unnamed(DataType::Int32),
+                Field::unnamed(DataType::Int32),
+            ],
+        };
+        let mut mock_executor = MockExecutor::new(schema);
+        // Most rows pass, so the chunk is not worth compacting.
+        mock_executor.add(chunk_with_passed(3));
+        // Few rows pass, so the chunk is compacted.
+        mock_executor.add(chunk_with_passed(1));
+        let filter_executor = FilterExecutor::new(
+            build_from_prost(&make_expression(Type::Equal)).unwrap(),
+            Box::new(mock_executor),
+            "FilterExecutor".to_string(),
+        );
+
+        let chunks = run_to_completion(Box::new(filter_executor)).await.unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].capacity(), 4);
+        assert_eq!(chunks[0].cardinality(), 3);
+        assert!(chunks[0].visibility().is_some());
+        assert_eq!(chunks[1].capacity(), 1);
+        assert!(chunks[1].visibility().is_none());
+    }
+
+    /// Builds a chunk of 4 rows, of which the first `passed` rows have equal columns.
+    fn chunk_with_passed(passed: i32) -> DataChunk {
+        let col1 = (0..4).map(Some).collect::<Vec<_>>();
+        let col2 = (0..4)
+            .map(|i| Some(if i < passed { i } else { -1 }))
+            .collect::<Vec<_>>();
+        DataChunk::builder()
+            .columns(vec![
+                create_column(&col1).unwrap(),
+                create_column(&col2).unwrap(),
+            ])
+            .build()
+    }
+
     fn make_expression(kind: Type) -> ExprNode {
         let lhs = make_inputref(0);
         let rhs = make_inputref(1);
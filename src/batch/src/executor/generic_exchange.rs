@@ -14,10 +14,14 @@
 
 use std::marker::PhantomData;
 
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt};
 use risingwave_common::array::DataChunk;
 use risingwave_common::catalog::{Field, Schema};
 use risingwave_common::error::Result;
 use risingwave_common::util::addr::{is_local_address, HostAddr};
+use risingwave_pb::plan::exchange_node::OrderingMode;
 use risingwave_pb::plan::plan_node::NodeBody;
 use risingwave_pb::plan::{ExchangeSource as ProstExchangeSource, Field as NodeField};
 use risingwave_rpc_client::{ExchangeSource, GrpcExchangeSource};
@@ -33,9 +37,13 @@ pub struct GenericExchangeExecutor<C> {
     sources: Vec<ProstExchangeSource>,
     server_addr: HostAddr,
     env: BatchEnvironment,
+    ordering_mode: OrderingMode,
 
     source_idx: usize,
     current_source: Option<Box<dyn ExchangeSource>>,
+    /// Pending takes of the sources not yet exhausted, only used in
+    /// [`OrderingMode::Interleave`].
+    interleaved_sources: Option<FuturesUnordered<TakeDataFuture>>,
 
     // Mock-able CreateSource.
     source_creator: PhantomData<C>,
@@ -78,7 +86,14 @@ impl CreateSource for DefaultCreateSource {
             value.get_task_output_id()
         );
         Ok(Box::new(
-            GrpcExchangeSource::create(peer_addr, value.get_task_output_id()?.clone()).await?,
+            GrpcExchangeSource::create_from_pool(
+                env.compute_client_pool(),
+                peer_addr,
+                value.get_task_output_id()?.clone(),
+                env.exchange_config(),
+                env.stats().exchange_metrics.clone(),
+            )
+            .await?,
         ))
     }
 }
@@ -101,9 +116,11 @@ impl<CS: 'static + CreateSource> BoxedExecutorBuilder for GenericExchangeExecuto
                 sources,
                 server_addr,
                 env: source.env.clone(),
+                ordering_mode: node.get_ordering_mode()?,
                 source_creator: PhantomData,
                 source_idx: 0,
                 current_source: None,
+                interleaved_sources: None,
                 schema: Schema { fields },
                 task_id: source.task_id.clone(),
                 identity: source.plan_node().get_identity().clone(),
@@ -120,6 +137,39 @@ impl<CS: CreateSource> Executor for GenericExchangeExecutor<CS> {
     }
 
     async fn next(&mut self) -> Result<Option<DataChunk>> {
+        match self.ordering_mode {
+            OrderingMode::PreservePartitionOrder => self.next_in_partition_order().await,
+            OrderingMode::Interleave => self.next_interleaved().await,
+        }
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn identity(&self) -> &str {
+        &self.identity
+    }
+}
+
+/// Takes a chunk from a source, handing the source back along with the result.
+type TakeDataFuture = BoxFuture<'static, (Box<dyn ExchangeSource>, Result<Option<DataChunk>>)>;
+
+fn take_data(mut source: Box<dyn ExchangeSource>) -> TakeDataFuture {
+    async move {
+        let res = source.take_data().await;
+        (source, res)
+    }
+    .boxed()
+}
+
+impl<CS: CreateSource> GenericExchangeExecutor<CS> {
+    /// Takes all chunks of a source before moving on to the next one.
+    async fn next_in_partition_order(&mut self) -> Result<Option<DataChunk>> {
         loop {
             if self.source_idx >= self.sources.len() {
                 break;
@@ -149,16 +199,28 @@ impl<CS: CreateSource> Executor for GenericExchangeExecutor<CS> {
         Ok(None)
     }
 
-    async fn close(&mut self) -> Result<()> {
-        Ok(())
-    }
-
-    fn schema(&self) -> &Schema {
-        &self.schema
-    }
+    /// Polls all sources concurrently and returns whichever chunk comes first, dropping sources
+    /// once they are exhausted.
+    async fn next_interleaved(&mut self) -> Result<Option<DataChunk>> {
+        if self.interleaved_sources.is_none() {
+            let sources = FuturesUnordered::new();
+            for proto_source in &self.sources {
+                let source =
+                    CS::create_source(self.env.clone(), proto_source, self.task_id.clone()).await?;
+                sources.push(take_data(source));
+            }
+            self.interleaved_sources = Some(sources);
+        }
 
-    fn identity(&self) -> &str {
-        &self.identity
+        let sources = self.interleaved_sources.as_mut().unwrap();
+        while let Some((source, res)) = sources.next().await {
+            if let Some(res) = res? {
+                assert_ne!(res.cardinality(), 0);
+                sources.push(take_data(source));
+                return Ok(Some(res));
+            }
+        }
+        Ok(None)
     }
 }
 
@@ -167,55 +229,98 @@ mod tests {
     use std::sync::Arc;
 
     use risingwave_common::array::column::Column;
-    use risingwave_common::array::{DataChunk, I32Array};
+    use risingwave_common::array::{Array, DataChunk, I32Array};
     use risingwave_common::array_nonnull;
     use risingwave_common::types::DataType;
+    use risingwave_pb::plan::TaskOutputId;
 
     use super::*;
 
-    #[tokio::test]
-    async fn test_exchange_multiple_sources() {
-        #[derive(Debug)]
-        struct FakeExchangeSource {
-            chunk: Option<DataChunk>,
+    #[derive(Debug)]
+    struct FakeExchangeSource {
+        chunks: Vec<DataChunk>,
+    }
+
+    #[async_trait::async_trait]
+    impl ExchangeSource for FakeExchangeSource {
+        async fn take_data(&mut self) -> Result<Option<DataChunk>> {
+            Ok(self.chunks.pop())
         }
+    }
 
-        #[async_trait::async_trait]
-        impl ExchangeSource for FakeExchangeSource {
-            async fn take_data(&mut self) -> Result<Option<DataChunk>> {
-                let chunk = self.chunk.take();
-                Ok(chunk)
-            }
+    /// A source that never outputs anything.
+    #[derive(Debug)]
+    struct PendingExchangeSource;
+
+    #[async_trait::async_trait]
+    impl ExchangeSource for PendingExchangeSource {
+        async fn take_data(&mut self) -> Result<Option<DataChunk>> {
+            futures::future::pending().await
         }
+    }
 
-        struct FakeCreateSource {}
-
-        #[async_trait::async_trait]
-        impl CreateSource for FakeCreateSource {
-            async fn create_source(
-                _: BatchEnvironment,
-                _: &ProstExchangeSource,
-                _: TaskId,
-            ) -> Result<Box<dyn ExchangeSource>> {
-                let chunk = DataChunk::builder()
-                    .columns(vec![Column::new(Arc::new(
-                        array_nonnull! { I32Array, [3, 4, 4] }.into(),
-                    ))])
-                    .build();
-                Ok(Box::new(FakeExchangeSource { chunk: Some(chunk) }))
+    /// Id of the output whose source is a [`PendingExchangeSource`].
+    const PENDING_OUTPUT_ID: u32 = 99;
+
+    /// Creates sources that output chunks `[10 * output_id]`, `[10 * output_id + 1]` and
+    /// `[10 * output_id + 2]` in order.
+    struct FakeCreateSource {}
+
+    #[async_trait::async_trait]
+    impl CreateSource for FakeCreateSource {
+        async fn create_source(
+            _: BatchEnvironment,
+            value: &ProstExchangeSource,
+            _: TaskId,
+        ) -> Result<Box<dyn ExchangeSource>> {
+            let output_id = value.get_task_output_id()?.output_id;
+            if output_id == PENDING_OUTPUT_ID {
+                return Ok(Box::new(PendingExchangeSource));
             }
+            let base = output_id as i32 * 10;
+            let chunks = (0..3)
+                .rev()
+                .map(|i| {
+                    DataChunk::builder()
+                        .columns(vec![Column::new(Arc::new(
+                            array_nonnull! { I32Array, [base + i] }.into(),
+                        ))])
+                        .build()
+                })
+                .collect();
+            Ok(Box::new(FakeExchangeSource { chunks }))
         }
+    }
 
-        let mut sources: Vec<ProstExchangeSource> = vec![];
-        for _ in 0..3 {
-            sources.push(ProstExchangeSource::default());
-        }
+    fn create_executor(
+        source_num: u32,
+        ordering_mode: OrderingMode,
+    ) -> GenericExchangeExecutor<FakeCreateSource> {
+        create_executor_with_outputs(0..source_num, ordering_mode)
+    }
+
+    fn create_executor_with_outputs(
+        output_ids: impl IntoIterator<Item = u32>,
+        ordering_mode: OrderingMode,
+    ) -> GenericExchangeExecutor<FakeCreateSource> {
+        let sources = output_ids
+            .into_iter()
+            .map(|output_id| ProstExchangeSource {
+                task_output_id: Some(TaskOutputId {
+                    task_id: None,
+                    output_id,
+                }),
+                host: None,
+            })
+            .collect();
 
-        let mut executor = GenericExchangeExecutor::<FakeCreateSource> {
+        GenericExchangeExecutor::<FakeCreateSource> {
             sources,
             server_addr: "127.0.0.1:5688".parse().unwrap(),
+            ordering_mode,
             source_idx: 0,
             current_source: None,
+            interleaved_sources: None,
             source_creator: PhantomData,
             env: BatchEnvironment::for_test(),
             schema: Schema {
@@ -223,7 +328,20 @@ mod tests {
             },
             task_id: TaskId::default(),
             identity: "GenericExchangeExecutor".to_string(),
-        };
+        }
+    }
+
+    async fn collect_values(executor: &mut GenericExchangeExecutor<FakeCreateSource>) -> Vec<i32> {
+        let mut values = vec![];
+        while let Some(chunk) = executor.next().await.unwrap() {
+            values.extend(chunk.column_at(0).array().as_int32().iter().flatten());
+        }
+        values
+    }
+
+    #[tokio::test]
+    async fn test_exchange_multiple_sources() {
+        let mut executor = create_executor(3, OrderingMode::PreservePartitionOrder);
 
         let mut chunks: usize = 0;
         loop {
@@ -233,6 +351,51 @@ mod tests {
                 None => break,
             }
         }
-        assert_eq!(chunks, 3);
+        assert_eq!(chunks, 9);
+    }
+
+    #[tokio::test]
+    async fn test_exchange_ordering_mode() {
+        let mut executor = create_executor(2, OrderingMode::PreservePartitionOrder);
+        assert_eq!(
+            collect_values(&mut executor).await,
+            vec![0, 1, 2, 10, 11, 12]
+        );
+
+        // Chunks of different sources are mixed, but each source is still in order.
+        let mut executor = create_executor(2, OrderingMode::Interleave);
+        let values = collect_values(&mut executor).await;
+        assert_eq!(values.len(), 6);
+        for base in [0, 10] {
+            let partition = values
+                .iter()
+                .filter(|v| **v / 10 * 10 == base)
+                .copied()
+                .collect::<Vec<_>>();
+            assert_eq!(partition, vec![base, base + 1, base + 2]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interleave_skips_pending_source() {
+        // The pending source comes first, but doesn't hold back chunks of the other one.
+        let mut executor =
+            create_executor_with_outputs([PENDING_OUTPUT_ID, 1], OrderingMode::Interleave);
+        for value in [10, 11, 12] {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(1), executor.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                chunk.column_at(0).array().as_int32().value_at(0),
+                Some(value)
+            );
+        }
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(50), executor.next())
+                .await
+                .is_err()
+        );
     }
 }
@@ -88,8 +88,8 @@ impl Executor for InsertExecutor {
         let mut notifiers = Vec::new();
 
         while let Some(child_chunk) = self.child.next().await? {
+            let child_chunk = child_chunk.compact()?;
             let len = child_chunk.cardinality();
-            assert!(child_chunk.visibility().is_none());
 
             // add row-id column as first column
             let mut builder = I64ArrayBuilder::new(len).unwrap();
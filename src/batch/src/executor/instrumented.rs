@@ -0,0 +1,141 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use risingwave_common::array::DataChunk;
+use risingwave_common::catalog::Schema;
+use risingwave_common::error::Result;
+
+use crate::executor::{BoxedExecutor, Executor};
+
+/// If `collect_executor_metrics` is enabled, we build an [`InstrumentedExecutor`] on top of the
+/// underlying executor. It records the wall-clock time spent in the executor (including its
+/// children) and the number of chunks and rows it emits, and logs a summary on close.
+pub(super) struct InstrumentedExecutor {
+    child: BoxedExecutor,
+    /// Total time spent in `open`, `next` and `close` of the child.
+    elapsed: Duration,
+    chunk_count: usize,
+    /// Number of visible rows in the emitted chunks.
+    row_count: usize,
+}
+
+impl InstrumentedExecutor {
+    pub fn new(child: BoxedExecutor) -> Self {
+        Self {
+            child,
+            elapsed: Duration::ZERO,
+            chunk_count: 0,
+            row_count: 0,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn chunk_count(&self) -> usize {
+        self.chunk_count
+    }
+
+    #[cfg(test)]
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+}
+
+#[async_trait::async_trait]
+impl Executor for InstrumentedExecutor {
+    async fn open(&mut self) -> Result<()> {
+        let start = Instant::now();
+        let result = self.child.open().await;
+        self.elapsed += start.elapsed();
+        result
+    }
+
+    async fn next(&mut self) -> Result<Option<DataChunk>> {
+        let start = Instant::now();
+        let result = self.child.next().await;
+        self.elapsed += start.elapsed();
+        if let Ok(Some(chunk)) = &result {
+            self.chunk_count += 1;
+            self.row_count += chunk.cardinality();
+        }
+        result
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        let start = Instant::now();
+        let result = self.child.close().await;
+        self.elapsed += start.elapsed();
+        tracing::debug!(
+            "{} emitted {} chunks and {} rows in {:?}",
+            self.child.identity(),
+            self.chunk_count,
+            self.row_count,
+            self.elapsed
+        );
+        result
+    }
+
+    fn schema(&self) -> &Schema {
+        self.child.schema()
+    }
+
+    /// Keeps the identity of the child, so that wrapping is transparent to plans, metrics and
+    /// errors.
+    fn identity(&self) -> &str {
+        self.child.identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use risingwave_common::array::column::Column;
+    use risingwave_common::array::{Array, I32Array};
+    use risingwave_common::catalog::{Field, Schema};
+    use risingwave_common::types::DataType;
+
+    use super::*;
+    use crate::executor::test_utils::MockExecutor;
+
+    #[tokio::test]
+    async fn test_instrumented_executor() {
+        let schema = Schema {
+            fields: vec![Field::unnamed(DataType::Int32)],
+        };
+        let mut mock_executor = MockExecutor::new(schema.clone());
+        for len in [3, 5] {
+            let array = I32Array::from_slice(&vec![Some(1); len]).unwrap();
+            let column = Column::new(Arc::new(array.into()));
+            mock_executor.add(DataChunk::builder().columns(vec![column]).build());
+        }
+
+        let identity = mock_executor.identity().to_string();
+        let mut executor = InstrumentedExecutor::new(Box::new(mock_executor));
+        assert_eq!(executor.schema(), &schema);
+        assert_eq!(executor.identity(), identity);
+        executor.open().await.unwrap();
+        let mut rows = 0;
+        while let Some(chunk) = executor.next().await.unwrap() {
+            assert_eq!(chunk.column_at(0).array().as_int32().value_at(0), Some(1));
+            rows += chunk.cardinality();
+        }
+        executor.close().await.unwrap();
+
+        assert_eq!(rows, 8);
+        assert_eq!(executor.row_count(), rows);
+        assert_eq!(executor.chunk_count(), 2);
+    }
+}
@@ -667,10 +667,20 @@ mod tests {
         }
 
         fn create_join_executor(&self, has_non_equi_cond: bool) -> BoxedExecutor {
-            let join_type = self.join_type;
+            self.create_join_executor_with_children(
+                self.create_left_executor(),
+                self.create_right_executor(),
+                has_non_equi_cond,
+            )
+        }
 
-            let left_child = self.create_left_executor();
-            let right_child = self.create_right_executor();
+        fn create_join_executor_with_children(
+            &self,
+            left_child: BoxedExecutor,
+            right_child: BoxedExecutor,
+            has_non_equi_cond: bool,
+        ) -> BoxedExecutor {
+            let join_type = self.join_type;
 
             let schema_fields = if join_type.keep_all() {
                 [
@@ -800,6 +810,28 @@ mod tests {
         test_fixture.do_test(expected_chunk, false).await;
     }
 
+    #[tokio::test]
+    async fn test_inner_join_with_empty_build_side() {
+        let test_fixture = TestFixture::with_join_type(JoinType::Inner);
+        let empty_right_child = MockExecutor::new(Schema {
+            fields: vec![
+                Field::unnamed(DataType::Int32),
+                Field::unnamed(DataType::Float64),
+            ],
+        });
+        let mut join_executor = test_fixture.create_join_executor_with_children(
+            test_fixture.create_left_executor(),
+            Box::new(empty_right_child),
+            false,
+        );
+
+        join_executor.open().await.unwrap();
+        while let Some(data_chunk) = join_executor.next().await.unwrap() {
+            assert_eq!(data_chunk.cardinality(), 0);
+        }
+        join_executor.close().await.unwrap();
+    }
+
     /// Sql:
     /// ```sql
     /// select t1.v2 as t1_v2, t2.v2 as t2_v2 from t1 join t2 on t1.v1 = t2.v1 and t1.v2 < t2.v2;
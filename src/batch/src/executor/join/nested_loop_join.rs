@@ -671,6 +671,8 @@ mod tests {
         left_types: Vec<DataType>,
         right_types: Vec<DataType>,
         join_type: JoinType,
+        /// Comparison between `t1.v1` and `t2.v1` as the join condition.
+        condition: Type,
     }
 
     /// Sql for creating test data:
@@ -690,10 +692,15 @@ mod tests {
     /// ```
     impl TestFixture {
         fn with_join_type(join_type: JoinType) -> Self {
+            Self::with_join_type_and_condition(join_type, Type::Equal)
+        }
+
+        fn with_join_type_and_condition(join_type: JoinType, condition: Type) -> Self {
             Self {
                 left_types: vec![DataType::Int32, DataType::Float32],
                 right_types: vec![DataType::Int32, DataType::Float64],
                 join_type,
+                condition,
             }
         }
 
@@ -816,7 +823,7 @@ mod tests {
 
             Box::new(NestedLoopJoinExecutor {
                 join_expr: new_binary_expr(
-                    Type::Equal,
+                    self.condition,
                     DataType::Boolean,
                     Box::new(InputRefExpression::new(DataType::Int32, 0)),
                     Box::new(InputRefExpression::new(DataType::Int32, 2)),
@@ -891,6 +898,54 @@ mod tests {
         test_fixture.do_test(expected_chunk).await;
     }
 
+    /// sql: select * from t1, t2 where t1.v1 > t2.v1
+    #[tokio::test]
+    async fn test_inequality_inner_join() {
+        let test_fixture =
+            TestFixture::with_join_type_and_condition(JoinType::Inner, Type::GreaterThan);
+
+        let column1 = Column::new(Arc::new(
+            array! {I32Array, [Some(3), Some(3), Some(4), Some(4), Some(6), Some(6), Some(6), Some(6), Some(8), Some(8), Some(8)]}.into(),
+        ));
+
+        let column2 = Column::new(Arc::new(array! {F32Array, [Some(3.9f32), Some(6.6f32), Some(0.7f32), Some(0.7f32), Some(5.5f32), Some(5.5f32), Some(5.6f32), Some(5.6f32), Some(7.0f32), Some(7.0f32), Some(7.0f32)]}.into()));
+
+        let column3 = Column::new(Arc::new(
+            array! {I32Array, [Some(2), Some(2), Some(2), Some(3), Some(2), Some(3), Some(2), Some(3), Some(2), Some(3), Some(6)]}.into(),
+        ));
+
+        let column4 = Column::new(Arc::new(array! {F64Array, [Some(6.1f64), Some(6.1f64), Some(6.1f64), Some(8.9f64), Some(6.1f64), Some(8.9f64), Some(6.1f64), Some(8.9f64), Some(6.1f64), Some(8.9f64), Some(3.4f64)]}.into()));
+
+        let expected_chunk = DataChunk::try_from(vec![column1, column2, column3, column4])
+            .expect("Failed to create chunk!");
+
+        test_fixture.do_test(expected_chunk).await;
+    }
+
+    /// sql: select * from t1 left outer join t2 on t1.v1 > t2.v1
+    #[tokio::test]
+    async fn test_inequality_left_outer_join() {
+        let test_fixture =
+            TestFixture::with_join_type_and_condition(JoinType::LeftOuter, Type::GreaterThan);
+
+        let column1 = Column::new(Arc::new(
+            array! {I32Array, [Some(1), Some(2), Some(3), Some(3), Some(4), Some(4), Some(6), Some(6), Some(6), Some(6), Some(8), Some(8), Some(8)]}.into(),
+        ));
+
+        let column2 = Column::new(Arc::new(array! {F32Array, [Some(6.1f32), Some(8.4f32), Some(3.9f32), Some(6.6f32), Some(0.7f32), Some(0.7f32), Some(5.5f32), Some(5.5f32), Some(5.6f32), Some(5.6f32), Some(7.0f32), Some(7.0f32), Some(7.0f32)]}.into()));
+
+        let column3 = Column::new(Arc::new(
+            array! {I32Array, [None, None, Some(2), Some(2), Some(2), Some(3), Some(2), Some(3), Some(2), Some(3), Some(2), Some(3), Some(6)]}.into(),
+        ));
+
+        let column4 = Column::new(Arc::new(array! {F64Array, [None, None, Some(6.1f64), Some(6.1f64), Some(6.1f64), Some(8.9f64), Some(6.1f64), Some(8.9f64), Some(6.1f64), Some(8.9f64), Some(6.1f64), Some(8.9f64), Some(3.4f64)]}.into()));
+
+        let expected_chunk = DataChunk::try_from(vec![column1, column2, column3, column4])
+            .expect("Failed to create chunk!");
+
+        test_fixture.do_test(expected_chunk).await;
+    }
+
     #[tokio::test]
     async fn test_left_semi_join() {
         let test_fixture = TestFixture::with_join_type(JoinType::LeftSemi);
@@ -0,0 +1,337 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::try_join_all;
+use itertools::Itertools;
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use risingwave_common::array::column::Column;
+use risingwave_common::array::{ArrayBuilder, DataChunk, PrimitiveArrayBuilder, RowRef};
+use risingwave_common::catalog::{Field, Schema};
+use risingwave_common::error::ErrorCode::{InternalError, ProtocolError};
+use risingwave_common::error::{Result, RwError};
+use risingwave_common::types::{DataType, DatumRef, ScalarRefImpl};
+use risingwave_pb::plan::plan_node::NodeBody;
+use serde_json::{Map, Value};
+
+use super::BoxedExecutor;
+use crate::executor::{BoxedExecutorBuilder, Executor, ExecutorBuilder};
+
+const KAFKA_TOPIC_KEY: &str = "kafka.topic";
+const KAFKA_BOOTSTRAP_SERVERS_KEY: &str = "kafka.bootstrap.servers";
+
+/// How long a message may wait for room in the producer queue before its delivery fails.
+const KAFKA_SINK_QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// [`KafkaSinkExecutor`] produces the rows of its child executor to a Kafka topic, and returns the
+/// number of rows produced.
+///
+/// Each row is encoded as a JSON object keyed by the column names of the child. All rows of a
+/// chunk are sent as one batch, and the next chunk is only pulled after the whole batch is
+/// delivered. Any delivery error fails the executor.
+///
+/// The delivery is at-least-once: rows delivered before a failure are not revoked, and will be
+/// produced again if the query is retried. Exactly-once delivery requires the consumers to
+/// deduplicate the rows, e.g. by a unique column.
+pub struct KafkaSinkExecutor {
+    child: BoxedExecutor,
+    producer: FutureProducer,
+    topic: String,
+    executed: bool,
+    schema: Schema,
+    identity: String,
+}
+
+impl KafkaSinkExecutor {
+    pub fn new(
+        child: BoxedExecutor,
+        properties: &HashMap<String, String>,
+        identity: String,
+    ) -> Result<Self> {
+        let get_property = |key: &str| {
+            properties
+                .get(key)
+                .ok_or_else(|| RwError::from(ProtocolError(format!("property {} not found", key))))
+        };
+        let topic = get_property(KAFKA_TOPIC_KEY)?.clone();
+        let producer = ClientConfig::new()
+            .set(
+                "bootstrap.servers",
+                get_property(KAFKA_BOOTSTRAP_SERVERS_KEY)?,
+            )
+            .create()
+            .map_err(|e| {
+                RwError::from(InternalError(format!(
+                    "failed to create kafka producer: {}",
+                    e
+                )))
+            })?;
+
+        Ok(Self {
+            child,
+            producer,
+            topic,
+            executed: false,
+            schema: Schema {
+                fields: vec![Field::unnamed(DataType::Int64)],
+            },
+            identity,
+        })
+    }
+
+    /// Produces the visible rows of `chunk`, and waits for all of them to be delivered.
+    async fn produce_chunk(&self, chunk: &DataChunk) -> Result<usize> {
+        let fields = &self.child.schema().fields;
+        let payloads = chunk
+            .rows()
+            .map(|row| row_to_json(fields, &row).to_string())
+            .collect_vec();
+
+        let deliveries = payloads.iter().map(|payload| {
+            self.producer.send(
+                FutureRecord::<(), _>::to(&self.topic).payload(payload),
+                KAFKA_SINK_QUEUE_TIMEOUT,
+            )
+        });
+        try_join_all(deliveries).await.map_err(|(e, _)| {
+            RwError::from(InternalError(format!(
+                "failed to deliver message to kafka: {}",
+                e
+            )))
+        })?;
+
+        Ok(payloads.len())
+    }
+}
+
+/// Encodes `row` as a JSON object keyed by the names of `fields`. Unnamed fields are keyed by
+/// their indices instead.
+fn row_to_json(fields: &[Field], row: &RowRef) -> Value {
+    let object = fields
+        .iter()
+        .zip_eq(row.0.iter())
+        .enumerate()
+        .map(|(idx, (field, datum))| {
+            let name = if field.name.is_empty() {
+                idx.to_string()
+            } else {
+                field.name.clone()
+            };
+            (name, datum_to_json(*datum))
+        })
+        .collect::<Map<_, _>>();
+    Value::Object(object)
+}
+
+/// Numbers and booleans are encoded as their JSON counterparts, and other types as their textual
+/// representation.
+fn datum_to_json(datum: DatumRef) -> Value {
+    match datum {
+        None => Value::Null,
+        Some(ScalarRefImpl::Bool(v)) => v.into(),
+        Some(ScalarRefImpl::Int16(v)) => v.into(),
+        Some(ScalarRefImpl::Int32(v)) => v.into(),
+        Some(ScalarRefImpl::Int64(v)) => v.into(),
+        // Non-finite floats become `null`, as JSON has no representation for them.
+        Some(ScalarRefImpl::Float32(v)) => v.into_inner().into(),
+        Some(ScalarRefImpl::Float64(v)) => v.into_inner().into(),
+        Some(scalar) => Value::String(scalar.to_string()),
+    }
+}
+
+#[async_trait::async_trait]
+impl Executor for KafkaSinkExecutor {
+    async fn open(&mut self) -> Result<()> {
+        self.child.open().await?;
+        Ok(())
+    }
+
+    async fn next(&mut self) -> Result<Option<DataChunk>> {
+        if self.executed {
+            return Ok(None);
+        }
+
+        let mut rows_produced = 0;
+        while let Some(chunk) = self.child.next().await? {
+            rows_produced += self.produce_chunk(&chunk).await?;
+        }
+
+        let mut array_builder = PrimitiveArrayBuilder::<i64>::new(1)?;
+        array_builder.append(Some(rows_produced as i64))?;
+        let array = array_builder.finish()?;
+        let ret_chunk = DataChunk::builder()
+            .columns(vec![Column::new(Arc::new(array.into()))])
+            .build();
+
+        self.executed = true;
+        Ok(Some(ret_chunk))
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.child.close().await?;
+        Ok(())
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn identity(&self) -> &str {
+        &self.identity
+    }
+}
+
+impl BoxedExecutorBuilder for KafkaSinkExecutor {
+    fn new_boxed_executor(source: &ExecutorBuilder) -> Result<BoxedExecutor> {
+        let kafka_sink_node = try_match_expand!(
+            source.plan_node().get_node_body().unwrap(),
+            NodeBody::KafkaSink
+        )?;
+
+        let proto_child = source.plan_node.get_children().get(0).ok_or_else(|| {
+            RwError::from(InternalError(String::from("Child interpreting error")))
+        })?;
+        let child = source.clone_for_plan(proto_child).build()?;
+
+        Ok(Box::new(
+            Self::new(
+                child,
+                &kafka_sink_node.properties,
+                source.plan_node().get_identity().clone(),
+            )?
+            .fuse(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rdkafka::consumer::{Consumer, StreamConsumer};
+    use rdkafka::mocking::MockCluster;
+    use rdkafka::Message;
+    use risingwave_common::array::{Array, F64Array, I32Array, Utf8Array};
+    use risingwave_common::column;
+    use serde_json::json;
+
+    use super::*;
+    use crate::executor::test_utils::MockExecutor;
+
+    #[test]
+    fn test_row_to_json() {
+        let fields = vec![
+            Field::with_name(DataType::Int32, "id"),
+            Field::unnamed(DataType::Float64),
+            Field::with_name(DataType::Varchar, "name"),
+        ];
+        let chunk = DataChunk::builder()
+            .columns(vec![
+                column!(I32Array, [Some(1), None]),
+                column!(F64Array, [Some(1.5.into()), Some(f64::NAN.into())]),
+                column!(Utf8Array, [Some("a"), Some("b")]),
+            ])
+            .build();
+
+        let rows = chunk
+            .rows()
+            .map(|row| row_to_json(&fields, &row))
+            .collect_vec();
+        assert_eq!(
+            rows,
+            vec![
+                json!({"id": 1, "1": 1.5, "name": "a"}),
+                json!({"id": null, "1": null, "name": "b"}),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_kafka_sink_executor() {
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic("sink", 1, 1).unwrap();
+        let bootstrap_servers = cluster.bootstrap_servers();
+
+        let schema = Schema {
+            fields: vec![
+                Field::with_name(DataType::Int32, "id"),
+                Field::with_name(DataType::Varchar, "name"),
+            ],
+        };
+        let mut child = MockExecutor::new(schema);
+        child.add(
+            DataChunk::builder()
+                .columns(vec![
+                    column!(I32Array, [Some(1), Some(2)]),
+                    column!(Utf8Array, [Some("a"), None]),
+                ])
+                .build(),
+        );
+        child.add(
+            DataChunk::builder()
+                .columns(vec![
+                    column!(I32Array, [Some(3)]),
+                    column!(Utf8Array, [Some("c")]),
+                ])
+                .build(),
+        );
+
+        let properties = HashMap::from([
+            (KAFKA_TOPIC_KEY.to_string(), "sink".to_string()),
+            (
+                KAFKA_BOOTSTRAP_SERVERS_KEY.to_string(),
+                bootstrap_servers.clone(),
+            ),
+        ]);
+        let mut executor = KafkaSinkExecutor::new(
+            Box::new(child),
+            &properties,
+            "KafkaSinkExecutor".to_string(),
+        )
+        .unwrap();
+        executor.open().await.unwrap();
+        let result = executor.next().await.unwrap().unwrap();
+        assert_eq!(result.column_at(0).array().as_int64().value_at(0), Some(3));
+        assert!(executor.next().await.unwrap().is_none());
+        executor.close().await.unwrap();
+
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &bootstrap_servers)
+            .set("group.id", "kafka_sink_test")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .unwrap();
+        consumer.subscribe(&["sink"]).unwrap();
+
+        let mut payloads = vec![];
+        while payloads.len() < 3 {
+            let message = tokio::time::timeout(Duration::from_secs(10), consumer.recv())
+                .await
+                .expect("timed out waiting for produced rows")
+                .unwrap();
+            let payload: Value = serde_json::from_slice(message.payload().unwrap()).unwrap();
+            payloads.push(payload);
+        }
+        assert_eq!(
+            payloads,
+            vec![
+                json!({"id": 1, "name": "a"}),
+                json!({"id": 2, "name": null}),
+                json!({"id": 3, "name": "c"}),
+            ]
+        );
+    }
+}
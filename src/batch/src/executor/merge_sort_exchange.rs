@@ -294,6 +294,7 @@ mod tests {
         let order_pairs = Arc::new(vec![OrderPair {
             column_idx: 0,
             order_type: OrderType::Ascending,
+            nulls_first: false,
         }]);
 
         let mut executor = MergeSortExchangeExecutorImpl::<FakeCreateSource> {
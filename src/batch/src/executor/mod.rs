@@ -37,11 +37,14 @@ pub use crate::executor::create_table::CreateTableExecutor;
 pub use crate::executor::delete::DeleteExecutor;
 use crate::executor::generate_series::GenerateSeriesI32Executor;
 pub use crate::executor::insert::InsertExecutor;
+use crate::executor::instrumented::InstrumentedExecutor;
 use crate::executor::join::nested_loop_join::NestedLoopJoinExecutor;
 use crate::executor::join::sort_merge_join::SortMergeJoinExecutor;
 use crate::executor::join::HashJoinExecutorBuilder;
+use crate::executor::kafka_sink::KafkaSinkExecutor;
 pub use crate::executor::stream_scan::StreamScanExecutor;
 use crate::executor::trace::TraceExecutor;
+use crate::executor::union::UnionAllExecutor;
 use crate::executor::values::ValuesExecutor;
 use crate::task::{BatchEnvironment, TaskId};
 
@@ -56,7 +59,9 @@ mod generate_series;
 mod generic_exchange;
 mod hash_agg;
 mod insert;
+mod instrumented;
 mod join;
+mod kafka_sink;
 mod limit;
 mod merge_sort_exchange;
 pub mod monitor;
@@ -69,6 +74,7 @@ mod stream_scan;
 mod test_utils;
 mod top_n;
 mod trace;
+mod union;
 mod values;
 
 /// `Executor` is an operator in the query execution.
@@ -181,9 +187,16 @@ impl<'a> ExecutorBuilder<'a> {
             NodeBody::DropSource => DropStreamExecutor,
             NodeBody::HashAgg => HashAggExecutorBuilder,
             NodeBody::MergeSortExchange => MergeSortExchangeExecutor,
-            NodeBody::GenerateInt32Series => GenerateSeriesI32Executor
+            NodeBody::GenerateInt32Series => GenerateSeriesI32Executor,
+            NodeBody::Union => UnionAllExecutor,
+            NodeBody::KafkaSink => KafkaSinkExecutor
         }?;
         let input_desc = real_executor.identity().to_string();
+        let real_executor: BoxedExecutor = if self.env.config().collect_executor_metrics {
+            Box::new(InstrumentedExecutor::new(real_executor))
+        } else {
+            real_executor
+        };
         Ok(Box::new(TraceExecutor::new(real_executor, input_desc)))
     }
 
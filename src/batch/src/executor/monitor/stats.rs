@@ -12,12 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 //
+use std::sync::Arc;
+
 use prometheus::{
     histogram_opts, register_histogram_with_registry, Histogram, Registry, DEFAULT_BUCKETS,
 };
+use risingwave_rpc_client::ExchangeMetrics;
 
 pub struct BatchMetrics {
     pub row_seq_scan_next_duration: Histogram,
+    pub exchange_metrics: Arc<ExchangeMetrics>,
 }
 
 impl BatchMetrics {
@@ -30,8 +34,11 @@ impl BatchMetrics {
         );
         let row_seq_scan_next_duration = register_histogram_with_registry!(opts, registry).unwrap();
 
+        let exchange_metrics = Arc::new(ExchangeMetrics::new(registry));
+
         Self {
             row_seq_scan_next_duration,
+            exchange_metrics,
         }
     }
 
@@ -312,10 +312,12 @@ mod tests {
             OrderPair {
                 column_idx: 1,
                 order_type: OrderType::Ascending,
+                nulls_first: false,
             },
             OrderPair {
                 column_idx: 0,
                 order_type: OrderType::Ascending,
+                nulls_first: false,
             },
         ];
         let mut order_by_executor = OrderByExecutor {
@@ -364,10 +366,12 @@ mod tests {
             OrderPair {
                 column_idx: 1,
                 order_type: OrderType::Ascending,
+                nulls_first: false,
             },
             OrderPair {
                 column_idx: 0,
                 order_type: OrderType::Ascending,
+                nulls_first: false,
             },
         ];
         let mut order_by_executor = OrderByExecutor {
@@ -425,10 +429,12 @@ mod tests {
             OrderPair {
                 column_idx: 1,
                 order_type: OrderType::Ascending,
+                nulls_first: false,
             },
             OrderPair {
                 column_idx: 0,
                 order_type: OrderType::Ascending,
+                nulls_first: false,
             },
         ];
         let mut order_by_executor = OrderByExecutor {
@@ -457,6 +463,118 @@ mod tests {
         }
     }
 
+    async fn order_by_with_nulls(
+        order_pairs: Vec<OrderPair>,
+        disable_encoding: bool,
+    ) -> Vec<(Option<i32>, Option<i32>)> {
+        let col0 = create_column_i32(&[Some(1), None, Some(1), Some(2), None, Some(1)]).unwrap();
+        let col1 = create_column_i32(&[Some(1), Some(2), None, Some(3), None, Some(2)]).unwrap();
+        let data_chunk = DataChunk::builder().columns([col0, col1].to_vec()).build();
+        let schema = Schema {
+            fields: vec![
+                Field::unnamed(DataType::Int32),
+                Field::unnamed(DataType::Int32),
+            ],
+        };
+        let mut mock_executor = MockExecutor::new(schema);
+        mock_executor.add(data_chunk);
+        let mut order_by_executor = OrderByExecutor {
+            order_pairs: Arc::new(order_pairs),
+            child: Box::new(mock_executor),
+            vis_indices: vec![],
+            chunks: vec![],
+            sorted_indices: vec![],
+            min_heap: BinaryHeap::new(),
+            encoded_keys: vec![],
+            encodable: false,
+            disable_encoding,
+            identity: "OrderByExecutor".to_string(),
+        };
+        order_by_executor.open().await.unwrap();
+        let mut rows = vec![];
+        while let Some(res) = order_by_executor.next().await.unwrap() {
+            let col0 = res.column_at(0).array();
+            let col1 = res.column_at(1).array();
+            for i in 0..res.cardinality() {
+                rows.push((col0.as_int32().value_at(i), col1.as_int32().value_at(i)));
+            }
+        }
+        order_by_executor.close().await.unwrap();
+        rows
+    }
+
+    #[tokio::test]
+    async fn test_nulls_first_and_last() {
+        for disable_encoding in [false, true] {
+            // ORDER BY col0 ASC NULLS FIRST, col1 DESC NULLS LAST
+            let order_pairs = vec![
+                OrderPair {
+                    column_idx: 0,
+                    order_type: OrderType::Ascending,
+                    nulls_first: true,
+                },
+                OrderPair {
+                    column_idx: 1,
+                    order_type: OrderType::Descending,
+                    nulls_first: false,
+                },
+            ];
+            assert_eq!(
+                order_by_with_nulls(order_pairs, disable_encoding).await,
+                vec![
+                    (None, Some(2)),
+                    (None, None),
+                    (Some(1), Some(2)),
+                    (Some(1), Some(1)),
+                    (Some(1), None),
+                    (Some(2), Some(3)),
+                ]
+            );
+
+            // ORDER BY col0 DESC NULLS LAST, col1 ASC NULLS FIRST
+            let order_pairs = vec![
+                OrderPair {
+                    column_idx: 0,
+                    order_type: OrderType::Descending,
+                    nulls_first: false,
+                },
+                OrderPair {
+                    column_idx: 1,
+                    order_type: OrderType::Ascending,
+                    nulls_first: true,
+                },
+            ];
+            assert_eq!(
+                order_by_with_nulls(order_pairs, disable_encoding).await,
+                vec![
+                    (Some(2), Some(3)),
+                    (Some(1), None),
+                    (Some(1), Some(1)),
+                    (Some(1), Some(2)),
+                    (None, None),
+                    (None, Some(2)),
+                ]
+            );
+
+            // ORDER BY col0 ASC, col1 DESC, where nulls are the largest by default.
+            let order_pairs = vec![
+                OrderPair::new(0, OrderType::Ascending),
+                OrderPair::new(1, OrderType::Descending),
+            ];
+            assert_eq!(
+                order_by_with_nulls(order_pairs, disable_encoding).await,
+                vec![
+                    (Some(1), None),
+                    (Some(1), Some(2)),
+                    (Some(1), Some(1)),
+                    (Some(2), Some(3)),
+                    (None, None),
+                    (None, Some(2)),
+                ]
+            );
+        }
+    }
+
     // TODO: enable benches
 
     // fn benchmark_1e4(b: &mut Bencher, enable_encoding: bool) {
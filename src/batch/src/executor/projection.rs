@@ -40,7 +40,8 @@ impl Executor for ProjectionExecutor {
         let child_output = self.child.next().await?;
         match child_output {
             Some(child_chunk) => {
-                // let child_chunk = child_chunk.compact()?;
+                // Expressions don't handle the visibility consistently.
+                let child_chunk = child_chunk.compact()?;
                 let arrays: Vec<Column> = self
                     .expr
                     .iter_mut()
@@ -174,7 +175,8 @@ mod tests {
             Schema::default(),
             "ValuesExecutor".to_string(),
             1024,
-        );
+        )
+        .unwrap();
 
         let mut proj_executor = ProjectionExecutor {
             expr: vec![Box::new(literal)],
@@ -190,4 +192,64 @@ mod tests {
             array_nonnull!(I32Array, [1]).into()
         );
     }
+
+    #[tokio::test]
+    async fn test_project_after_filter() {
+        use risingwave_common::buffer::Bitmap;
+        use risingwave_expr::expr::expr_binary_nonnull::new_binary_expr;
+        use risingwave_pb::expr::expr_node::Type;
+
+        use crate::executor::filter::FilterExecutor;
+        use crate::executor::test_utils::run_to_completion;
+
+        let build_chunk = || {
+            let col1 = column_nonnull! {I32Array, [1, 2, 3, 4]};
+            let col2 = column_nonnull! {I32Array, [1, 2, 0, 4]};
+            DataChunk::builder().columns(vec![col1, col2]).build()
+        };
+        let input_ref = |idx| Box::new(InputRefExpression::new(DataType::Int32, idx));
+        let build_project = |child: BoxedExecutor| ProjectionExecutor {
+            expr: vec![new_binary_expr(
+                Type::Add,
+                DataType::Int32,
+                input_ref(0),
+                input_ref(1),
+            )],
+            child,
+            schema: schema_unnamed!(DataType::Int32),
+            identity: "ProjectionExecutor".to_string(),
+        };
+        let output = |chunks: Vec<DataChunk>| {
+            chunks
+                .iter()
+                .flat_map(|chunk| {
+                    chunk
+                        .column_at(0)
+                        .array()
+                        .as_int32()
+                        .iter()
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Most rows pass the filter.
+        let schema = schema_unnamed! { DataType::Int32, DataType::Int32 };
+        let filter = FilterExecutor::new(
+            new_binary_expr(Type::Equal, DataType::Boolean, input_ref(0), input_ref(1)),
+            Box::new(MockExecutor::with_chunk(build_chunk(), schema.clone())),
+            "FilterExecutor".to_string(),
+        );
+        let chunks = run_to_completion(Box::new(build_project(Box::new(filter))))
+            .await
+            .unwrap();
+        assert_eq!(output(chunks), vec![Some(2), Some(4), Some(8)]);
+
+        // Rows invisible in the input don't show up in the output.
+        let chunk =
+            build_chunk().with_visibility(Bitmap::try_from(vec![true, true, false, true]).unwrap());
+        let project = build_project(Box::new(MockExecutor::with_chunk(chunk, schema)));
+        let chunks = run_to_completion(Box::new(project)).await.unwrap();
+        assert_eq!(output(chunks), vec![Some(2), Some(4), Some(8)]);
+    }
 }
@@ -16,7 +16,7 @@ use std::sync::Arc;
 
 use itertools::Itertools;
 use risingwave_common::array::column::Column;
-use risingwave_common::array::DataChunk;
+use risingwave_common::array::{ArrayBuilderImpl, DataChunk};
 use risingwave_common::catalog::{Field, Schema};
 use risingwave_common::error::{ErrorCode, Result};
 use risingwave_expr::expr::{build_from_prost, BoxedExpression};
@@ -34,6 +34,10 @@ use crate::executor::{BoxedExecutor, Executor, ExecutorBuilder};
 ///
 /// As a special case, simple aggregate without groups satisfies the requirement
 /// automatically because all tuples should be aggregated together.
+///
+/// Groups completed by an input chunk are emitted right after the chunk, while the
+/// last group of the chunk may continue in the next one, so its partial state is kept
+/// until the next group starts or the input is exhausted.
 pub(super) struct SortAggExecutor {
     agg_states: Vec<BoxedAggState>,
     group_exprs: Vec<BoxedExpression>,
@@ -109,16 +113,15 @@ impl Executor for SortAggExecutor {
             return Ok(None);
         }
 
-        let cardinality = 1;
         let mut group_builders = self
             .group_exprs
             .iter()
-            .map(|e| e.return_type().create_array_builder(cardinality))
+            .map(|e| e.return_type().create_array_builder(1))
             .collect::<Result<Vec<_>>>()?;
         let mut array_builders = self
             .agg_states
             .iter()
-            .map(|e| e.return_type().create_array_builder(cardinality))
+            .map(|e| e.return_type().create_array_builder(1))
             .collect::<Result<Vec<_>>>()?;
 
         while let Some(child_chunk) = self.child.next().await? {
@@ -150,6 +153,11 @@ impl Executor for SortAggExecutor {
                 .try_for_each(|(state, builder)| {
                     state.update_and_output_with_sorted_groups(&child_chunk, builder, &groups)
                 })?;
+
+            // Each starting index of a new group completes the previous group.
+            if !groups.get_starting_indices().is_empty() {
+                return Ok(Some(Self::build_chunk(group_builders, array_builders)?));
+            }
         }
         self.child_done = true;
 
@@ -162,14 +170,7 @@ impl Executor for SortAggExecutor {
             .zip_eq(&mut array_builders)
             .try_for_each(|(state, builder)| state.output(builder))?;
 
-        let columns = group_builders
-            .into_iter()
-            .chain(array_builders)
-            .map(|b| Ok(Column::new(Arc::new(b.finish()?))))
-            .collect::<Result<Vec<_>>>()?;
-
-        let ret = DataChunk::builder().columns(columns).build();
-
+        let ret = Self::build_chunk(group_builders, array_builders)?;
         if ret.cardinality() == 0 {
             Ok(None)
         } else {
@@ -190,6 +191,20 @@ impl Executor for SortAggExecutor {
     }
 }
 
+impl SortAggExecutor {
+    fn build_chunk(
+        group_builders: Vec<ArrayBuilderImpl>,
+        array_builders: Vec<ArrayBuilderImpl>,
+    ) -> Result<DataChunk> {
+        let columns = group_builders
+            .into_iter()
+            .chain(array_builders)
+            .map(|b| Ok(Column::new(Arc::new(b.finish()?))))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(DataChunk::builder().columns(columns).build())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use risingwave_common::array::{Array as _, I32Array, I64Array};
@@ -269,36 +284,12 @@ mod tests {
         Ok(())
     }
 
-    #[tokio::test]
-    #[allow(clippy::many_single_char_names)]
-    async fn execute_sum_int32_grouped() -> Result<()> {
-        use risingwave_common::array::ArrayImpl;
-        let a: Arc<ArrayImpl> = Arc::new(array_nonnull! { I32Array, [1, 2, 3] }.into());
-        let chunk = DataChunk::builder()
-            .columns(vec![
-                Column::new(a.clone()),
-                Column::new(Arc::new(array_nonnull! { I32Array, [1, 1, 3] }.into())),
-                Column::new(Arc::new(array_nonnull! { I32Array, [7, 8, 8] }.into())),
-            ])
-            .build();
-        let schema = Schema {
-            fields: vec![
-                Field::unnamed(DataType::Int32),
-                Field::unnamed(DataType::Int32),
-                Field::unnamed(DataType::Int32),
-            ],
-        };
-        let mut child = MockExecutor::new(schema);
-        child.add(chunk);
-        let chunk = DataChunk::builder()
-            .columns(vec![
-                Column::new(a),
-                Column::new(Arc::new(array_nonnull! { I32Array, [3, 4, 4] }.into())),
-                Column::new(Arc::new(array_nonnull! { I32Array, [8, 8, 8] }.into())),
-            ])
-            .build();
-        child.add(chunk);
-
+    /// Builds a [`SortAggExecutor`] summing up column 0 of `child`, grouped by `group_columns`.
+    /// All columns are of `Int32`.
+    fn create_grouped_sum_executor(
+        child: MockExecutor,
+        group_columns: &[usize],
+    ) -> Result<SortAggExecutor> {
         let prost = AggCall {
             r#type: Type::Sum as i32,
             args: vec![Arg {
@@ -317,7 +308,8 @@ mod tests {
 
         let s = AggStateFactory::new(&prost)?.create_agg_state()?;
 
-        let group_exprs = (1..=2)
+        let group_exprs = group_columns
+            .iter()
             .map(|idx| {
                 build_from_prost(&ExprNode {
                     expr_type: InputRef as i32,
@@ -325,7 +317,9 @@ mod tests {
                         type_name: TypeName::Int32 as i32,
                         ..Default::default()
                     }),
-                    rex_node: Some(RexNode::InputRef(InputRefExpr { column_idx: idx })),
+                    rex_node: Some(RexNode::InputRef(InputRefExpr {
+                        column_idx: *idx as i32,
+                    })),
                 })
             })
             .collect::<Result<Vec<BoxedExpression>>>()?;
@@ -342,7 +336,7 @@ mod tests {
             .map(Field::unnamed)
             .collect::<Vec<Field>>();
 
-        let mut executor = SortAggExecutor {
+        Ok(SortAggExecutor {
             agg_states,
             group_exprs,
             sorted_groupers,
@@ -350,32 +344,106 @@ mod tests {
             child_done: false,
             schema: Schema { fields },
             identity: "SortAggExecutor".to_string(),
+        })
+    }
+
+    fn int32_column(column: &Column) -> Vec<Option<i32>> {
+        column.array().as_int32().iter().collect()
+    }
+
+    fn int64_column(column: &Column) -> Vec<Option<i64>> {
+        column.array().as_int64().iter().collect()
+    }
+
+    #[tokio::test]
+    #[allow(clippy::many_single_char_names)]
+    async fn execute_sum_int32_grouped() -> Result<()> {
+        use risingwave_common::array::ArrayImpl;
+        let a: Arc<ArrayImpl> = Arc::new(array_nonnull! { I32Array, [1, 2, 3] }.into());
+        let chunk = DataChunk::builder()
+            .columns(vec![
+                Column::new(a.clone()),
+                Column::new(Arc::new(array_nonnull! { I32Array, [1, 1, 3] }.into())),
+                Column::new(Arc::new(array_nonnull! { I32Array, [7, 8, 8] }.into())),
+            ])
+            .build();
+        let schema = Schema {
+            fields: vec![
+                Field::unnamed(DataType::Int32),
+                Field::unnamed(DataType::Int32),
+                Field::unnamed(DataType::Int32),
+            ],
         };
+        let mut child = MockExecutor::new(schema);
+        child.add(chunk);
+        let chunk = DataChunk::builder()
+            .columns(vec![
+                Column::new(a),
+                Column::new(Arc::new(array_nonnull! { I32Array, [3, 4, 4] }.into())),
+                Column::new(Arc::new(array_nonnull! { I32Array, [8, 8, 8] }.into())),
+            ])
+            .build();
+        child.add(chunk);
+
+        let mut executor = create_grouped_sum_executor(child, &[1, 2])?;
 
         executor.open().await?;
         let fields = &executor.schema().fields;
         assert_eq!(fields[0].data_type, DataType::Int32);
         assert_eq!(fields[1].data_type, DataType::Int32);
         assert_eq!(fields[2].data_type, DataType::Int64);
+        // Groups completed by each chunk are emitted right away, and group (3, 8) spanning
+        // both chunks is emitted after the second chunk.
         let o = executor.next().await?.unwrap();
-        if executor.next().await?.is_some() {
-            panic!("simple agg should have no more than 1 output.");
-        }
+        assert_eq!(int32_column(o.column_at(0)), vec![Some(1), Some(1)]);
+        assert_eq!(int32_column(o.column_at(1)), vec![Some(7), Some(8)]);
+        assert_eq!(int64_column(o.column_at(2)), vec![Some(1), Some(2)]);
+
+        let o = executor.next().await?.unwrap();
+        assert_eq!(int32_column(o.column_at(0)), vec![Some(3)]);
+        assert_eq!(int32_column(o.column_at(1)), vec![Some(8)]);
+        assert_eq!(int64_column(o.column_at(2)), vec![Some(4)]);
+
+        // The last group is flushed when the input is exhausted.
+        let o = executor.next().await?.unwrap();
+        assert_eq!(int32_column(o.column_at(0)), vec![Some(4)]);
+        assert_eq!(int32_column(o.column_at(1)), vec![Some(8)]);
+        assert_eq!(int64_column(o.column_at(2)), vec![Some(5)]);
+
+        assert!(executor.next().await?.is_none());
         executor.close().await?;
 
-        let actual = o.column_at(2).array();
-        let actual: &I64Array = actual.as_ref().into();
-        let v = actual.iter().collect::<Vec<Option<i64>>>();
-        assert_eq!(v, vec![Some(1), Some(2), Some(4), Some(5)]);
-
-        assert_eq!(
-            o.column_at(0).array().as_int32().iter().collect::<Vec<_>>(),
-            vec![Some(1), Some(1), Some(3), Some(4)]
-        );
-        assert_eq!(
-            o.column_at(1).array().as_int32().iter().collect::<Vec<_>>(),
-            vec![Some(7), Some(8), Some(8), Some(8)]
-        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn execute_sum_int32_single_group() -> Result<()> {
+        let schema = Schema {
+            fields: vec![
+                Field::unnamed(DataType::Int32),
+                Field::unnamed(DataType::Int32),
+            ],
+        };
+        let mut child = MockExecutor::new(schema);
+        for values in [[1, 2, 3], [4, 5, 6]] {
+            child.add(
+                DataChunk::builder()
+                    .columns(vec![
+                        Column::new(Arc::new(I32Array::from_slice(&values.map(Some))?.into())),
+                        Column::new(Arc::new(array_nonnull! { I32Array, [7, 7, 7] }.into())),
+                    ])
+                    .build(),
+            );
+        }
+
+        let mut executor = create_grouped_sum_executor(child, &[1])?;
+        executor.open().await?;
+        // The only group never completes until the input is exhausted.
+        let o = executor.next().await?.unwrap();
+        assert_eq!(int32_column(o.column_at(0)), vec![Some(7)]);
+        assert_eq!(int64_column(o.column_at(1)), vec![Some(21)]);
+        assert!(executor.next().await?.is_none());
+        executor.close().await?;
 
         Ok(())
     }
@@ -79,6 +79,18 @@ impl Executor for MockExecutor {
     }
 }
 
+/// Drives `executor` through `open`, `next` until exhausted and `close`, and returns all chunks it
+/// outputs. `executor` can be the root of a pipeline, which is run as a whole.
+pub async fn run_to_completion(mut executor: BoxedExecutor) -> Result<Vec<DataChunk>> {
+    let mut chunks = vec![];
+    executor.open().await?;
+    while let Some(chunk) = executor.next().await? {
+        chunks.push(chunk);
+    }
+    executor.close().await?;
+    Ok(chunks)
+}
+
 /// if the input from two child executor is same(considering order),
 /// it will also check the columns structure of chunks from child executor
 /// use for executor unit test.
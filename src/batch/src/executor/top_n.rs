@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::cmp::Reverse;
+use std::cmp::{Ordering, Reverse};
 use std::collections::BinaryHeap;
 use std::sync::Arc;
 use std::vec::Vec;
@@ -27,15 +27,49 @@ use risingwave_pb::plan::plan_node::NodeBody;
 use super::{BoxedExecutor, BoxedExecutorBuilder};
 use crate::executor::{Executor, ExecutorBuilder};
 
+/// An element in [`TopNHeap`]. Rows with equal sort keys are ordered by `seq`, the index of the
+/// row in the input, so that the output is deterministic.
+struct TopNHeapElem {
+    elem: HeapElem,
+    seq: usize,
+}
+
+impl Ord for TopNHeapElem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `HeapElem` is reversed, i.e. a "greater" element ranks first. So is `seq`.
+        self.elem
+            .cmp(&other.elem)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for TopNHeapElem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TopNHeapElem {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TopNHeapElem {}
+
 struct TopNHeap {
     order_pairs: Arc<Vec<OrderPair>>,
-    min_heap: BinaryHeap<Reverse<HeapElem>>,
+    min_heap: BinaryHeap<Reverse<TopNHeapElem>>,
     limit: usize,
+    offset: usize,
+    /// Number of rows fitted so far, used as the `seq` of the next row.
+    row_count: usize,
 }
 
 impl TopNHeap {
-    fn insert(&mut self, elem: HeapElem) {
-        if self.min_heap.len() < self.limit {
+    fn insert(&mut self, elem: TopNHeapElem) {
+        // The first `offset` rows are kept as well, as they are only dropped after ranking.
+        if self.min_heap.len() < self.limit + self.offset {
             self.min_heap.push(Reverse(elem));
         } else if elem > self.min_heap.peek().unwrap().0 {
             self.min_heap.push(Reverse(elem));
@@ -55,7 +89,9 @@ impl TopNHeap {
                     elem_idx: 0usize,
                     encoded_chunk: None,
                 };
-                self.insert(elem);
+                let seq = self.row_count;
+                self.row_count += 1;
+                self.insert(TopNHeapElem { elem, seq });
             });
     }
 
@@ -66,9 +102,13 @@ impl TopNHeap {
         let mut chunks = self
             .min_heap
             .drain_sorted()
-            .map(|e| e.0.chunk)
+            .map(|e| e.0.elem.chunk)
             .collect::<Vec<_>>();
         chunks.reverse();
+        let chunks = chunks.split_off(self.offset.min(chunks.len()));
+        if chunks.is_empty() {
+            return None;
+        }
         if let Ok(mut res) = DataChunk::rechunk(&chunks, self.limit) {
             assert_eq!(res.len(), 1);
             Some(res.remove(0))
@@ -103,6 +143,7 @@ impl BoxedExecutorBuilder for TopNExecutor {
                     child,
                     order_pairs,
                     top_n_node.get_limit() as usize,
+                    top_n_node.get_offset() as usize,
                     source.plan_node().get_identity().clone(),
                 )
                 .fuse(),
@@ -117,12 +158,15 @@ impl TopNExecutor {
         child: BoxedExecutor,
         order_pairs: Vec<OrderPair>,
         limit: usize,
+        offset: usize,
         identity: String,
     ) -> Self {
         Self {
             top_n_heap: TopNHeap {
                 min_heap: BinaryHeap::new(),
                 limit,
+                offset,
+                row_count: 0,
                 order_pairs: Arc::new(order_pairs),
             },
             child,
@@ -200,16 +244,19 @@ mod tests {
             OrderPair {
                 column_idx: 1,
                 order_type: OrderType::Ascending,
+                nulls_first: false,
             },
             OrderPair {
                 column_idx: 0,
                 order_type: OrderType::Ascending,
+                nulls_first: false,
             },
         ];
         let mut top_n_executor = TopNExecutor::new(
             Box::new(mock_executor),
             order_pairs,
             2usize,
+            0usize,
             "TopNExecutor".to_string(),
         );
         let fields = &top_n_executor.schema().fields;
@@ -228,4 +275,71 @@ mod tests {
         assert!(matches!(res, None));
         top_n_executor.close().await.unwrap();
     }
+
+    async fn top_n_with_ties(limit: usize, offset: usize) -> Vec<(i32, i32)> {
+        // Rows are (key, id), where the sort key has duplicates and `id` is the input row index.
+        let schema = Schema {
+            fields: vec![
+                Field::unnamed(DataType::Int32),
+                Field::unnamed(DataType::Int32),
+            ],
+        };
+        let mut mock_executor = MockExecutor::new(schema);
+        mock_executor.add(
+            DataChunk::builder()
+                .columns(vec![
+                    create_column(&[Some(2), Some(1), Some(2)]).unwrap(),
+                    create_column(&[Some(0), Some(1), Some(2)]).unwrap(),
+                ])
+                .build(),
+        );
+        mock_executor.add(
+            DataChunk::builder()
+                .columns(vec![
+                    create_column(&[Some(1), Some(2)]).unwrap(),
+                    create_column(&[Some(3), Some(4)]).unwrap(),
+                ])
+                .build(),
+        );
+        let order_pairs = vec![OrderPair {
+            column_idx: 0,
+            order_type: OrderType::Ascending,
+            nulls_first: false,
+        }];
+        let mut top_n_executor = TopNExecutor::new(
+            Box::new(mock_executor),
+            order_pairs,
+            limit,
+            offset,
+            "TopNExecutor".to_string(),
+        );
+        top_n_executor.open().await.unwrap();
+        let mut rows = vec![];
+        while let Some(res) = top_n_executor.next().await.unwrap() {
+            let key = res.column_at(0).array();
+            let id = res.column_at(1).array();
+            for i in 0..res.cardinality() {
+                rows.push((
+                    key.as_int32().value_at(i).unwrap(),
+                    id.as_int32().value_at(i).unwrap(),
+                ));
+            }
+        }
+        top_n_executor.close().await.unwrap();
+        rows
+    }
+
+    #[tokio::test]
+    async fn test_top_n_executor_with_offset_and_ties() {
+        // Ranked rows are (1, 1), (1, 3), (2, 0), (2, 2), (2, 4).
+        assert_eq!(top_n_with_ties(2, 0).await, vec![(1, 1), (1, 3)]);
+        assert_eq!(top_n_with_ties(2, 1).await, vec![(1, 3), (2, 0)]);
+        assert_eq!(top_n_with_ties(5, 3).await, vec![(2, 2), (2, 4)]);
+        assert_eq!(top_n_with_ties(2, 5).await, vec![]);
+
+        // The output is the same across runs.
+        for _ in 0..10 {
+            assert_eq!(top_n_with_ties(3, 1).await, vec![(1, 3), (2, 0), (2, 2)]);
+        }
+    }
 }
@@ -0,0 +1,278 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use itertools::Itertools;
+use risingwave_common::array::column::Column;
+use risingwave_common::array::DataChunk;
+use risingwave_common::catalog::Schema;
+use risingwave_common::error::ErrorCode::InternalError;
+use risingwave_common::error::Result;
+use risingwave_common::types::DataType;
+use risingwave_expr::expr::expr_unary::new_unary_expr;
+use risingwave_expr::expr::{BoxedExpression, InputRefExpression};
+use risingwave_pb::expr::expr_node::Type as ExprType;
+use risingwave_pb::plan::plan_node::NodeBody;
+
+use crate::executor::{BoxedExecutor, BoxedExecutorBuilder, Executor, ExecutorBuilder};
+
+/// `UnionAllExecutor` concatenates the outputs of all its children, one child after another.
+///
+/// All children must produce the same number of columns. Column types may differ as long as they
+/// can be implicitly widened to a common type (e.g. `int` and `bigint` to `bigint`), in which case
+/// a cast is applied to the outputs of the narrower children.
+pub(super) struct UnionAllExecutor {
+    inputs: Vec<BoxedExecutor>,
+    /// Casts applied to the output of each input, `None` if the input already matches `schema`.
+    casts: Vec<Option<Vec<BoxedExpression>>>,
+    /// Index of the input currently being consumed.
+    current: usize,
+    schema: Schema,
+    identity: String,
+}
+
+impl UnionAllExecutor {
+    pub(super) fn new(inputs: Vec<BoxedExecutor>, identity: String) -> Result<Self> {
+        let schema = Self::derive_schema(&inputs)?;
+
+        let casts = inputs
+            .iter()
+            .map(|input| {
+                let input_types = input.schema().data_types();
+                if input_types == schema.data_types() {
+                    return Ok(None);
+                }
+                input_types
+                    .into_iter()
+                    .zip_eq(schema.fields())
+                    .enumerate()
+                    .map(|(idx, (input_type, field))| {
+                        let input_ref = Box::new(InputRefExpression::new(input_type.clone(), idx));
+                        if input_type == field.data_type {
+                            Ok(input_ref as BoxedExpression)
+                        } else {
+                            new_unary_expr(ExprType::Cast, field.data_type.clone(), input_ref)
+                        }
+                    })
+                    .collect::<Result<Vec<_>>>()
+                    .map(Some)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            inputs,
+            casts,
+            current: 0,
+            schema,
+            identity,
+        })
+    }
+
+    /// Derives the output schema from the schemas of all inputs, widening column types where
+    /// allowed. Returns an error naming the first mismatched column otherwise.
+    fn derive_schema(inputs: &[BoxedExecutor]) -> Result<Schema> {
+        let first = inputs
+            .first()
+            .ok_or_else(|| InternalError("Union must have at least one child".to_string()))?;
+        let mut fields = first.schema().fields().to_vec();
+
+        for (input_idx, input) in inputs.iter().enumerate().skip(1) {
+            let input_fields = input.schema().fields();
+            if input_fields.len() != fields.len() {
+                return Err(InternalError(format!(
+                    "Union child {} has {} columns, but child 0 has {}",
+                    input_idx,
+                    input_fields.len(),
+                    fields.len()
+                ))
+                .into());
+            }
+            for (col_idx, (field, input_field)) in
+                fields.iter_mut().zip_eq(input_fields).enumerate()
+            {
+                field.data_type =
+                    widen_type(&field.data_type, &input_field.data_type).ok_or_else(|| {
+                        InternalError(format!(
+                            "Union children have incompatible types at column {}: {:?} and {:?}",
+                            col_idx, field.data_type, input_field.data_type
+                        ))
+                    })?;
+            }
+        }
+
+        Ok(Schema { fields })
+    }
+}
+
+/// Returns the type both `lhs` and `rhs` can be implicitly cast to, or `None` if there isn't one.
+fn widen_type(lhs: &DataType, rhs: &DataType) -> Option<DataType> {
+    /// Numeric types in the order they can be implicitly widened.
+    fn numeric_rank(data_type: &DataType) -> Option<u8> {
+        match data_type {
+            DataType::Int16 => Some(0),
+            DataType::Int32 => Some(1),
+            DataType::Int64 => Some(2),
+            DataType::Decimal => Some(3),
+            DataType::Float32 => Some(4),
+            DataType::Float64 => Some(5),
+            _ => None,
+        }
+    }
+
+    if lhs == rhs {
+        return Some(lhs.clone());
+    }
+    match (lhs, rhs) {
+        (DataType::Date, DataType::Timestamp) | (DataType::Timestamp, DataType::Date) => {
+            Some(DataType::Timestamp)
+        }
+        _ => {
+            let (lhs_rank, rhs_rank) = (numeric_rank(lhs)?, numeric_rank(rhs)?);
+            if lhs_rank > rhs_rank {
+                Some(lhs.clone())
+            } else {
+                Some(rhs.clone())
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Executor for UnionAllExecutor {
+    async fn open(&mut self) -> Result<()> {
+        for input in &mut self.inputs {
+            input.open().await?;
+        }
+        Ok(())
+    }
+
+    async fn next(&mut self) -> Result<Option<DataChunk>> {
+        while self.current < self.inputs.len() {
+            let chunk = match self.inputs[self.current].next().await? {
+                Some(chunk) => chunk,
+                None => {
+                    self.current += 1;
+                    continue;
+                }
+            };
+
+            return match &self.casts[self.current] {
+                None => Ok(Some(chunk)),
+                Some(casts) => {
+                    let chunk = chunk.compact()?;
+                    let columns = casts
+                        .iter()
+                        .map(|expr| expr.eval(&chunk).map(Column::new))
+                        .collect::<Result<Vec<_>>>()?;
+                    Ok(Some(DataChunk::builder().columns(columns).build()))
+                }
+            };
+        }
+        Ok(None)
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        for input in &mut self.inputs {
+            input.close().await?;
+        }
+        Ok(())
+    }
+
+    fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    fn identity(&self) -> &str {
+        &self.identity
+    }
+}
+
+impl BoxedExecutorBuilder for UnionAllExecutor {
+    fn new_boxed_executor(source: &ExecutorBuilder) -> Result<BoxedExecutor> {
+        let _union_node =
+            try_match_expand!(source.plan_node().get_node_body().unwrap(), NodeBody::Union)?;
+
+        let inputs = source
+            .plan_node()
+            .get_children()
+            .iter()
+            .map(|child| source.clone_for_plan(child).build())
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Box::new(
+            Self::new(inputs, source.plan_node().get_identity().clone())?.fuse(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use risingwave_common::array::{Array, I32Array, I64Array};
+    use risingwave_common::column_nonnull;
+
+    use super::*;
+    use crate::executor::test_utils::MockExecutor;
+
+    #[tokio::test]
+    async fn test_union_all_widen_type() {
+        let mut narrow = MockExecutor::new(schema_unnamed! { DataType::Int32 });
+        narrow.add(
+            DataChunk::builder()
+                .columns(vec![column_nonnull! { I32Array, [1, 2] }])
+                .build(),
+        );
+        let mut wide = MockExecutor::new(schema_unnamed! { DataType::Int64 });
+        wide.add(
+            DataChunk::builder()
+                .columns(vec![column_nonnull! { I64Array, [3] }])
+                .build(),
+        );
+
+        let mut union_executor = UnionAllExecutor::new(
+            vec![Box::new(narrow), Box::new(wide)],
+            "UnionAllExecutor".to_string(),
+        )
+        .unwrap();
+        assert_eq!(union_executor.schema().data_types(), vec![DataType::Int64]);
+        assert!(union_executor.casts[0].is_some());
+        assert!(union_executor.casts[1].is_none());
+
+        union_executor.open().await.unwrap();
+        let mut result = vec![];
+        while let Some(chunk) = union_executor.next().await.unwrap() {
+            result.extend(chunk.column_at(0).array().as_int64().iter());
+        }
+        union_executor.close().await.unwrap();
+        assert_eq!(result, vec![Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn test_union_all_incompatible_type() {
+        let lhs = MockExecutor::new(schema_unnamed! { DataType::Int32, DataType::Int32 });
+        let rhs = MockExecutor::new(schema_unnamed! { DataType::Int32, DataType::Varchar });
+
+        let err = UnionAllExecutor::new(
+            vec![Box::new(lhs), Box::new(rhs)],
+            "UnionAllExecutor".to_string(),
+        )
+        .err()
+        .unwrap();
+        let message = err.to_string();
+        assert!(message.contains("column 1"), "{}", message);
+        assert!(
+            message.contains("Int32") && message.contains("Varchar"),
+            "{}",
+            message
+        );
+    }
+}
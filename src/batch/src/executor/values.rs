@@ -15,18 +15,23 @@
 use std::sync::Arc;
 use std::vec;
 
-use itertools::Itertools;
 use risingwave_common::array::column::Column;
 use risingwave_common::array::{DataChunk, I32Array};
 use risingwave_common::catalog::{Field, Schema};
-use risingwave_common::error::Result;
+use risingwave_common::error::ErrorCode::InternalError;
+use risingwave_common::error::{Result, RwError};
 use risingwave_common::util::chunk_coalesce::DEFAULT_CHUNK_BUFFER_SIZE;
+use risingwave_expr::expr::expr_unary::new_unary_expr;
 use risingwave_expr::expr::{build_from_prost, BoxedExpression};
+use risingwave_pb::expr::expr_node::Type as ExprType;
 use risingwave_pb::plan::plan_node::NodeBody;
 
 use crate::executor::{BoxedExecutor, BoxedExecutorBuilder, Executor, ExecutorBuilder};
 
 /// `ValuesExecutor` implements Values executor.
+///
+/// Cells of a type different from the column in `schema` are cast to the type of the column, e.g.
+/// `VALUES (1), (2::bigint)`.
 pub(super) struct ValuesExecutor {
     rows: vec::IntoIter<Vec<BoxedExpression>>,
     schema: Schema,
@@ -40,13 +45,36 @@ impl ValuesExecutor {
         schema: Schema,
         identity: String,
         chunk_size: usize,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                if row.len() != schema.len() {
+                    return Err(RwError::from(InternalError(format!(
+                        "values row has {} columns, but the schema has {}",
+                        row.len(),
+                        schema.len()
+                    ))));
+                }
+                row.into_iter()
+                    .zip(schema.fields())
+                    .map(|(expr, field)| {
+                        if expr.return_type() == field.data_type {
+                            Ok(expr)
+                        } else {
+                            new_unary_expr(ExprType::Cast, field.data_type.clone(), expr)
+                        }
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
             rows: rows.into_iter(),
             schema,
             identity,
             chunk_size,
-        }
+        })
     }
 }
 
@@ -81,7 +109,8 @@ impl Executor for ValuesExecutor {
         let chunk_size = self.chunk_size.min(self.rows.len());
         let mut array_builders = self.schema.create_array_builders(chunk_size)?;
         for row in self.rows.by_ref().take(chunk_size) {
-            for (expr, builder) in row.into_iter().zip_eq(&mut array_builders) {
+            // Rows are checked against the schema on creation.
+            for (expr, builder) in row.into_iter().zip(&mut array_builders) {
                 let out = expr.eval(&one_row_chunk)?;
                 builder.append_array(&out)?;
             }
@@ -139,7 +168,7 @@ impl BoxedExecutorBuilder for ValuesExecutor {
                 Schema { fields },
                 source.plan_node().get_identity().clone(),
                 DEFAULT_CHUNK_BUFFER_SIZE,
-            )
+            )?
             .fuse(),
         ))
     }
@@ -180,7 +209,8 @@ mod tests {
             Schema { fields },
             "ValuesExecutor".to_string(),
             1024,
-        );
+        )
+        .unwrap();
         values_executor.open().await.unwrap();
 
         let fields = &values_executor.schema().fields;
@@ -205,6 +235,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_values_executor_arity_mismatch() {
+        let row = || -> Vec<BoxedExpression> {
+            vec![Box::new(LiteralExpression::new(
+                DataType::Int32,
+                Some(ScalarImpl::Int32(1)),
+            ))]
+        };
+        let schema = Schema {
+            fields: vec![
+                Field::unnamed(DataType::Int32),
+                Field::unnamed(DataType::Int32),
+            ],
+        };
+        assert!(ValuesExecutor::new(
+            vec![row(), row()],
+            schema,
+            "ValuesExecutor".to_string(),
+            1024
+        )
+        .is_err());
+    }
+
     #[tokio::test]
     async fn test_chunk_split_size() {
         let rows = [
@@ -231,7 +284,7 @@ mod tests {
 
         let fields = vec![Field::unnamed(DataType::Int32)];
         let mut values_executor =
-            ValuesExecutor::new(rows, Schema { fields }, "ValuesExecutor".to_string(), 3);
+            ValuesExecutor::new(rows, Schema { fields }, "ValuesExecutor".to_string(), 3).unwrap();
 
         assert_eq!(
             values_executor.next().await.unwrap().unwrap().cardinality(),
@@ -252,7 +305,8 @@ mod tests {
             Schema::default(),
             "ValuesExecutor".to_string(),
             1024,
-        );
+        )
+        .unwrap();
         values_executor.open().await.unwrap();
 
         let result = values_executor.next().await.unwrap().unwrap();
@@ -261,4 +315,63 @@ mod tests {
 
         assert!(values_executor.next().await.unwrap().is_none());
     }
+
+    #[tokio::test]
+    async fn test_empty_values_executor() {
+        let mut values_executor = ValuesExecutor::new(
+            vec![],
+            Schema {
+                fields: vec![Field::unnamed(DataType::Int32)],
+            },
+            "ValuesExecutor".to_string(),
+            1024,
+        )
+        .unwrap();
+        values_executor.open().await.unwrap();
+        assert!(values_executor.next().await.unwrap().is_none());
+        values_executor.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_multi_row_values_executor_with_coercion() {
+        let literal = |data_type: DataType, value: Option<ScalarImpl>| {
+            Box::new(LiteralExpression::new(data_type, value)) as BoxedExpression
+        };
+        // VALUES (1::smallint, 2), (3, NULL), (4::bigint, 5)
+        let rows = vec![
+            vec![
+                literal(DataType::Int16, Some(ScalarImpl::Int16(1))),
+                literal(DataType::Int32, Some(ScalarImpl::Int32(2))),
+            ],
+            vec![
+                literal(DataType::Int32, Some(ScalarImpl::Int32(3))),
+                literal(DataType::Int32, None),
+            ],
+            vec![
+                literal(DataType::Int64, Some(ScalarImpl::Int64(4))),
+                literal(DataType::Int32, Some(ScalarImpl::Int32(5))),
+            ],
+        ];
+        let schema = Schema {
+            fields: vec![
+                Field::unnamed(DataType::Int64),
+                Field::unnamed(DataType::Int32),
+            ],
+        };
+        let mut values_executor =
+            ValuesExecutor::new(rows, schema, "ValuesExecutor".to_string(), 1024).unwrap();
+        values_executor.open().await.unwrap();
+
+        let result = values_executor.next().await.unwrap().unwrap();
+        assert_eq!(
+            *result.column_at(0).array(),
+            array! {I64Array, [Some(1), Some(3), Some(4)]}.into()
+        );
+        assert_eq!(
+            *result.column_at(1).array(),
+            array! {I32Array, [Some(2), None, Some(5)]}.into()
+        );
+        assert!(values_executor.next().await.unwrap().is_none());
+        values_executor.close().await.unwrap();
+    }
 }
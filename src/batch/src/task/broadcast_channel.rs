@@ -16,16 +16,16 @@ use std::future::Future;
 
 use risingwave_common::array::DataChunk;
 use risingwave_common::error::ErrorCode::InternalError;
-use risingwave_common::error::{Result, ToRwResult};
+use risingwave_common::error::Result;
 use risingwave_pb::plan::exchange_info::BroadcastInfo;
 use risingwave_pb::plan::*;
-use tokio::sync::mpsc;
 
 use crate::task::channel::{ChanReceiver, ChanReceiverImpl, ChanSender, ChanSenderImpl};
+use crate::task::chunk_queue::{new_chunk_queue, ChunkReceiver, ChunkSender};
 
 /// `BroadcastSender` sends the same chunk to a number of `BroadcastReceiver`s.
 pub struct BroadcastSender {
-    senders: Vec<mpsc::UnboundedSender<Option<DataChunk>>>,
+    senders: Vec<ChunkSender>,
     broadcast_info: BroadcastInfo,
 }
 
@@ -34,18 +34,17 @@ impl ChanSender for BroadcastSender {
 
     fn send(&mut self, chunk: Option<DataChunk>) -> Self::SendFuture<'_> {
         async move {
-            self.senders.iter().try_for_each(|sender| {
-                sender
-                    .send(chunk.clone())
-                    .to_rw_result_with(|| "BroadcastSender::send".into())
-            })
+            for sender in &self.senders {
+                sender.send(chunk.clone()).await?;
+            }
+            Ok(())
         }
     }
 }
 
 /// One or more `BroadcastReceiver`s corresponds to a single `BroadcastReceiver`
 pub struct BroadcastReceiver {
-    receiver: mpsc::UnboundedReceiver<Option<DataChunk>>,
+    receiver: ChunkReceiver,
 }
 
 impl ChanReceiver for BroadcastReceiver {
@@ -62,7 +61,10 @@ impl ChanReceiver for BroadcastReceiver {
     }
 }
 
-pub fn new_broadcast_channel(shuffle: &ExchangeInfo) -> (ChanSenderImpl, Vec<ChanReceiverImpl>) {
+pub fn new_broadcast_channel(
+    shuffle: &ExchangeInfo,
+    max_bytes: Option<usize>,
+) -> (ChanSenderImpl, Vec<ChanReceiverImpl>) {
     let broadcast_info = match shuffle.distribution {
         Some(exchange_info::Distribution::BroadcastInfo(ref v)) => v.clone(),
         _ => exchange_info::BroadcastInfo::default(),
@@ -72,7 +74,7 @@ pub fn new_broadcast_channel(shuffle: &ExchangeInfo) -> (ChanSenderImpl, Vec<Cha
     let mut senders = Vec::with_capacity(output_count);
     let mut receivers = Vec::with_capacity(output_count);
     for _ in 0..output_count {
-        let (s, r) = mpsc::unbounded_channel();
+        let (s, r) = new_chunk_queue(max_bytes);
         senders.push(s);
         receivers.push(r);
     }
@@ -78,14 +78,65 @@ impl ChanReceiverImpl {
 
 /// Output-channel is a synchronous, bounded single-producer-multiple-consumer queue.
 /// The producer is the local task executor, the consumer is
-/// [`ExchangeService`](risingwave_pb::task_service::exchange_service_server::ExchangeService).
-/// The implementation depends on the shuffling strategy.
+/// [`ExchangeService`](risingwave_pb::task_service::exchange_service_server::ExchangeService)
+/// or a local exchange source.
+/// The implementation depends on the shuffling strategy. If `max_bytes` is set, each receiver
+/// holds at most `max_bytes` bytes of chunks, and the producer is blocked while any receiver it
+/// sends to is full.
 pub fn create_output_channel(
     shuffle: &ExchangeInfo,
+    max_bytes: Option<usize>,
 ) -> Result<(ChanSenderImpl, Vec<ChanReceiverImpl>)> {
     match shuffle.get_mode()? {
-        ShuffleDistributionMode::Single => Ok(new_fifo_channel()),
-        ShuffleDistributionMode::Hash => Ok(new_hash_shuffle_channel(shuffle)),
-        ShuffleDistributionMode::Broadcast => Ok(new_broadcast_channel(shuffle)),
+        ShuffleDistributionMode::Single => Ok(new_fifo_channel(max_bytes)),
+        ShuffleDistributionMode::Hash => Ok(new_hash_shuffle_channel(shuffle, max_bytes)),
+        ShuffleDistributionMode::Broadcast => Ok(new_broadcast_channel(shuffle, max_bytes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use risingwave_common::array::I64Array;
+    use risingwave_common::column_nonnull;
+    use risingwave_pb::plan::exchange_info::{BroadcastInfo, Distribution};
+
+    use super::*;
+    use crate::task::chunk_queue::estimated_size;
+
+    #[tokio::test]
+    async fn test_bounded_output_blocks_producer() {
+        let chunk = DataChunk::builder()
+            .columns(vec![column_nonnull! { I64Array, [1, 2, 3, 4] }])
+            .build();
+        let shuffle = ExchangeInfo {
+            mode: ShuffleDistributionMode::Broadcast as i32,
+            distribution: Some(Distribution::BroadcastInfo(BroadcastInfo { count: 2 })),
+        };
+        let (mut sender, mut receivers) =
+            create_output_channel(&shuffle, Some(estimated_size(&chunk))).unwrap();
+        sender.send(Some(chunk.clone())).await.unwrap();
+
+        // Only the first receiver takes its chunk, and the producer waits for the other one.
+        receivers[0].recv().await.unwrap().unwrap();
+        let mut join_handle = tokio::spawn(async move {
+            sender.send(Some(chunk)).await.unwrap();
+            sender.send(None).await.unwrap();
+        });
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), &mut join_handle)
+                .await
+                .is_err()
+        );
+
+        receivers[1].recv().await.unwrap().unwrap();
+        for receiver in &mut receivers {
+            assert!(receiver.recv().await.unwrap().is_some());
+        }
+        join_handle.await.unwrap();
+        for receiver in &mut receivers {
+            assert!(receiver.recv().await.unwrap().is_none());
+        }
     }
 }
@@ -0,0 +1,194 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use risingwave_common::array::DataChunk;
+use risingwave_common::error::ErrorCode::InternalError;
+use risingwave_common::error::{Result, RwError};
+use risingwave_common::types::Datum;
+use tokio::sync::{mpsc, Semaphore};
+
+type QueueItem = (Option<DataChunk>, usize);
+
+/// Sending half of a queue created by [`new_chunk_queue`].
+pub(super) struct ChunkSender {
+    sender: mpsc::UnboundedSender<QueueItem>,
+    /// Bytes that can still be queued, if the queue is bounded.
+    permits: Option<Arc<Semaphore>>,
+    max_bytes: usize,
+}
+
+/// Receiving half of a queue created by [`new_chunk_queue`].
+pub(super) struct ChunkReceiver {
+    receiver: mpsc::UnboundedReceiver<QueueItem>,
+    permits: Option<Arc<Semaphore>>,
+}
+
+/// Creates a queue of chunks from a task to a consumer of its output. If `max_bytes` is set, the
+/// queue holds at most `max_bytes` bytes of chunks as measured by [`estimated_size`], and sending
+/// blocks the task until the consumer makes room. A chunk larger than `max_bytes` is accepted only
+/// when the queue is empty.
+pub(super) fn new_chunk_queue(max_bytes: Option<usize>) -> (ChunkSender, ChunkReceiver) {
+    // A single acquisition of the semaphore can take at most `u32::MAX` permits.
+    let max_bytes = max_bytes.map(|max_bytes| max_bytes.clamp(1, u32::MAX as usize));
+    let permits = max_bytes.map(|max_bytes| Arc::new(Semaphore::new(max_bytes)));
+    let (sender, receiver) = mpsc::unbounded_channel();
+    (
+        ChunkSender {
+            sender,
+            permits: permits.clone(),
+            max_bytes: max_bytes.unwrap_or(usize::MAX),
+        },
+        ChunkReceiver { receiver, permits },
+    )
+}
+
+/// Estimates the size of `chunk` as if each value took as much as a [`Datum`]. It's far cheaper
+/// than encoding the chunk, and precise enough to bound the queue.
+pub(super) fn estimated_size(chunk: &DataChunk) -> usize {
+    chunk.capacity() * chunk.dimension() * std::mem::size_of::<Datum>()
+}
+
+fn queue_closed() -> RwError {
+    InternalError("chunk queue closed".to_string()).into()
+}
+
+impl ChunkSender {
+    /// Waits until there's enough room for `chunk`, then queues it. `None` is sent as a mark of the
+    /// ending of the output. Fails if the receiver is dropped.
+    pub(super) async fn send(&self, chunk: Option<DataChunk>) -> Result<()> {
+        let bytes = match (&self.permits, &chunk) {
+            (Some(permits), Some(chunk)) => {
+                let bytes = estimated_size(chunk).min(self.max_bytes);
+                if bytes > 0 {
+                    permits
+                        .acquire_many(bytes as u32)
+                        .await
+                        .map_err(|_| queue_closed())?
+                        .forget();
+                }
+                bytes
+            }
+            _ => 0,
+        };
+        self.sender.send((chunk, bytes)).map_err(|_| queue_closed())
+    }
+}
+
+impl ChunkReceiver {
+    /// Takes the next item in the queue and releases its room. Returns `None` if the sender is
+    /// dropped and the queue is drained.
+    pub(super) async fn recv(&mut self) -> Option<Option<DataChunk>> {
+        let (chunk, bytes) = self.receiver.recv().await?;
+        if let Some(permits) = &self.permits {
+            permits.add_permits(bytes);
+        }
+        Some(chunk)
+    }
+}
+
+impl Drop for ChunkReceiver {
+    fn drop(&mut self) {
+        // Wakes up the sender waiting for room.
+        if let Some(permits) = &self.permits {
+            permits.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use risingwave_common::array::{Array, I64Array};
+    use risingwave_common::column_nonnull;
+
+    use super::*;
+
+    fn build_chunk(i: i64) -> DataChunk {
+        DataChunk::builder()
+            .columns(vec![column_nonnull! { I64Array, [i, i, i, i, i, i, i, i] }])
+            .build()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bounded_queue() {
+        const CHUNK_NUM: i64 = 20;
+        // Room for 2 chunks only.
+        let chunk_bytes = estimated_size(&build_chunk(0));
+        let (sender, mut receiver) = new_chunk_queue(Some(chunk_bytes * 2));
+
+        let sent = Arc::new(AtomicUsize::new(0));
+        let sent_clone = sent.clone();
+        let mut join_handle = tokio::spawn(async move {
+            for i in 0..CHUNK_NUM {
+                sender.send(Some(build_chunk(i))).await.unwrap();
+                sent_clone.fetch_add(1, Ordering::SeqCst);
+            }
+            sender.send(None).await.unwrap();
+        });
+
+        // The sender is blocked once the queue is full.
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), &mut join_handle)
+                .await
+                .is_err()
+        );
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+
+        for i in 0..CHUNK_NUM {
+            // Let the sender fill the queue.
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            let chunk = receiver.recv().await.unwrap().unwrap();
+            let column = chunk.column_at(0).array();
+            assert_eq!(column.as_int64().value_at(0), Some(i));
+            assert!(sent.load(Ordering::SeqCst) <= i as usize + 1 + 2);
+        }
+        // Queued chunks are drained before the end.
+        assert!(receiver.recv().await.unwrap().is_none());
+        join_handle.await.unwrap();
+        assert_eq!(sent.load(Ordering::SeqCst), CHUNK_NUM as usize);
+    }
+
+    #[tokio::test]
+    async fn test_unbounded_queue() {
+        let (sender, mut receiver) = new_chunk_queue(None);
+        for i in 0..100 {
+            sender.send(Some(build_chunk(i))).await.unwrap();
+        }
+        sender.send(None).await.unwrap();
+        drop(sender);
+
+        for _ in 0..100 {
+            assert!(receiver.recv().await.unwrap().is_some());
+        }
+        assert!(receiver.recv().await.unwrap().is_none());
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_receiver_dropped() {
+        let chunk_bytes = estimated_size(&build_chunk(0));
+        let (sender, receiver) = new_chunk_queue(Some(chunk_bytes));
+        sender.send(Some(build_chunk(0))).await.unwrap();
+
+        // The sender waiting for room fails instead of hanging.
+        let join_handle = tokio::spawn(async move { sender.send(Some(build_chunk(1))).await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(receiver);
+        assert!(join_handle.await.unwrap().is_err());
+    }
+}
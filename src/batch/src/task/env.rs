@@ -13,9 +13,11 @@
 // limitations under the License.
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use risingwave_common::config::BatchConfig;
 use risingwave_common::util::addr::HostAddr;
+use risingwave_rpc_client::{ComputeClientPool, ExchangeRetryConfig};
 use risingwave_source::{SourceManager, SourceManagerRef};
 use risingwave_storage::StateStoreImpl;
 
@@ -24,6 +26,8 @@ use crate::task::BatchManager;
 
 pub(crate) type WorkerNodeId = u32;
 
+const COMPUTE_CLIENT_POOL_CAPACITY: u64 = 1024;
+
 /// The global environment for task execution.
 /// The instance will be shared by every task.
 #[derive(Clone)]
@@ -48,6 +52,9 @@ pub struct BatchEnvironment {
 
     /// Statistics.
     stats: Arc<BatchMetrics>,
+
+    /// Clients to other compute nodes, shared by the exchange sources of all tasks.
+    compute_client_pool: ComputeClientPool,
 }
 
 impl BatchEnvironment {
@@ -60,6 +67,7 @@ impl BatchEnvironment {
         state_store: StateStoreImpl,
         stats: Arc<BatchMetrics>,
     ) -> Self {
+        let exchange_config = exchange_config(&config);
         BatchEnvironment {
             server_addr,
             task_manager,
@@ -68,6 +76,11 @@ impl BatchEnvironment {
             worker_id,
             state_store,
             stats,
+            compute_client_pool: ComputeClientPool::with_timeout(
+                COMPUTE_CLIENT_POOL_CAPACITY,
+                exchange_config.connect_timeout,
+                exchange_config.request_timeout,
+            ),
         }
     }
 
@@ -87,6 +100,7 @@ impl BatchEnvironment {
                 StateStoreMetrics::unused(),
             )),
             stats: Arc::new(BatchMetrics::unused()),
+            compute_client_pool: ComputeClientPool::new(COMPUTE_CLIENT_POOL_CAPACITY),
         }
     }
 
@@ -121,4 +135,23 @@ impl BatchEnvironment {
     pub fn stats(&self) -> Arc<BatchMetrics> {
         self.stats.clone()
     }
+
+    pub fn compute_client_pool(&self) -> &ComputeClientPool {
+        &self.compute_client_pool
+    }
+
+    /// Retry and timeout policy of exchanging data from other compute nodes.
+    pub fn exchange_config(&self) -> ExchangeRetryConfig {
+        exchange_config(&self.config)
+    }
+}
+
+fn exchange_config(config: &BatchConfig) -> ExchangeRetryConfig {
+    ExchangeRetryConfig {
+        connect_timeout: Duration::from_millis(config.exchange_connect_timeout_ms),
+        request_timeout: config
+            .exchange_request_timeout_ms
+            .map(Duration::from_millis),
+        ..Default::default()
+    }
 }
@@ -16,28 +16,24 @@ use std::future::Future;
 
 use risingwave_common::array::DataChunk;
 use risingwave_common::error::ErrorCode::InternalError;
-use risingwave_common::error::{Result, ToRwResult};
-use tokio::sync::mpsc;
+use risingwave_common::error::Result;
 
 use crate::task::channel::{ChanReceiver, ChanReceiverImpl, ChanSender, ChanSenderImpl};
+use crate::task::chunk_queue::{new_chunk_queue, ChunkReceiver, ChunkSender};
 
 pub struct FifoSender {
-    sender: mpsc::UnboundedSender<Option<DataChunk>>,
+    sender: ChunkSender,
 }
 
 pub struct FifoReceiver {
-    receiver: mpsc::UnboundedReceiver<Option<DataChunk>>,
+    receiver: ChunkReceiver,
 }
 
 impl ChanSender for FifoSender {
     type SendFuture<'a> = impl Future<Output = Result<()>>;
 
     fn send(&mut self, chunk: Option<DataChunk>) -> Self::SendFuture<'_> {
-        async move {
-            self.sender
-                .send(chunk)
-                .to_rw_result_with(|| "FifoSender::send".into())
-        }
+        async move { self.sender.send(chunk).await }
     }
 }
 
@@ -55,8 +51,8 @@ impl ChanReceiver for FifoReceiver {
     }
 }
 
-pub fn new_fifo_channel() -> (ChanSenderImpl, Vec<ChanReceiverImpl>) {
-    let (s, r) = mpsc::unbounded_channel();
+pub fn new_fifo_channel(max_bytes: Option<usize>) -> (ChanSenderImpl, Vec<ChanReceiverImpl>) {
+    let (s, r) = new_chunk_queue(max_bytes);
     (
         ChanSenderImpl::Fifo(FifoSender { sender: s }),
         vec![ChanReceiverImpl::Fifo(FifoReceiver { receiver: r })],
@@ -68,7 +64,7 @@ mod tests {
     async fn test_recv_not_fail_on_closed_channel() {
         use crate::task::fifo_channel::new_fifo_channel;
 
-        let (sender, mut receivers) = new_fifo_channel();
+        let (sender, mut receivers) = new_fifo_channel(None);
         assert_eq!(receivers.len(), 1);
         drop(sender);
 
@@ -17,21 +17,21 @@ use std::option::Option;
 
 use risingwave_common::array::DataChunk;
 use risingwave_common::error::ErrorCode::InternalError;
-use risingwave_common::error::{Result, ToRwResult};
+use risingwave_common::error::Result;
 use risingwave_common::util::hash_util::CRC32FastBuilder;
 use risingwave_pb::plan::exchange_info::HashInfo;
 use risingwave_pb::plan::*;
-use tokio::sync::mpsc;
 
 use crate::task::channel::{ChanReceiver, ChanReceiverImpl, ChanSender, ChanSenderImpl};
+use crate::task::chunk_queue::{new_chunk_queue, ChunkReceiver, ChunkSender};
 
 pub struct HashShuffleSender {
-    senders: Vec<mpsc::UnboundedSender<Option<DataChunk>>>,
+    senders: Vec<ChunkSender>,
     hash_info: exchange_info::HashInfo,
 }
 
 pub struct HashShuffleReceiver {
-    receiver: mpsc::UnboundedReceiver<Option<DataChunk>>,
+    receiver: ChunkReceiver,
 }
 
 fn generate_hash_values(chunk: &DataChunk, hash_info: &HashInfo) -> Result<Vec<usize>> {
@@ -113,19 +113,17 @@ impl HashShuffleSender {
             // The reason we need to add this filter only in HashShuffleSender is that
             // `generate_new_data_chunks` may generate an empty chunk.
             if new_data_chunk.cardinality() > 0 {
-                self.senders[sink_id]
-                    .send(Some(new_data_chunk))
-                    .to_rw_result_with(|| "HashShuffleSender::send".into())?;
+                self.senders[sink_id].send(Some(new_data_chunk)).await?;
             }
         }
         Ok(())
     }
 
     async fn send_done(&mut self) -> Result<()> {
-        self.senders.iter_mut().try_for_each(|s| {
-            s.send(None)
-                .to_rw_result_with(|| "HashShuffleSender::send".into())
-        })
+        for sender in &self.senders {
+            sender.send(None).await?;
+        }
+        Ok(())
     }
 }
 
@@ -143,7 +141,10 @@ impl ChanReceiver for HashShuffleReceiver {
     }
 }
 
-pub fn new_hash_shuffle_channel(shuffle: &ExchangeInfo) -> (ChanSenderImpl, Vec<ChanReceiverImpl>) {
+pub fn new_hash_shuffle_channel(
+    shuffle: &ExchangeInfo,
+    max_bytes: Option<usize>,
+) -> (ChanSenderImpl, Vec<ChanReceiverImpl>) {
     let hash_info = match shuffle.distribution {
         Some(exchange_info::Distribution::HashInfo(ref v)) => v.clone(),
         _ => exchange_info::HashInfo::default(),
@@ -153,7 +154,7 @@ pub fn new_hash_shuffle_channel(shuffle: &ExchangeInfo) -> (ChanSenderImpl, Vec<
     let mut senders = Vec::with_capacity(output_count);
     let mut receivers = Vec::with_capacity(output_count);
     for _ in 0..output_count {
-        let (s, r) = mpsc::unbounded_channel();
+        let (s, r) = new_chunk_queue(max_bytes);
         senders.push(s);
         receivers.push(r);
     }
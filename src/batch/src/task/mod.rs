@@ -18,6 +18,7 @@ pub use task_manager::*;
 
 mod broadcast_channel;
 mod channel;
+mod chunk_queue;
 mod env;
 mod fifo_channel;
 mod hash_shuffle_channel;
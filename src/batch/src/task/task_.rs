@@ -225,7 +225,10 @@ impl BatchTaskExecution {
         )
         .build()?;
 
-        let (sender, receivers) = create_output_channel(self.plan.get_exchange_info()?)?;
+        let (sender, receivers) = create_output_channel(
+            self.plan.get_exchange_info()?,
+            self.env.config().task_output_buffer_bytes,
+        )?;
         self.receivers
             .lock()
             .extend(receivers.into_iter().map(Some));
@@ -64,6 +64,24 @@ impl Default for ServerConfig {
 pub struct BatchConfig {
     #[serde(default = "default::chunk_size")]
     pub chunk_size: u32,
+
+    /// Whether to collect the time spent and the rows emitted by each executor
+    #[serde(default = "default::collect_executor_metrics")]
+    pub collect_executor_metrics: bool,
+
+    /// Timeout of connecting to another compute node for exchange, in milliseconds
+    #[serde(default = "default::exchange_connect_timeout_ms")]
+    pub exchange_connect_timeout_ms: u64,
+
+    /// Deadline of requesting data from another compute node for exchange, in milliseconds. No
+    /// deadline if not set.
+    #[serde(default)]
+    pub exchange_request_timeout_ms: Option<u64>,
+
+    /// Max bytes of chunks buffered in each output of a batch task. The task is blocked once an
+    /// output is full until its consumer takes chunks out. Unbounded if not set.
+    #[serde(default)]
+    pub task_output_buffer_bytes: Option<usize>,
 }
 
 impl Default for BatchConfig {
@@ -169,6 +187,14 @@ mod default {
         1024
     }
 
+    pub fn collect_executor_metrics() -> bool {
+        false
+    }
+
+    pub fn exchange_connect_timeout_ms() -> u64 {
+        5000
+    }
+
     pub fn sst_size() -> u32 {
         // 256MB
         268435456
@@ -18,7 +18,7 @@ use itertools::Itertools;
 
 use crate::array::{ArrayImpl, DataChunk};
 use crate::error::Result;
-use crate::types::{serialize_datum_ref_into, DataType};
+use crate::types::{serialize_datum_ref_not_null_into, DataType};
 use crate::util::sort_util::{OrderPair, OrderType};
 
 struct EncodedColumn(pub Vec<Vec<u8>>);
@@ -38,13 +38,22 @@ pub fn is_type_encodable(t: DataType) -> bool {
     )
 }
 
-fn encode_array(array: &ArrayImpl, order: &OrderType) -> Result<EncodedColumn> {
+fn encode_array(array: &ArrayImpl, order_pair: &OrderPair) -> Result<EncodedColumn> {
     let mut data = Vec::with_capacity(array.len());
 
     for datum in array.iter() {
-        let mut serializer = memcomparable::Serializer::new(vec![]);
-        serializer.set_reverse(order == &OrderType::Descending);
-        serialize_datum_ref_into(&datum, &mut serializer)?;
+        // The null tag is not affected by `set_reverse`, so that the placement of nulls only
+        // depends on `nulls_first`.
+        let null_tag = match (&datum, order_pair.nulls_first) {
+            (Some(_), _) => 1u8,
+            (None, true) => 0u8,
+            (None, false) => 2u8,
+        };
+        let mut serializer = memcomparable::Serializer::new(vec![null_tag]);
+        serializer.set_reverse(order_pair.order_type == OrderType::Descending);
+        if datum.is_some() {
+            serialize_datum_ref_not_null_into(&datum, &mut serializer)?;
+        }
         data.push(serializer.into_inner());
     }
 
@@ -54,12 +63,10 @@ fn encode_array(array: &ArrayImpl, order: &OrderType) -> Result<EncodedColumn> {
 /// This function is used to accelerate the comparison of tuples. It takes datachunk and
 /// user-defined order as input, yield encoded binary string with order preserved for each tuple in
 /// the datachunk.
-///
-/// TODO: specify the order for `NULL`.
 pub fn encode_chunk(chunk: &DataChunk, order_pairs: Arc<Vec<OrderPair>>) -> Arc<Vec<Vec<u8>>> {
     let encoded_columns = order_pairs
         .iter()
-        .map(|o| encode_array(chunk.column_at(o.column_idx).array_ref(), &o.order_type).unwrap())
+        .map(|o| encode_array(chunk.column_at(o.column_idx).array_ref(), o).unwrap())
         .collect_vec();
 
     let mut encoded_chunk = vec![vec![]; chunk.capacity()];
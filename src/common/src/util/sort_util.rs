@@ -46,19 +46,29 @@ impl OrderType {
             OrderType::Descending => ProstOrderType::Descending,
         }
     }
+
+    /// Returns whether nulls come first by default, which follows PostgreSQL: nulls are larger
+    /// than any non-null value, i.e. last for ascending order and first for descending order.
+    pub fn default_nulls_first(&self) -> bool {
+        *self == OrderType::Descending
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OrderPair {
     pub column_idx: usize,
     pub order_type: OrderType,
+    /// Whether nulls are placed before non-null values, regardless of `order_type`.
+    pub nulls_first: bool,
 }
 
 impl OrderPair {
+    /// Creates an [`OrderPair`] with the default null placement of `order_type`.
     pub fn new(column_idx: usize, order_type: OrderType) -> Self {
         Self {
             column_idx,
             order_type,
+            nulls_first: order_type.default_nulls_first(),
         }
     }
 
@@ -68,6 +78,7 @@ impl OrderPair {
         OrderPair {
             order_type: OrderType::from_prost(&order_type),
             column_idx: input_ref.column_idx as usize,
+            nulls_first: column_order.nulls_first,
         }
     }
 }
@@ -126,16 +137,21 @@ fn compare_value_in_array<'a, T>(
     lhs_idx: usize,
     rhs_array: &'a T,
     rhs_idx: usize,
-    order_type: &'a OrderType,
+    order_pair: &'a OrderPair,
 ) -> Ordering
 where
     T: Array,
     <<T as Array>::RefItem<'a> as ScalarRef<'a>>::ScalarType: ScalarPartialOrd,
 {
-    let (lhs_val, rhs_val) = (
-        lhs_array.value_at(lhs_idx).unwrap(),
-        rhs_array.value_at(rhs_idx).unwrap(),
-    );
+    let order_type = &order_pair.order_type;
+    let (lhs_val, rhs_val) = match (lhs_array.value_at(lhs_idx), rhs_array.value_at(rhs_idx)) {
+        (Some(lhs_val), Some(rhs_val)) => (lhs_val, rhs_val),
+        (None, None) => return Ordering::Equal,
+        (None, Some(_)) if order_pair.nulls_first => return Ordering::Less,
+        (None, Some(_)) => return Ordering::Greater,
+        (Some(_), None) if order_pair.nulls_first => return Ordering::Greater,
+        (Some(_), None) => return Ordering::Less,
+    };
     match lhs_val.to_owned_scalar().scalar_cmp(rhs_val).unwrap() {
         Ordering::Equal => Ordering::Equal,
         Ordering::Less => {
@@ -168,7 +184,7 @@ pub fn compare_two_row(
         macro_rules! gen_match {
         ($lhs: ident, $rhs: ident, [$( $tt: ident), *]) => {
             match ($lhs, $rhs) {
-                $((ArrayImpl::$tt(lhs_inner), ArrayImpl::$tt(rhs_inner)) => Ok(compare_value_in_array(lhs_inner, lhs_idx, rhs_inner, rhs_idx, &order_pair.order_type)),)*
+                $((ArrayImpl::$tt(lhs_inner), ArrayImpl::$tt(rhs_inner)) => Ok(compare_value_in_array(lhs_inner, lhs_idx, rhs_inner, rhs_idx, order_pair)),)*
                 (l_arr, r_arr) => Err(InternalError(format!("Unmatched array types, lhs array is: {}, rhs array is: {}", l_arr.get_ident(), r_arr.get_ident()))),
             }?
         }
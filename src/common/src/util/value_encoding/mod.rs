@@ -122,3 +122,52 @@ fn deserialize_decimal(deserializer: &mut value_encoding::Deserializer<impl Buf>
         scale as u32,
     ))))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{IntervalUnit, NaiveDateTimeWrapper};
+
+    fn round_trip(datum: Datum, ty: &DataType) -> Datum {
+        let bytes = serialize_cell(&datum).unwrap();
+        let mut deserializer = value_encoding::Deserializer::new(bytes.as_slice());
+        deserialize_cell(&mut deserializer, ty).unwrap()
+    }
+
+    #[test]
+    fn test_timestamp_cell() {
+        let timestamps = [-86_400, -1, 0, 1, 1_650_000_000]
+            .into_iter()
+            .map(|secs| {
+                Some(ScalarImpl::NaiveDateTime(
+                    NaiveDateTimeWrapper::new_with_secs_nsecs(secs, 123_456_000).unwrap(),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        for datum in timestamps.iter().cloned().chain([None]) {
+            assert_eq!(round_trip(datum.clone(), &DataType::Timestamp), datum);
+        }
+
+        // Encoded timestamps keep their order.
+        let encoded = timestamps
+            .iter()
+            .map(|datum| serialize_cell(datum).unwrap())
+            .collect::<Vec<_>>();
+        assert!(encoded.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_interval_cell() {
+        for interval in [
+            IntervalUnit::new(1, 2, 3_000),
+            IntervalUnit::new(-14, 0, 0),
+            IntervalUnit::new(0, -3, -86_399_999),
+            IntervalUnit::new(0, 0, 0),
+        ] {
+            let datum = Some(ScalarImpl::Interval(interval));
+            assert_eq!(round_trip(datum.clone(), &DataType::Interval), datum);
+        }
+        assert_eq!(round_trip(None, &DataType::Interval), None);
+    }
+}
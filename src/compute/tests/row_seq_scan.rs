@@ -37,10 +37,17 @@ async fn test_row_seq_scan() -> Result<()> {
         Field::unnamed(DataType::Int32),
         Field::unnamed(DataType::Int64),
     ]);
-    let column_ids = vec![ColumnId::from(0), ColumnId::from(1), ColumnId::from(2)];
+    let state_column_descs = vec![
+        ColumnDesc::unnamed(ColumnId::from(0), schema[0].data_type.clone()),
+        ColumnDesc::unnamed(ColumnId::from(1), schema[1].data_type.clone()),
+        ColumnDesc::unnamed(ColumnId::from(2), schema[2].data_type.clone()),
+    ];
 
-    let mut state =
-        ManagedMViewState::new(keyspace.clone(), column_ids, vec![OrderType::Ascending]);
+    let mut state = ManagedMViewState::new(
+        keyspace.clone(),
+        state_column_descs,
+        vec![OrderType::Ascending],
+    );
 
     let column_descs = vec![
         ColumnDesc::unnamed(ColumnId::from(0), schema[0].data_type.clone()),
@@ -63,22 +70,30 @@ async fn test_row_seq_scan() -> Result<()> {
     );
 
     let epoch: u64 = 0;
-    state.put(
-        Row(vec![Some(1_i32.into())]),
-        Row(vec![
-            Some(1_i32.into()),
-            Some(4_i32.into()),
-            Some(7_i64.into()),
-        ]),
-    );
-    state.put(
-        Row(vec![Some(2_i32.into())]),
-        Row(vec![
-            Some(2_i32.into()),
-            Some(5_i32.into()),
-            Some(8_i64.into()),
-        ]),
-    );
+    state
+        .put(
+            Row(vec![Some(1_i32.into())]),
+            Row(vec![
+                Some(1_i32.into()),
+                Some(4_i32.into()),
+                Some(7_i64.into()),
+            ]),
+            epoch,
+        )
+        .await
+        .unwrap();
+    state
+        .put(
+            Row(vec![Some(2_i32.into())]),
+            Row(vec![
+                Some(2_i32.into()),
+                Some(5_i32.into()),
+                Some(8_i64.into()),
+            ]),
+            epoch,
+        )
+        .await
+        .unwrap();
     state.flush(epoch).await.unwrap();
 
     executor.open().await.unwrap();
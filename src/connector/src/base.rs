@@ -21,6 +21,7 @@ use itertools::Itertools;
 use kafka::enumerator::KafkaSplitEnumerator;
 use serde::{Deserialize, Serialize};
 
+use crate::datagen::DatagenSplitReader;
 use crate::kafka::source::KafkaSplitReader;
 use crate::kinesis::source::reader::KinesisSplitReader;
 
@@ -35,6 +36,12 @@ use crate::{kafka, pulsar};
 const UPSTREAM_SOURCE_KEY: &str = "connector";
 const KAFKA_SOURCE: &str = "kafka";
 const KINESIS_SOURCE: &str = "kinesis";
+const DATAGEN_SOURCE: &str = "datagen";
+
+/// Limits the number of rows returned by a source reader per second, for all connectors which
+/// support it. Each batch of rows is no more than the limit, so that a small limit still makes
+/// steady progress. Kafka sources also accept the older `kafka.max.messages.per.second`.
+pub(crate) const SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY: &str = "max.rows.per.second";
 
 pub trait SourceMessage {
     fn payload(&self) -> Result<Option<&[u8]>>;
@@ -47,6 +54,10 @@ pub struct InnerMessage {
     pub payload: Option<Bytes>,
     pub offset: String,
     pub split_id: String,
+    /// Key of the record, if the source has one.
+    pub key: Option<Vec<u8>>,
+    /// Headers of the record in order, if the source supports them.
+    pub headers: Vec<(String, Vec<u8>)>,
 }
 
 pub trait SourceSplit {
@@ -64,6 +75,9 @@ pub struct ConnectorState {
 #[async_trait]
 pub trait SourceReader {
     async fn next(&mut self) -> Result<Option<Vec<InnerMessage>>>;
+    /// Stops reading and releases the resources held for the assigned splits. Afterwards, `next`
+    /// returns `None`.
+    async fn stop(&mut self) -> Result<()>;
     async fn new(config: HashMap<String, String>, state: Option<ConnectorState>) -> Result<Self>
     where
         Self: Sized;
@@ -129,6 +143,10 @@ pub async fn new_connector(
             let kinesis = KinesisSplitReader::new(config, state).await?;
             Box::new(kinesis)
         }
+        DATAGEN_SOURCE => {
+            let datagen = DatagenSplitReader::new(config, state).await?;
+            Box::new(datagen)
+        }
         _other => {
             todo!()
         }
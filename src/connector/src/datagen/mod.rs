@@ -0,0 +1,21 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod source;
+
+pub use source::*;
+
+const DATAGEN_CONFIG_ROWS_PER_SECOND_KEY: &str = "datagen.rows.per.second";
+const DATAGEN_CONFIG_MAX_ROWS_KEY: &str = "datagen.max.rows";
+const DATAGEN_CONFIG_SEED_KEY: &str = "datagen.seed";
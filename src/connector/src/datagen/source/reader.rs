@@ -0,0 +1,294 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::base::{InnerMessage, SourceReader, SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY};
+use crate::datagen::{
+    DATAGEN_CONFIG_MAX_ROWS_KEY, DATAGEN_CONFIG_ROWS_PER_SECOND_KEY, DATAGEN_CONFIG_SEED_KEY,
+};
+use crate::utils::{rate_limit, StopSignal, TokenBucket};
+use crate::ConnectorState;
+
+/// Max number of messages returned by one call to `next`.
+const DATAGEN_MAX_FETCH_MESSAGES: u64 = 1024;
+
+/// Split id of all generated messages, as a datagen source has only one split.
+const DATAGEN_SPLIT_ID: &str = "0";
+
+/// Generates synthetic messages for load testing, without any external system.
+///
+/// The `n`-th message has offset `n`, and a JSON payload `{"id": n, "value": v}`, where `v` only
+/// depends on the seed and `n`, so that a reader with the same seed always produces the same
+/// messages, no matter how they are batched.
+pub struct DatagenSplitReader {
+    seed: u64,
+    /// Offset of the next message to generate.
+    next_offset: u64,
+    /// Generation stops before this offset, if configured.
+    max_rows: Option<u64>,
+    /// Limits the number of messages generated per second, if configured by either
+    /// [`DATAGEN_CONFIG_ROWS_PER_SECOND_KEY`] or [`SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY`].
+    limiter: Option<TokenBucket>,
+    max_fetch_messages: u64,
+    /// Makes `next` return `None` once stop is requested, even if it's waiting for the limiter.
+    stop_signal: Arc<StopSignal>,
+}
+
+#[async_trait]
+impl SourceReader for DatagenSplitReader {
+    async fn next(&mut self) -> Result<Option<Vec<InnerMessage>>> {
+        if self.stop_signal.is_stopped() {
+            return Ok(None);
+        }
+        let remaining = match self.max_rows {
+            Some(max_rows) => max_rows.saturating_sub(self.next_offset),
+            None => u64::MAX,
+        };
+        if remaining == 0 {
+            return Ok(None);
+        }
+
+        let count = remaining.min(self.max_fetch_messages);
+        if let Some(limiter) = &mut self.limiter {
+            tokio::select! {
+                _ = self.stop_signal.stopped() => return Ok(None),
+                _ = limiter.acquire(count) => {}
+            }
+        }
+
+        let ret = (self.next_offset..self.next_offset + count)
+            .map(|offset| generate_message(self.seed, offset))
+            .collect();
+        self.next_offset += count;
+
+        Ok(Some(ret))
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        self.stop_signal.stop();
+        Ok(())
+    }
+
+    /// For datagen, the start offset of the state is the last generated offset, and generation
+    /// resumes right after it. Other fields are ignored.
+    async fn new(config: HashMap<String, String>, state: Option<ConnectorState>) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let rows_per_second = rate_limit(
+            &config,
+            &[
+                DATAGEN_CONFIG_ROWS_PER_SECOND_KEY,
+                SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY,
+            ],
+        )?;
+        let max_rows = parse_config::<u64>(&config, DATAGEN_CONFIG_MAX_ROWS_KEY)?;
+        let seed = parse_config::<u64>(&config, DATAGEN_CONFIG_SEED_KEY)?.unwrap_or_default();
+
+        let next_offset = match state {
+            Some(state) if !state.start_offset.is_empty() => {
+                let offset = state
+                    .start_offset
+                    .parse::<u64>()
+                    .map_err(|e| anyhow!("invalid datagen offset {}: {}", state.start_offset, e))?;
+                offset + 1
+            }
+            _ => 0,
+        };
+
+        Ok(Self {
+            seed,
+            next_offset,
+            max_rows,
+            limiter: rows_per_second.map(TokenBucket::new),
+            // Keep each batch within the budget of one second.
+            max_fetch_messages: rows_per_second
+                .unwrap_or(DATAGEN_MAX_FETCH_MESSAGES)
+                .min(DATAGEN_MAX_FETCH_MESSAGES),
+            stop_signal: Arc::new(StopSignal::default()),
+        })
+    }
+}
+
+fn parse_config<T: std::str::FromStr>(
+    config: &HashMap<String, String>,
+    key: &str,
+) -> Result<Option<T>> {
+    config
+        .get(key)
+        .map(|value| {
+            value
+                .parse::<T>()
+                .map_err(|_| anyhow!("{} must be a non-negative integer, got {}", key, value))
+        })
+        .transpose()
+}
+
+fn generate_message(seed: u64, offset: u64) -> InnerMessage {
+    let payload = format!(
+        r#"{{"id": {}, "value": {}}}"#,
+        offset,
+        split_mix64(split_mix64(seed) ^ offset) as i64
+    );
+    InnerMessage {
+        payload: Some(Bytes::from(payload)),
+        offset: offset.to_string(),
+        split_id: DATAGEN_SPLIT_ID.to_string(),
+        key: None,
+        headers: vec![],
+    }
+}
+
+/// The mixing function of `SplitMix64`, which scrambles `x` into a pseudo-random number.
+fn split_mix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use maplit::hashmap;
+
+    use super::*;
+
+    async fn read_all(config: HashMap<String, String>) -> Vec<InnerMessage> {
+        let mut reader = DatagenSplitReader::new(config, None).await.unwrap();
+        let mut messages = vec![];
+        while let Some(mut batch) = reader.next().await.unwrap() {
+            assert!(!batch.is_empty());
+            messages.append(&mut batch);
+        }
+        // Stays stopped.
+        assert!(reader.next().await.unwrap().is_none());
+        messages
+    }
+
+    #[tokio::test]
+    async fn test_datagen_reader() {
+        let config = hashmap! {
+            DATAGEN_CONFIG_MAX_ROWS_KEY.to_string() => "100".to_string(),
+            DATAGEN_CONFIG_SEED_KEY.to_string() => "42".to_string(),
+        };
+        let messages = read_all(config.clone()).await;
+        assert_eq!(messages.len(), 100);
+        for (i, message) in messages.iter().enumerate() {
+            assert_eq!(message.offset, i.to_string());
+            assert_eq!(message.split_id, DATAGEN_SPLIT_ID);
+        }
+        assert_eq!(
+            messages[0].payload.as_deref(),
+            Some(
+                format!(
+                    r#"{{"id": 0, "value": {}}}"#,
+                    split_mix64(split_mix64(42)) as i64
+                )
+                .as_bytes()
+            )
+        );
+
+        // Same seed, same messages, with or without the rate limit.
+        let mut rate_limited = config.clone();
+        rate_limited.insert(
+            DATAGEN_CONFIG_ROWS_PER_SECOND_KEY.to_string(),
+            "1000".to_string(),
+        );
+        assert_eq!(read_all(rate_limited).await, messages);
+
+        // Different seed, different values.
+        let mut other_seed = config.clone();
+        other_seed.insert(DATAGEN_CONFIG_SEED_KEY.to_string(), "43".to_string());
+        let other_messages = read_all(other_seed).await;
+        assert_eq!(other_messages.len(), 100);
+        assert_ne!(other_messages, messages);
+
+        // Resumes after the last generated offset, with the same messages.
+        let state = ConnectorState {
+            identifier: Bytes::from(DATAGEN_SPLIT_ID),
+            start_offset: "89".to_string(),
+            end_offset: "".to_string(),
+        };
+        let mut reader = DatagenSplitReader::new(config, Some(state)).await.unwrap();
+        assert_eq!(reader.next().await.unwrap().unwrap(), messages[90..]);
+        assert!(reader.next().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_max_rows_per_second() {
+        let config = hashmap! {
+            DATAGEN_CONFIG_MAX_ROWS_KEY.to_string() => "30".to_string(),
+            SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY.to_string() => "20".to_string(),
+        };
+        let mut reader = DatagenSplitReader::new(config, None).await.unwrap();
+
+        let start = std::time::Instant::now();
+        let mut batch_sizes = vec![];
+        while let Some(batch) = reader.next().await.unwrap() {
+            batch_sizes.push(batch.len());
+        }
+        let elapsed = start.elapsed();
+
+        // 30 rows at 20 rows per second, in batches within the budget of one second.
+        assert_eq!(batch_sizes, vec![20, 10]);
+        assert!(
+            elapsed >= Duration::from_millis(1400) && elapsed < Duration::from_millis(2500),
+            "took {:?}",
+            elapsed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stop_while_throttled() {
+        let config = hashmap! {
+            SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY.to_string() => "1".to_string(),
+        };
+        let mut reader = DatagenSplitReader::new(config, None).await.unwrap();
+        let stop_signal = reader.stop_signal.clone();
+
+        // The limiter starts empty, so the first row is held back for a second.
+        let join_handle = tokio::spawn(async move {
+            let ret = reader.next().await.unwrap();
+            (reader, ret)
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let start = std::time::Instant::now();
+        stop_signal.stop();
+        let (reader, ret) = join_handle.await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(500));
+        assert!(ret.is_none());
+        // The held back row is generated again on resume.
+        assert_eq!(reader.next_offset, 0);
+    }
+
+    #[tokio::test]
+    async fn test_datagen_invalid_config() {
+        let config = hashmap! {
+            DATAGEN_CONFIG_ROWS_PER_SECOND_KEY.to_string() => "0".to_string(),
+        };
+        assert!(DatagenSplitReader::new(config, None).await.is_err());
+        let config = hashmap! {
+            DATAGEN_CONFIG_MAX_ROWS_KEY.to_string() => "-1".to_string(),
+        };
+        assert!(DatagenSplitReader::new(config, None).await.is_err());
+    }
+}
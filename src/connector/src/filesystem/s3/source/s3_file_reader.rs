@@ -139,11 +139,13 @@ pub struct S3FileReader {
     s3_receive_stream: ReceiverStream<S3InnerMessage>,
     s3_msg_sender: Sender<S3InnerMessage>,
     stop_signal: Arc<watch::Sender<StatusWatch>>,
+    stopped: bool,
 }
 
 impl Drop for S3FileReader {
     fn drop(&mut self) {
-        self.stop_signal.send(StatusWatch::Stopped).unwrap();
+        // The reading task may have exited already, after the reader is stopped.
+        let _ = self.stop_signal.send(StatusWatch::Stopped);
     }
 }
 
@@ -160,6 +162,7 @@ impl S3FileReader {
             s3_receive_stream: ReceiverStream::from(rx),
             s3_msg_sender: tx.clone(),
             stop_signal: signal_arc.clone(),
+            stopped: false,
         };
         signal_arc.send(StatusWatch::Running).unwrap();
         tokio::task::spawn(async move {
@@ -314,6 +317,9 @@ impl S3FileReader {
 #[async_trait]
 impl SourceReader for S3FileReader {
     async fn next(&mut self) -> anyhow::Result<Option<Vec<InnerMessage>>> {
+        if self.stopped {
+            return Ok(None);
+        }
         let mut read_chunk = self
             .s3_receive_stream
             .borrow_mut()
@@ -335,6 +341,8 @@ impl SourceReader for S3FileReader {
                         payload: Some(msg.payload),
                         offset: new_offset.to_string(),
                         split_id: msg_id,
+                        key: None,
+                        headers: vec![],
                     }
                 })
                 .collect_vec(),
@@ -342,6 +350,14 @@ impl SourceReader for S3FileReader {
         Ok(Some(msg_vec))
     }
 
+    /// Stops the reading task. Messages received but not returned yet are discarded.
+    async fn stop(&mut self) -> anyhow::Result<()> {
+        self.stopped = true;
+        let _ = self.stop_signal.send(StatusWatch::Stopped);
+        self.s3_receive_stream.close();
+        Ok(())
+    }
+
     /// 1. The config include all information about the connection to S3, for example:
     /// `s3.region_name, s3.bucket_name, s3-dd-storage-notify-queue` and the credential's access_key
     /// and secret. For now, only static credential is supported.
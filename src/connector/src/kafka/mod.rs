@@ -25,3 +25,9 @@ pub use split::*;
 const KAFKA_SYNC_CALL_TIMEOUT: Duration = Duration::from_secs(1);
 const KAFKA_CONFIG_BROKER_KEY: &str = "kafka.broker";
 const KAFKA_CONFIG_TOPIC_KEY: &str = "kafka.topic";
+const KAFKA_CONFIG_GROUP_ID_KEY: &str = "kafka.consumer.group";
+const KAFKA_CONFIG_MAX_FETCH_MESSAGES_KEY: &str = "kafka.max.fetch.messages";
+/// Kept for existing Kafka sources as an alias of the generic `max.rows.per.second`. If both are
+/// set, the smaller limit applies.
+const KAFKA_CONFIG_MAX_MESSAGES_PER_SECOND_KEY: &str = "kafka.max.messages.per.second";
+const KAFKA_CONFIG_MAX_BYTES_PER_SECOND_KEY: &str = "kafka.max.bytes.per.second";
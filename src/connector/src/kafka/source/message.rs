@@ -14,7 +14,7 @@
 
 use anyhow::anyhow;
 use bytes::Bytes;
-use rdkafka::message::BorrowedMessage;
+use rdkafka::message::{BorrowedMessage, Headers};
 use rdkafka::Message;
 use serde::{Deserialize, Serialize};
 
@@ -44,10 +44,82 @@ impl SourceMessage for KafkaMessage {
 
 impl<'a> From<BorrowedMessage<'a>> for InnerMessage {
     fn from(message: BorrowedMessage<'a>) -> Self {
-        InnerMessage {
-            payload: message.payload().map(Bytes::copy_from_slice),
-            offset: message.offset().to_string(),
-            split_id: message.partition().to_string(),
-        }
+        inner_message_from_kafka(&message)
+    }
+}
+
+/// Converts any Kafka message into an [`InnerMessage`]. A tombstone, i.e. a record without
+/// payload, keeps its key but has no payload, so that parsers skip it.
+fn inner_message_from_kafka(message: &impl Message) -> InnerMessage {
+    let headers = message
+        .headers()
+        .map(|headers| {
+            (0..headers.count())
+                .filter_map(|idx| headers.get(idx))
+                .map(|(name, value): (&str, &[u8])| (name.to_string(), value.to_vec()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    InnerMessage {
+        payload: message.payload().map(Bytes::copy_from_slice),
+        offset: message.offset().to_string(),
+        split_id: message.partition().to_string(),
+        key: message.key().map(|key| key.to_vec()),
+        headers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rdkafka::message::{OwnedHeaders, OwnedMessage, Timestamp};
+
+    use super::*;
+
+    #[test]
+    fn test_inner_message_from_kafka() {
+        let headers = OwnedHeaders::new()
+            .add("op", "c")
+            .add("source", &[1_u8, 2, 3][..]);
+        let message = OwnedMessage::new(
+            Some(b"payload".to_vec()),
+            Some(b"key".to_vec()),
+            "topic".to_string(),
+            Timestamp::NotAvailable,
+            3,
+            42,
+            Some(headers),
+        );
+
+        let inner = inner_message_from_kafka(&message);
+        assert_eq!(inner.payload, Some(Bytes::from("payload")));
+        assert_eq!(inner.offset, "42");
+        assert_eq!(inner.split_id, "3");
+        assert_eq!(inner.key, Some(b"key".to_vec()));
+        assert_eq!(
+            inner.headers,
+            vec![
+                ("op".to_string(), b"c".to_vec()),
+                ("source".to_string(), vec![1, 2, 3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tombstone_from_kafka() {
+        let tombstone = OwnedMessage::new(
+            None,
+            Some(b"key".to_vec()),
+            "topic".to_string(),
+            Timestamp::NotAvailable,
+            3,
+            43,
+            None,
+        );
+        let inner = inner_message_from_kafka(&tombstone);
+        assert_eq!(inner.payload, None);
+        assert_eq!(inner.offset, "43");
+        assert_eq!(inner.key, Some(b"key".to_vec()));
+        assert!(inner.headers.is_empty());
     }
 }
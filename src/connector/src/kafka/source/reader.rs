@@ -12,20 +12,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use bytes::Bytes;
 use futures::StreamExt;
 use rdkafka::config::RDKafkaLogLevel;
 use rdkafka::consumer::stream_consumer::StreamPartitionQueue;
 use rdkafka::consumer::{Consumer, DefaultConsumerContext, StreamConsumer};
-use rdkafka::{ClientConfig, Message, TopicPartitionList};
+use rdkafka::{ClientConfig, Message, Offset, TopicPartitionList};
 
-use crate::base::{InnerMessage, SourceReader};
+use crate::base::{InnerMessage, SourceReader, SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY};
 use crate::kafka::split::{KafkaOffset, KafkaSplit};
+use crate::kafka::{
+    KAFKA_CONFIG_BROKER_KEY, KAFKA_CONFIG_GROUP_ID_KEY, KAFKA_CONFIG_MAX_BYTES_PER_SECOND_KEY,
+    KAFKA_CONFIG_MAX_FETCH_MESSAGES_KEY, KAFKA_CONFIG_MAX_MESSAGES_PER_SECOND_KEY,
+    KAFKA_CONFIG_TOPIC_KEY, KAFKA_SYNC_CALL_TIMEOUT,
+};
+use crate::utils::{rate_limit, StopSignal, TokenBucket};
+use crate::ConnectorState;
 
+/// Default max number of messages returned by one call to `next`.
 const KAFKA_MAX_FETCH_MESSAGES: usize = 1024;
 
 pub struct KafkaSplitReader {
@@ -33,22 +43,39 @@ pub struct KafkaSplitReader {
     partition_queue: StreamPartitionQueue<DefaultConsumerContext>,
     topic: String,
     assigned_split: KafkaSplit,
+    max_fetch_messages: usize,
+    /// Limits the number of messages returned per second, if configured by either
+    /// [`SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY`] or [`KAFKA_CONFIG_MAX_MESSAGES_PER_SECOND_KEY`].
+    message_limiter: Option<TokenBucket>,
+    /// Limits the total payload size in bytes returned per second, if configured.
+    byte_limiter: Option<TokenBucket>,
+    /// Makes `next` return `None` once stop is requested, even if it's waiting for messages or for
+    /// the rate limiters.
+    stop_signal: Arc<StopSignal>,
 }
 
 #[async_trait]
 impl SourceReader for KafkaSplitReader {
     async fn next(&mut self) -> Result<Option<Vec<InnerMessage>>> {
+        if self.stop_signal.is_stopped() {
+            return Ok(None);
+        }
+
         let mut stream = self
             .partition_queue
             .stream()
-            .ready_chunks(KAFKA_MAX_FETCH_MESSAGES);
+            .ready_chunks(self.max_fetch_messages);
 
-        let chunk = match stream.next().await {
-            None => return Ok(None),
-            Some(chunk) => chunk,
+        let chunk = tokio::select! {
+            _ = self.stop_signal.stopped() => return Ok(None),
+            chunk = stream.next() => match chunk {
+                None => return Ok(None),
+                Some(chunk) => chunk,
+            },
         };
 
         let mut ret = Vec::with_capacity(chunk.len());
+        let mut next_offset = None;
 
         for msg in chunk {
             let msg = msg.map_err(|e| anyhow!(e))?;
@@ -68,59 +95,201 @@ impl SourceReader for KafkaSplitReader {
             }
 
             ret.push(InnerMessage::from(msg));
+            next_offset = Some(offset + 1);
         }
 
+        // Hold the whole batch back until it fits in the rate limits, instead of splitting it. A
+        // stop cancels the wait and drops the batch, which is read again on resume.
+        let message_limiter = &mut self.message_limiter;
+        let byte_limiter = &mut self.byte_limiter;
+        let throttle = async {
+            if let Some(limiter) = message_limiter {
+                limiter.acquire(ret.len() as u64).await;
+            }
+            if let Some(limiter) = byte_limiter {
+                let bytes = ret
+                    .iter()
+                    .map(|msg| msg.payload.as_ref().map_or(0, |payload| payload.len()))
+                    .sum::<usize>();
+                limiter.acquire(bytes as u64).await;
+            }
+        };
+        tokio::select! {
+            _ = self.stop_signal.stopped() => return Ok(None),
+            _ = throttle => {}
+        }
+
+        // Move the split forward, so that a snapshot taken now resumes after the batch.
+        if let Some(offset) = next_offset {
+            self.assigned_split.start_offset = KafkaOffset::Offset(offset);
+        }
         Ok(Some(ret))
     }
 
-    // async fn assign_split<'a>(&'a mut self, split: &'a [u8]) -> Result<()> {
-    //     let kafka_split: KafkaSplit = serde_json::from_str(from_utf8(split)?)?;
-    //     let mut tpl = TopicPartitionList::new();
+    /// Unassigns the partition from the consumer, so that no more messages are fetched into the
+    /// partition queue.
+    async fn stop(&mut self) -> Result<()> {
+        self.stop_signal.stop();
+        self.consumer
+            .assign(&TopicPartitionList::new())
+            .map_err(|e| anyhow!(e))
+    }
 
-    //     let offset = match kafka_split.start_offset {
-    //         KafkaOffset::None | KafkaOffset::Earliest => Offset::Beginning,
-    //         KafkaOffset::Latest => Offset::End,
-    //         KafkaOffset::Offset(offset) => Offset::Offset(offset),
-    //         KafkaOffset::Timestamp(_) => unimplemented!(),
-    //     };
+    /// For Kafka, state identifier is the partition, start offset is the last consumed offset and
+    /// end offset is the stop offset. Both offsets may be empty. See [`split_from_state`].
+    async fn new(config: HashMap<String, String>, state: Option<ConnectorState>) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let topic = config
+            .get(KAFKA_CONFIG_TOPIC_KEY)
+            .ok_or_else(|| anyhow!("{} not found", KAFKA_CONFIG_TOPIC_KEY))?
+            .clone();
+        let state = state.ok_or_else(|| anyhow!("no split is assigned to kafka reader"))?;
+        let mut split = split_from_state(&state)?;
+        let max_messages_per_second = rate_limit(
+            &config,
+            &[
+                SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY,
+                KAFKA_CONFIG_MAX_MESSAGES_PER_SECOND_KEY,
+            ],
+        )?;
+        // Keep each batch within the budget of one second, so that a small limit makes steady
+        // progress rather than waiting for a large batch at once.
+        let max_fetch_messages = match max_messages_per_second {
+            Some(rate) => max_fetch_messages(&config)?.min(rate as usize),
+            None => max_fetch_messages(&config)?,
+        };
+        let message_limiter = max_messages_per_second.map(TokenBucket::new);
+        let byte_limiter = rate_limiter(&config, KAFKA_CONFIG_MAX_BYTES_PER_SECOND_KEY)?;
 
-    //     tpl.add_partition_offset(self.topic.as_str(), kafka_split.partition, offset)
-    //         .map_err(|e| anyhow!(e))?;
+        let consumer = Arc::new(Self::create_consumer(&config)?);
+        let (partition_queue, start_offset) =
+            Self::assign_partition(&consumer, topic.as_str(), &split)?;
+        split.start_offset = start_offset;
 
-    //     self.consumer.assign(&tpl).map_err(|e| anyhow!(e))?;
+        Ok(Self {
+            consumer,
+            partition_queue,
+            topic,
+            assigned_split: split,
+            max_fetch_messages,
+            message_limiter,
+            byte_limiter,
+            stop_signal: Arc::new(StopSignal::default()),
+        })
+    }
+}
 
-    //     let partition_queue = self
-    //         .consumer
-    //         .split_partition_queue(self.topic.as_str(), kafka_split.partition)
-    //         .ok_or_else(|| anyhow!("Failed to split partition queue"))?;
+impl KafkaSplitReader {
+    /// Moves the reader to another split of the topic. Messages buffered for the previous split
+    /// are discarded along with its partition queue, and the next call to `next` reads from the
+    /// start offset of `split`.
+    pub fn assign_split(&mut self, split: KafkaSplit) -> Result<()> {
+        // Release the previous partition first, so that none of its messages are fetched into
+        // the new queue when both splits are on the same partition.
+        self.consumer
+            .assign(&TopicPartitionList::new())
+            .map_err(|e| anyhow!(e))?;
 
-    //     self.partition_queue = partition_queue;
-    //     self.assigned_split = kafka_split;
+        let (partition_queue, start_offset) =
+            Self::assign_partition(&self.consumer, self.topic.as_str(), &split)?;
+        self.partition_queue = partition_queue;
+        self.assigned_split = KafkaSplit {
+            start_offset,
+            ..split
+        };
 
-    //     Ok(())
-    // }
+        Ok(())
+    }
 
-    async fn new(
-        _config: std::collections::HashMap<String, String>,
-        _state: Option<crate::ConnectorState>,
-    ) -> Result<Self>
-    where
-        Self: Sized,
-    {
-        todo!()
+    /// Returns the progress of the reader, from which a new reader resumes right after the last
+    /// consumed message. If nothing is consumed yet, the new reader starts where this one would.
+    pub fn snapshot_state(&self) -> ConnectorState {
+        let offset_to_string = |offset: KafkaOffset| match offset {
+            KafkaOffset::Offset(offset) => offset.to_string(),
+            _ => "".to_string(),
+        };
+
+        ConnectorState {
+            identifier: Bytes::from(self.assigned_split.partition.to_string()),
+            start_offset: match self.assigned_split.start_offset {
+                KafkaOffset::Offset(next_offset) => (next_offset - 1).to_string(),
+                _ => "".to_string(),
+            },
+            end_offset: offset_to_string(self.assigned_split.stop_offset),
+        }
     }
-}
 
-impl KafkaSplitReader {
-    fn create_consumer(&self) -> Result<StreamConsumer<DefaultConsumerContext>> {
+    /// Rewinds or fast-forwards the reader to `offset` of the assigned partition, so that the next
+    /// call to `next` delivers from there. A concrete offset must be within the watermarks of the
+    /// partition.
+    pub fn seek(&mut self, offset: KafkaOffset) -> Result<()> {
+        if let KafkaOffset::Offset(_) = offset {
+            let watermarks = self
+                .consumer
+                .fetch_watermarks(
+                    self.topic.as_str(),
+                    self.assigned_split.partition,
+                    KAFKA_SYNC_CALL_TIMEOUT,
+                )
+                .map_err(|e| anyhow!(e))?;
+            check_offset_in_watermarks(offset, watermarks)?;
+        }
+
+        let split = KafkaSplit::new(
+            self.assigned_split.partition,
+            offset,
+            self.assigned_split.stop_offset,
+        );
+        self.assign_split(split)
+    }
+
+    /// Looks up the earliest offset of `partition` whose timestamp is at or after `time` (in
+    /// milliseconds).
+    fn fetch_offset_for_time(
+        consumer: &Arc<StreamConsumer<DefaultConsumerContext>>,
+        topic: &str,
+        partition: i32,
+        time: i64,
+    ) -> Result<Offset> {
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition_offset(topic, partition, Offset::Offset(time))
+            .map_err(|e| anyhow!(e))?;
+
+        let offsets = consumer
+            .offsets_for_times(tpl, KAFKA_SYNC_CALL_TIMEOUT)
+            .map_err(|e| anyhow!(e))?;
+        let resolved = offsets
+            .find_partition(topic, partition)
+            .ok_or_else(|| anyhow!("no offset found for partition {} at {}", partition, time))?
+            .offset();
+        offset_for_time(resolved)
+    }
+
+    fn create_consumer(
+        properties: &HashMap<String, String>,
+    ) -> Result<StreamConsumer<DefaultConsumerContext>> {
+        Self::client_config(properties)?
+            .set_log_level(RDKafkaLogLevel::Debug)
+            .create_with_context(DefaultConsumerContext)
+            .map_err(|e| anyhow!(e))
+    }
+
+    fn client_config(properties: &HashMap<String, String>) -> Result<ClientConfig> {
+        let bootstrap_servers = properties
+            .get(KAFKA_CONFIG_BROKER_KEY)
+            .ok_or_else(|| anyhow!("{} not found", KAFKA_CONFIG_BROKER_KEY))?;
+
         let mut config = ClientConfig::new();
 
         config.set("topic.metadata.refresh.interval.ms", "30000");
         config.set("fetch.message.max.bytes", "134217728");
         config.set("auto.offset.reset", "earliest");
 
-        if config.get("group.id").is_none() {
-            config.set(
+        match properties.get(KAFKA_CONFIG_GROUP_ID_KEY) {
+            Some(group_id) => config.set("group.id", group_id),
+            None => config.set(
                 "group.id",
                 format!(
                     "consumer-{}",
@@ -129,17 +298,670 @@ impl KafkaSplitReader {
                         .unwrap()
                         .as_micros()
                 ),
-            );
-        }
+            ),
+        };
 
         // disable partition eof
         config.set("enable.partition.eof", "false");
         config.set("enable.auto.commit", "false");
-        // config.set("bootstrap.servers", self.bootstrap_servers.join(","));
+        config.set("bootstrap.servers", bootstrap_servers);
 
-        config
-            .set_log_level(RDKafkaLogLevel::Debug)
-            .create_with_context(DefaultConsumerContext)
-            .map_err(|e| anyhow!(e))
+        Ok(config)
+    }
+
+    /// Assigns the partition of `split` at its start offset to `consumer`. Returns the queue of the
+    /// partition, along with the start offset resolved by [`Self::resolve_start_offset`].
+    fn assign_partition(
+        consumer: &Arc<StreamConsumer<DefaultConsumerContext>>,
+        topic: &str,
+        split: &KafkaSplit,
+    ) -> Result<(StreamPartitionQueue<DefaultConsumerContext>, KafkaOffset)> {
+        let start_offset = Self::resolve_start_offset(consumer, topic, split)?;
+        let offset = match start_offset {
+            KafkaOffset::Offset(offset) => Offset::Offset(offset),
+            _ => Offset::Beginning,
+        };
+
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition_offset(topic, split.partition, offset)
+            .map_err(|e| anyhow!(e))?;
+        consumer.assign(&tpl).map_err(|e| anyhow!(e))?;
+
+        let partition_queue = consumer
+            .split_partition_queue(topic, split.partition)
+            .ok_or_else(|| anyhow!("Failed to split partition queue"))?;
+        Ok((partition_queue, start_offset))
+    }
+
+    /// Resolves a `Latest` or `Timestamp` start offset of `split` to the concrete offset it points
+    /// to now, so that a snapshot taken before anything is consumed resumes from the same position
+    /// rather than the earliest one. Other start offsets are returned as is.
+    fn resolve_start_offset(
+        consumer: &Arc<StreamConsumer<DefaultConsumerContext>>,
+        topic: &str,
+        split: &KafkaSplit,
+    ) -> Result<KafkaOffset> {
+        let offset = match split.start_offset {
+            KafkaOffset::Latest => Offset::End,
+            KafkaOffset::Timestamp(time) => {
+                Self::fetch_offset_for_time(consumer, topic, split.partition, time)?
+            }
+            start_offset => return Ok(start_offset),
+        };
+
+        match offset {
+            Offset::Offset(offset) => Ok(KafkaOffset::Offset(offset)),
+            // The end of the partition is the high watermark, i.e. the offset of the next message.
+            _ => {
+                let (_, high) = consumer
+                    .fetch_watermarks(topic, split.partition, KAFKA_SYNC_CALL_TIMEOUT)
+                    .map_err(|e| anyhow!(e))?;
+                Ok(KafkaOffset::Offset(high))
+            }
+        }
+    }
+}
+
+/// Reads the max number of messages per fetch from the config, which must be at least 1. Falls
+/// back to [`KAFKA_MAX_FETCH_MESSAGES`] if it's absent or not a number.
+fn max_fetch_messages(config: &HashMap<String, String>) -> Result<usize> {
+    let max_fetch_messages = config
+        .get(KAFKA_CONFIG_MAX_FETCH_MESSAGES_KEY)
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(KAFKA_MAX_FETCH_MESSAGES);
+    if max_fetch_messages < 1 {
+        return Err(anyhow!(
+            "{} must be at least 1",
+            KAFKA_CONFIG_MAX_FETCH_MESSAGES_KEY
+        ));
+    }
+    Ok(max_fetch_messages)
+}
+
+/// Builds a rate limiter from the per-second limit under `key` in the config, if any. Unlike
+/// [`max_fetch_messages`], an invalid limit is an error rather than silently ignored.
+fn rate_limiter(config: &HashMap<String, String>, key: &str) -> Result<Option<TokenBucket>> {
+    Ok(rate_limit(config, &[key])?.map(TokenBucket::new))
+}
+
+/// Returns the offset to start from given the result of looking up a timestamp. Kafka resolves a
+/// timestamp after the last message to `-1`, i.e. [`Offset::End`], in which case we start from the
+/// end of the partition. Any other result means the lookup failed.
+fn offset_for_time(resolved: Offset) -> Result<Offset> {
+    match resolved {
+        Offset::Offset(offset) => Ok(Offset::Offset(offset)),
+        Offset::End => Ok(Offset::End),
+        resolved => Err(anyhow!(
+            "invalid offset {:?} looked up by timestamp",
+            resolved
+        )),
+    }
+}
+
+/// Checks that a concrete `offset` is between the low and high watermarks of a partition. The high
+/// watermark itself is allowed, which means seeking to the next message to be produced.
+fn check_offset_in_watermarks(offset: KafkaOffset, (low, high): (i64, i64)) -> Result<()> {
+    match offset {
+        KafkaOffset::Offset(offset) if offset < low || offset > high => Err(anyhow!(
+            "offset {} is out of the range [{}, {}] of the partition",
+            offset,
+            low,
+            high
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Builds the split to read from a state taken by [`KafkaSplitReader::snapshot_state`]. The start
+/// offset in the state is the last consumed one, so reading resumes from the offset after it, or
+/// from the earliest offset if it's empty.
+fn split_from_state(state: &ConnectorState) -> Result<KafkaSplit> {
+    let partition = String::from_utf8(state.identifier.to_vec())?.parse::<i32>()?;
+    let parse_offset = |offset: &str| -> Result<Option<i64>> {
+        if offset.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(offset.parse::<i64>()?))
+        }
+    };
+
+    let start_offset = match parse_offset(&state.start_offset)? {
+        Some(last_offset) => KafkaOffset::Offset(last_offset + 1),
+        None => KafkaOffset::Earliest,
+    };
+    let stop_offset = match parse_offset(&state.end_offset)? {
+        Some(offset) => KafkaOffset::Offset(offset),
+        None => KafkaOffset::None,
+    };
+
+    Ok(KafkaSplit::new(partition, start_offset, stop_offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use maplit::hashmap;
+    use rdkafka::mocking::MockCluster;
+    use rdkafka::producer::{DefaultProducerContext, FutureProducer, FutureRecord};
+
+    use super::*;
+
+    fn test_config() -> HashMap<String, String> {
+        hashmap! {
+            KAFKA_CONFIG_BROKER_KEY.to_string() => "localhost:9092".to_string(),
+            KAFKA_CONFIG_TOPIC_KEY.to_string() => "test_topic".to_string(),
+        }
+    }
+
+    fn test_state(partition: i32, start_offset: &str) -> ConnectorState {
+        ConnectorState {
+            identifier: Bytes::from(partition.to_string()),
+            start_offset: start_offset.to_string(),
+            end_offset: "".to_string(),
+        }
+    }
+
+    /// Starts a mock cluster with `test_topic` of `partitions` partitions, and returns it along
+    /// with the config of a reader connecting to it. The cluster shuts down once dropped.
+    fn mock_cluster(
+        partitions: i32,
+    ) -> (
+        MockCluster<'static, DefaultProducerContext>,
+        HashMap<String, String>,
+    ) {
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic("test_topic", partitions, 1).unwrap();
+        let mut config = test_config();
+        config.insert(
+            KAFKA_CONFIG_BROKER_KEY.to_string(),
+            cluster.bootstrap_servers(),
+        );
+        (cluster, config)
+    }
+
+    async fn produce(config: &HashMap<String, String>, partition: i32, payloads: &[&str]) {
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &config[KAFKA_CONFIG_BROKER_KEY])
+            .create()
+            .unwrap();
+        for payload in payloads {
+            producer
+                .send(
+                    FutureRecord::<(), _>::to("test_topic")
+                        .partition(partition)
+                        .payload(*payload),
+                    Duration::from_secs(5),
+                )
+                .await
+                .unwrap();
+        }
+    }
+
+    /// Calls `next` until at least `count` messages are returned.
+    async fn read_messages(reader: &mut KafkaSplitReader, count: usize) -> Vec<InnerMessage> {
+        let mut messages = vec![];
+        while messages.len() < count {
+            let chunk = tokio::time::timeout(Duration::from_secs(10), reader.next())
+                .await
+                .expect("timed out waiting for messages")
+                .unwrap()
+                .unwrap();
+            messages.extend(chunk);
+        }
+        messages
+    }
+
+    /// Produces `count` messages of `payload`, and returns how long a reader limited to `rate` per
+    /// second by `key` takes to read all of them, counting from the creation of the reader.
+    async fn time_limited_read(key: &str, rate: u64, payload: &str, count: usize) -> f64 {
+        let (_cluster, mut config) = mock_cluster(1);
+        produce(&config, 0, &vec![payload; count]).await;
+        config.insert(key.to_string(), rate.to_string());
+
+        let start = Instant::now();
+        let mut reader = KafkaSplitReader::new(config, Some(test_state(0, "")))
+            .await
+            .unwrap();
+        assert_eq!(read_messages(&mut reader, count).await.len(), count);
+        start.elapsed().as_secs_f64()
+    }
+
+    fn offsets(messages: &[InnerMessage]) -> Vec<i64> {
+        messages
+            .iter()
+            .map(|msg| msg.offset.parse::<i64>().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_default_group_id() {
+        let config = KafkaSplitReader::client_config(&test_config()).unwrap();
+        assert!(config.get("group.id").unwrap().starts_with("consumer-"));
+        assert_eq!(config.get("bootstrap.servers"), Some("localhost:9092"));
+
+        let mut properties = test_config();
+        properties.insert(
+            KAFKA_CONFIG_GROUP_ID_KEY.to_string(),
+            "my_group".to_string(),
+        );
+        let config = KafkaSplitReader::client_config(&properties).unwrap();
+        assert_eq!(config.get("group.id"), Some("my_group"));
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let key = KAFKA_CONFIG_MAX_BYTES_PER_SECOND_KEY;
+        assert!(rate_limiter(&test_config(), key).unwrap().is_none());
+
+        let mut config = test_config();
+        config.insert(key.to_string(), "1024".to_string());
+        assert!(rate_limiter(&config, key).unwrap().is_some());
+
+        for invalid in ["0", "-1", "fast"] {
+            config.insert(key.to_string(), invalid.to_string());
+            assert!(rate_limiter(&config, key).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_reader() {
+        let reader = KafkaSplitReader::new(test_config(), Some(test_state(3, "42")))
+            .await
+            .unwrap();
+        assert_eq!(reader.topic, "test_topic");
+        assert_eq!(reader.assigned_split.partition, 3);
+        assert_eq!(reader.assigned_split.start_offset, KafkaOffset::Offset(43));
+        assert_eq!(reader.assigned_split.stop_offset, KafkaOffset::None);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_state() {
+        let mut reader = KafkaSplitReader::new(test_config(), Some(test_state(3, "")))
+            .await
+            .unwrap();
+        assert_eq!(reader.snapshot_state().start_offset, "");
+
+        // Simulate consuming messages up to offset 7, which is what `next` records.
+        reader.assigned_split.start_offset = KafkaOffset::Offset(8);
+        let state = reader.snapshot_state();
+        assert_eq!(state.identifier, Bytes::from("3"));
+        assert_eq!(state.start_offset, "7");
+
+        // The resumed reader must not deliver offset 7 again.
+        let reader = KafkaSplitReader::new(test_config(), Some(state))
+            .await
+            .unwrap();
+        assert_eq!(reader.assigned_split.partition, 3);
+        assert_eq!(reader.assigned_split.start_offset, KafkaOffset::Offset(8));
+        assert_eq!(reader.snapshot_state().start_offset, "7");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_and_restore() {
+        let (_cluster, mut config) = mock_cluster(1);
+        config.insert(
+            KAFKA_CONFIG_MAX_FETCH_MESSAGES_KEY.to_string(),
+            "1".to_string(),
+        );
+        produce(&config, 0, &["0", "1", "2", "3", "4"]).await;
+
+        let mut reader = KafkaSplitReader::new(config.clone(), Some(test_state(0, "")))
+            .await
+            .unwrap();
+        assert_eq!(offsets(&read_messages(&mut reader, 3).await), vec![0, 1, 2]);
+        let state = reader.snapshot_state();
+        reader.stop().await.unwrap();
+
+        // The restored reader delivers the rest, without any message consumed before.
+        let mut reader = KafkaSplitReader::new(config.clone(), Some(state))
+            .await
+            .unwrap();
+        assert_eq!(offsets(&read_messages(&mut reader, 2).await), vec![3, 4]);
+
+        // A split starting at the latest offset is snapshotted at where it started, even if
+        // nothing is consumed yet.
+        reader
+            .assign_split(KafkaSplit::new(0, KafkaOffset::Latest, KafkaOffset::None))
+            .unwrap();
+        assert_eq!(reader.assigned_split.start_offset, KafkaOffset::Offset(5));
+        let state = reader.snapshot_state();
+        assert_eq!(state.start_offset, "4");
+        reader.stop().await.unwrap();
+
+        produce(&config, 0, &["5", "6"]).await;
+        let mut reader = KafkaSplitReader::new(config, Some(state)).await.unwrap();
+        assert_eq!(offsets(&read_messages(&mut reader, 2).await), vec![5, 6]);
+    }
+
+    #[tokio::test]
+    async fn test_seek() {
+        let (_cluster, mut config) = mock_cluster(1);
+        config.insert(
+            KAFKA_CONFIG_MAX_FETCH_MESSAGES_KEY.to_string(),
+            "1".to_string(),
+        );
+        let payloads = (0..10).map(|i| i.to_string()).collect::<Vec<_>>();
+        produce(
+            &config,
+            0,
+            &payloads.iter().map(String::as_str).collect::<Vec<_>>(),
+        )
+        .await;
+
+        let mut reader = KafkaSplitReader::new(config, Some(test_state(0, "")))
+            .await
+            .unwrap();
+        assert_eq!(
+            offsets(&read_messages(&mut reader, 6).await),
+            vec![0, 1, 2, 3, 4, 5]
+        );
+
+        // Rewind from offset 5 to 2, and the messages from there are delivered again.
+        reader.seek(KafkaOffset::Offset(2)).unwrap();
+        let messages = read_messages(&mut reader, 3).await;
+        assert_eq!(offsets(&messages), vec![2, 3, 4]);
+        assert_eq!(messages[0].payload.as_deref(), Some(b"2".as_slice()));
+
+        assert!(reader.seek(KafkaOffset::Offset(11)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_assign_split() {
+        let mut reader = KafkaSplitReader::new(test_config(), Some(test_state(0, "")))
+            .await
+            .unwrap();
+
+        let split = KafkaSplit::new(1, KafkaOffset::Offset(10), KafkaOffset::Offset(20));
+        reader.assign_split(split).unwrap();
+        assert_eq!(reader.assigned_split.partition, 1);
+        assert_eq!(reader.assigned_split.stop_offset, KafkaOffset::Offset(20));
+
+        // Only the new partition remains assigned to the consumer.
+        let assignment = reader.consumer.assignment().unwrap().elements();
+        assert_eq!(assignment.len(), 1);
+        assert_eq!(assignment[0].topic(), "test_topic");
+        assert_eq!(assignment[0].partition(), 1);
+        assert_eq!(assignment[0].offset(), Offset::Offset(10));
+    }
+
+    #[tokio::test]
+    async fn test_assign_split_and_read() {
+        let (_cluster, mut config) = mock_cluster(2);
+        config.insert(
+            KAFKA_CONFIG_MAX_FETCH_MESSAGES_KEY.to_string(),
+            "1".to_string(),
+        );
+        produce(&config, 0, &["a0", "a1", "a2"]).await;
+        produce(&config, 1, &["b0", "b1", "b2"]).await;
+
+        let mut reader = KafkaSplitReader::new(config, Some(test_state(0, "")))
+            .await
+            .unwrap();
+        let messages = read_messages(&mut reader, 1).await;
+        assert_eq!(messages[0].split_id, "0");
+        assert_eq!(messages[0].payload.as_deref(), Some(b"a0".as_slice()));
+
+        reader
+            .assign_split(KafkaSplit::new(
+                1,
+                KafkaOffset::Offset(1),
+                KafkaOffset::None,
+            ))
+            .unwrap();
+        let messages = read_messages(&mut reader, 2).await;
+        assert_eq!(offsets(&messages), vec![1, 2]);
+        for (message, payload) in messages.iter().zip([b"b1", b"b2"]) {
+            assert_eq!(message.split_id, "1");
+            assert_eq!(message.payload.as_deref(), Some(payload.as_slice()));
+        }
+        assert_eq!(reader.snapshot_state().identifier, Bytes::from("1"));
+    }
+
+    #[tokio::test]
+    async fn test_stop() {
+        let mut reader = KafkaSplitReader::new(test_config(), Some(test_state(0, "")))
+            .await
+            .unwrap();
+        let stop_signal = reader.stop_signal.clone();
+
+        // There's no broker, so `next` keeps waiting for messages until stopped.
+        let mut join_handle = tokio::spawn(async move {
+            let ret = reader.next().await.unwrap();
+            (reader, ret)
+        });
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), &mut join_handle)
+                .await
+                .is_err()
+        );
+
+        stop_signal.stop();
+        let (mut reader, ret) = tokio::time::timeout(Duration::from_secs(5), join_handle)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(ret.is_none());
+
+        reader.stop().await.unwrap();
+        assert_eq!(reader.consumer.assignment().unwrap().count(), 0);
+        assert!(reader.next().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stop_while_throttled() {
+        let (_cluster, mut config) = mock_cluster(1);
+        produce(&config, 0, &["0123456789"]).await;
+        // The limiter starts empty, so the 10-byte message is held back for 10 seconds.
+        config.insert(
+            KAFKA_CONFIG_MAX_BYTES_PER_SECOND_KEY.to_string(),
+            "1".to_string(),
+        );
+        let mut reader = KafkaSplitReader::new(config, Some(test_state(0, "")))
+            .await
+            .unwrap();
+        let start_offset = reader.assigned_split.start_offset;
+        let stop_signal = reader.stop_signal.clone();
+
+        let join_handle = tokio::spawn(async move {
+            let ret = reader.next().await.unwrap();
+            (reader, ret)
+        });
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        stop_signal.stop();
+        let (reader, ret) = tokio::time::timeout(Duration::from_secs(2), join_handle)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(ret.is_none());
+        // The dropped message is read again after a restore.
+        assert_eq!(reader.assigned_split.start_offset, start_offset);
+    }
+
+    #[tokio::test]
+    async fn test_max_fetch_messages() {
+        let reader = KafkaSplitReader::new(test_config(), Some(test_state(0, "")))
+            .await
+            .unwrap();
+        assert_eq!(reader.max_fetch_messages, KAFKA_MAX_FETCH_MESSAGES);
+
+        let mut config = test_config();
+        config.insert(
+            KAFKA_CONFIG_MAX_FETCH_MESSAGES_KEY.to_string(),
+            "4".to_string(),
+        );
+        let reader = KafkaSplitReader::new(config.clone(), Some(test_state(0, "")))
+            .await
+            .unwrap();
+        assert_eq!(reader.max_fetch_messages, 4);
+
+        config.insert(
+            KAFKA_CONFIG_MAX_FETCH_MESSAGES_KEY.to_string(),
+            "many".to_string(),
+        );
+        assert_eq!(
+            max_fetch_messages(&config).unwrap(),
+            KAFKA_MAX_FETCH_MESSAGES
+        );
+
+        config.insert(
+            KAFKA_CONFIG_MAX_FETCH_MESSAGES_KEY.to_string(),
+            "0".to_string(),
+        );
+        assert!(KafkaSplitReader::new(config, Some(test_state(0, "")))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_max_fetch_messages() {
+        let (_cluster, mut config) = mock_cluster(1);
+        config.insert(
+            KAFKA_CONFIG_MAX_FETCH_MESSAGES_KEY.to_string(),
+            "4".to_string(),
+        );
+        let payloads = (0..10).map(|i| i.to_string()).collect::<Vec<_>>();
+        produce(
+            &config,
+            0,
+            &payloads.iter().map(String::as_str).collect::<Vec<_>>(),
+        )
+        .await;
+
+        let mut reader = KafkaSplitReader::new(config, Some(test_state(0, "")))
+            .await
+            .unwrap();
+        let mut messages = vec![];
+        while messages.len() < 10 {
+            let chunk = tokio::time::timeout(Duration::from_secs(10), reader.next())
+                .await
+                .expect("timed out waiting for messages")
+                .unwrap()
+                .unwrap();
+            assert!(!chunk.is_empty() && chunk.len() <= 4);
+            messages.extend(chunk);
+        }
+        assert_eq!(offsets(&messages), (0..10).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_max_rows_per_second() {
+        let mut config = test_config();
+        config.insert(
+            SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY.to_string(),
+            "100".to_string(),
+        );
+        let reader = KafkaSplitReader::new(config.clone(), Some(test_state(0, "")))
+            .await
+            .unwrap();
+        assert!(reader.message_limiter.is_some());
+        // Batches are capped to the limit.
+        assert_eq!(reader.max_fetch_messages, 100);
+
+        // The limiter starts empty, so reading 30 rows at 20 rows per second takes 1.5s.
+        let elapsed = time_limited_read(SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY, 20, "row", 30).await;
+        assert!(
+            (1.4..4.0).contains(&elapsed),
+            "read 30 rows in {}s",
+            elapsed
+        );
+
+        // The smaller limit wins.
+        config.insert(
+            KAFKA_CONFIG_MAX_MESSAGES_PER_SECOND_KEY.to_string(),
+            "10".to_string(),
+        );
+        let reader = KafkaSplitReader::new(config.clone(), Some(test_state(0, "")))
+            .await
+            .unwrap();
+        assert_eq!(reader.max_fetch_messages, 10);
+
+        config.insert(
+            SOURCE_CONFIG_MAX_ROWS_PER_SECOND_KEY.to_string(),
+            "0".to_string(),
+        );
+        assert!(KafkaSplitReader::new(config, Some(test_state(0, "")))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_rate_limits() {
+        // The limiters start empty, so reading 30 messages at 20 messages per second takes 1.5s.
+        let elapsed =
+            time_limited_read(KAFKA_CONFIG_MAX_MESSAGES_PER_SECOND_KEY, 20, "message", 30).await;
+        assert!(
+            (1.4..4.0).contains(&elapsed),
+            "read 30 messages in {}s",
+            elapsed
+        );
+
+        // 20 messages of 10 bytes at 100 bytes per second take 2s.
+        let elapsed =
+            time_limited_read(KAFKA_CONFIG_MAX_BYTES_PER_SECOND_KEY, 100, "0123456789", 20).await;
+        assert!(
+            (1.9..4.5).contains(&elapsed),
+            "read 200 bytes in {}s",
+            elapsed
+        );
+    }
+
+    #[test]
+    fn test_offset_for_time() {
+        assert_eq!(
+            offset_for_time(Offset::Offset(42)).unwrap(),
+            Offset::Offset(42)
+        );
+        // No message at or after the timestamp.
+        assert_eq!(offset_for_time(Offset::End).unwrap(), Offset::End);
+        assert!(offset_for_time(Offset::Invalid).is_err());
+        assert!(offset_for_time(Offset::Beginning).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_split() {
+        let (_cluster, config) = mock_cluster(1);
+        produce(&config, 0, &["0", "1", "2"]).await;
+
+        let mut reader = KafkaSplitReader::new(config.clone(), Some(test_state(0, "")))
+            .await
+            .unwrap();
+        // All messages are before the timestamp, so the split starts at the next one produced.
+        let split = KafkaSplit::new(0, KafkaOffset::Timestamp(i64::MAX), KafkaOffset::None);
+        reader.assign_split(split).unwrap();
+        assert_eq!(reader.assigned_split.start_offset, KafkaOffset::Offset(3));
+        let assignment = reader.consumer.assignment().unwrap().elements();
+        assert_eq!(assignment[0].offset(), Offset::Offset(3));
+
+        produce(&config, 0, &["3"]).await;
+        assert_eq!(offsets(&read_messages(&mut reader, 1).await), vec![3]);
+    }
+
+    #[test]
+    fn test_check_offset_in_watermarks() {
+        for offset in [2, 5, 10] {
+            check_offset_in_watermarks(KafkaOffset::Offset(offset), (2, 10)).unwrap();
+        }
+        for offset in [0, 1, 11] {
+            let err = check_offset_in_watermarks(KafkaOffset::Offset(offset), (2, 10))
+                .err()
+                .unwrap();
+            assert!(err.to_string().contains("out of the range [2, 10]"));
+        }
+        check_offset_in_watermarks(KafkaOffset::Earliest, (2, 10)).unwrap();
+        check_offset_in_watermarks(KafkaOffset::Latest, (2, 10)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_new_reader_missing_config() {
+        for key in [KAFKA_CONFIG_BROKER_KEY, KAFKA_CONFIG_TOPIC_KEY] {
+            let mut config = test_config();
+            config.remove(key);
+            let err = KafkaSplitReader::new(config, Some(test_state(0, "")))
+                .await
+                .err()
+                .unwrap();
+            assert!(err.to_string().contains(key));
+        }
     }
 }
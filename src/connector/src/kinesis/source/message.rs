@@ -50,6 +50,8 @@ impl From<KinesisMessage> for InnerMessage {
                 .map(|payload| Bytes::copy_from_slice(payload)),
             offset: msg.sequence_number.clone(),
             split_id: msg.shard_id,
+            key: None,
+            headers: vec![],
         }
     }
 }
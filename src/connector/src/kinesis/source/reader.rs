@@ -12,7 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{thread, time};
+use std::cmp::Ordering;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
@@ -24,19 +25,27 @@ use aws_sdk_kinesis::Client as kinesis_client;
 use aws_smithy_types::DateTime;
 use http::Uri;
 
-use crate::base::{InnerMessage, SourceReader};
+use crate::base::{ConnectorState, InnerMessage, SourceReader};
 use crate::kinesis::config::AwsConfigInfo;
 use crate::kinesis::source::message::KinesisMessage;
 use crate::kinesis::source::state::KinesisSplitReaderState;
 use crate::kinesis::split::{KinesisOffset, KinesisSplit};
 
+/// Start offset of a state to read from the oldest record of the shard.
+const KINESIS_OFFSET_TRIM_HORIZON: &str = "TRIM_HORIZON";
+/// Start offset of a state to read only records put after the reader is created.
+const KINESIS_OFFSET_LATEST: &str = "LATEST";
+
 pub struct KinesisSplitReader {
     client: kinesis_client,
     stream_name: String,
     shard_id: String,
+    /// Sequence number of the last returned record, or empty if none is returned yet.
     latest_sequence_num: String,
     shard_iter: Option<String>,
     assigned_split: Option<KinesisSplit>,
+    /// Set once a record at or after the stop sequence number is seen.
+    finished: bool,
 }
 
 #[async_trait]
@@ -47,6 +56,9 @@ impl SourceReader for KinesisSplitReader {
                 "you should call `assign_split` before calling `next`".to_string(),
             ));
         }
+        if self.finished {
+            return Ok(None);
+        }
         loop {
             let iter = match &self.shard_iter {
                 Some(_iter) => _iter,
@@ -63,13 +75,10 @@ impl SourceReader for KinesisSplitReader {
                     return Err(anyhow!(e));
                 }
                 Err(SdkError::ServiceError { err, .. }) if err.is_expired_iterator_exception() => {
-                    match self.renew_shard_iter().await {
-                        Ok(_) => {}
-                        Err(e) => {
-                            return Err(e);
-                        }
-                    }
-                    return self.next().await;
+                    // A shard iterator expires 5 minutes after it's returned, get a new one from
+                    // where we left off.
+                    self.renew_shard_iter().await?;
+                    continue;
                 }
                 Err(SdkError::ServiceError { err, .. })
                     if err.is_provisioned_throughput_exceeded_exception() =>
@@ -80,23 +89,23 @@ impl SourceReader for KinesisSplitReader {
                     return Err(anyhow!("{}", e));
                 }
             };
-            println!("get_record_output {:#?}", get_record_output);
             self.shard_iter = get_record_output.next_shard_iterator.clone();
 
             let records = get_record_output.records.unwrap_or_default();
             if records.is_empty() {
                 // if records is empty, retry after 200ms to avoid
                 // ProvisionedThroughputExceededException
-                thread::sleep(time::Duration::from_millis(200));
+                tokio::time::sleep(Duration::from_millis(200)).await;
                 continue;
             }
 
             let mut record_collection: Vec<InnerMessage> = Vec::new();
             for record in records {
-                if !is_stopping(
+                if is_stopping(
                     record.sequence_number.as_ref().unwrap(),
                     self.assigned_split.as_ref().unwrap(),
                 ) {
+                    self.finished = true;
                     return Ok(Some(record_collection));
                 }
                 self.latest_sequence_num = record.sequence_number().unwrap().to_string();
@@ -109,15 +118,23 @@ impl SourceReader for KinesisSplitReader {
         }
     }
 
-    /// For Kinesis, state identifier is split_id, stream_name is never changed
+    /// Makes `next` return `None` from now on.
+    async fn stop(&mut self) -> Result<()> {
+        self.finished = true;
+        Ok(())
+    }
+
+    /// For Kinesis, state identifier is split_id, stream_name is never changed. See
+    /// [`split_from_state`] for the offsets of the state.
     async fn new(
         config: std::collections::HashMap<String, String>,
-        state: Option<crate::ConnectorState>,
+        state: Option<ConnectorState>,
     ) -> Result<Self>
     where
         Self: Sized,
     {
         let config = AwsConfigInfo::build(&config)?;
+        let split = state.as_ref().map(split_from_state).transpose()?;
         let aws_config = config.load().await?;
         let mut builder = aws_sdk_kinesis::config::Builder::from(&aws_config);
         if let Some(endpoint) = &config.endpoint {
@@ -134,55 +151,16 @@ impl SourceReader for KinesisSplitReader {
             latest_sequence_num: "".to_string(),
             shard_iter: None,
             assigned_split: None,
+            finished: false,
         };
 
-        if let Some(state) = state {
-            let split_id = String::from_utf8(state.identifier.to_vec())?;
-
-            let mut start_offset = KinesisOffset::Earliest;
-            if !state.start_offset.is_empty() {
-                start_offset = KinesisOffset::SequenceNumber(state.start_offset);
-            }
-            let mut end_offset = KinesisOffset::None;
-            if !state.end_offset.is_empty() {
-                end_offset = KinesisOffset::SequenceNumber(state.end_offset);
+        if let Some(split) = split {
+            if let KinesisOffset::SequenceNumber(seq_number) = &split.start_position {
+                split_reader.latest_sequence_num = seq_number.clone();
             }
-            let split = KinesisSplit {
-                shard_id: split_id.clone(),
-                start_position: start_offset.clone(),
-                end_position: end_offset.clone(),
-            };
-
-            let shard_iter: Option<String> = match &start_offset {
-                KinesisOffset::Earliest => {
-                    Self::get_kinesis_iterator(
-                        &split_reader.client,
-                        &split_reader.stream_name,
-                        &split_id,
-                        ShardIteratorType::TrimHorizon,
-                        None,
-                        None,
-                    )
-                    .await?
-                }
-                KinesisOffset::SequenceNumber(seq_number) => {
-                    Self::get_kinesis_iterator(
-                        &split_reader.client,
-                        &split_reader.stream_name,
-                        &split_id,
-                        ShardIteratorType::AfterSequenceNumber,
-                        None,
-                        Some(seq_number.clone()),
-                    )
-                    .await?
-                }
-                other => {
-                    return Err(anyhow::Error::msg(format!("invalid KinesisOffset, expect either KinesisOffset::Earliest or KinesisOffset::SequenceNumber, got {:?}", other)));
-                }
-            };
-
+            split_reader.shard_id = split.shard_id.clone();
             split_reader.assigned_split = Some(split);
-            split_reader.shard_iter = shard_iter;
+            split_reader.renew_shard_iter().await?;
         }
 
         Ok(split_reader)
@@ -203,22 +181,40 @@ impl KinesisSplitReader {
         resp
     }
 
+    /// Gets a new shard iterator right after the last returned record, or from the start
+    /// position of the split if no record is returned yet.
     async fn renew_shard_iter(&mut self) -> Result<()> {
-        let get_shard_iter_resp = self
-            .client
-            .get_shard_iterator()
-            .stream_name(&self.stream_name)
-            .shard_id(self.shard_id.clone())
-            .shard_iterator_type(aws_sdk_kinesis::model::ShardIteratorType::AfterSequenceNumber)
-            .starting_sequence_number(self.latest_sequence_num.clone())
-            .send()
-            .await;
-        self.shard_iter = match get_shard_iter_resp {
-            Ok(resp) => resp.shard_iterator().map(String::from),
-            Err(e) => {
-                return Err(anyhow!("{}", e));
+        let (shard_iterator_type, seq_num) = if !self.latest_sequence_num.is_empty() {
+            (
+                ShardIteratorType::AfterSequenceNumber,
+                Some(self.latest_sequence_num.clone()),
+            )
+        } else {
+            match self
+                .assigned_split
+                .as_ref()
+                .map(|split| &split.start_position)
+            {
+                Some(KinesisOffset::Earliest) => (ShardIteratorType::TrimHorizon, None),
+                Some(KinesisOffset::Latest) => (ShardIteratorType::Latest, None),
+                other => {
+                    return Err(anyhow!(
+                        "invalid start position {:?} to renew the iterator of shard {}",
+                        other,
+                        self.shard_id
+                    ))
+                }
             }
         };
+        self.shard_iter = Self::get_kinesis_iterator(
+            &self.client,
+            &self.stream_name,
+            &self.shard_id,
+            shard_iterator_type,
+            None,
+            seq_num,
+        )
+        .await?;
         Ok(())
     }
 
@@ -269,14 +265,135 @@ impl KinesisSplitReader {
     }
 }
 
+/// Returns whether the record with `cur_seq_num` reaches the end position of the split, which is
+/// exclusive.
 fn is_stopping(cur_seq_num: &str, split: &KinesisSplit) -> bool {
     match &split.end_position {
         KinesisOffset::SequenceNumber(stopping_seq_num) => {
-            if cur_seq_num < stopping_seq_num.as_str() {
-                return true;
-            }
-            false
+            cmp_sequence_number(cur_seq_num, stopping_seq_num) != Ordering::Less
         }
-        _ => true,
+        _ => false,
+    }
+}
+
+/// Compares two sequence numbers. They are decimal numbers of up to 128 bits, which may have
+/// different lengths, so they can't be compared as strings directly.
+fn cmp_sequence_number(a: &str, b: &str) -> Ordering {
+    let a = a.trim_start_matches('0');
+    let b = b.trim_start_matches('0');
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Builds the split to read from a state. The start offset is either
+/// [`KINESIS_OFFSET_TRIM_HORIZON`] (or empty), [`KINESIS_OFFSET_LATEST`], or the sequence number of
+/// the last consumed record to resume after. The end offset is either empty for no end, or an
+/// exclusive sequence number to stop at.
+fn split_from_state(state: &ConnectorState) -> Result<KinesisSplit> {
+    let shard_id = String::from_utf8(state.identifier.to_vec())?;
+    let parse_sequence_number = |offset: &str| -> Result<String> {
+        if offset.chars().all(|c| c.is_ascii_digit()) {
+            Ok(offset.to_string())
+        } else {
+            Err(anyhow!("invalid kinesis sequence number {}", offset))
+        }
+    };
+
+    let start_position = match state.start_offset.as_str() {
+        "" | KINESIS_OFFSET_TRIM_HORIZON => KinesisOffset::Earliest,
+        KINESIS_OFFSET_LATEST => KinesisOffset::Latest,
+        offset => KinesisOffset::SequenceNumber(parse_sequence_number(offset)?),
+    };
+    let end_position = match state.end_offset.as_str() {
+        "" => KinesisOffset::None,
+        offset => KinesisOffset::SequenceNumber(parse_sequence_number(offset)?),
+    };
+
+    Ok(KinesisSplit::new(shard_id, start_position, end_position))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::kinesis::config::kinesis_demo_properties;
+
+    fn test_state(start_offset: &str, end_offset: &str) -> ConnectorState {
+        ConnectorState {
+            identifier: Bytes::from("shardId-000000000000"),
+            start_offset: start_offset.to_string(),
+            end_offset: end_offset.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_split_from_state() {
+        let split = split_from_state(&test_state("", "")).unwrap();
+        assert_eq!(split.shard_id, "shardId-000000000000");
+        assert_eq!(split.start_position, KinesisOffset::Earliest);
+        assert_eq!(split.end_position, KinesisOffset::None);
+
+        let split = split_from_state(&test_state(KINESIS_OFFSET_TRIM_HORIZON, "")).unwrap();
+        assert_eq!(split.start_position, KinesisOffset::Earliest);
+        let split = split_from_state(&test_state(KINESIS_OFFSET_LATEST, "")).unwrap();
+        assert_eq!(split.start_position, KinesisOffset::Latest);
+
+        let split = split_from_state(&test_state("123", "456")).unwrap();
+        assert_eq!(
+            split.start_position,
+            KinesisOffset::SequenceNumber("123".to_string())
+        );
+        assert_eq!(
+            split.end_position,
+            KinesisOffset::SequenceNumber("456".to_string())
+        );
+
+        assert!(split_from_state(&test_state("earliest", "")).is_err());
+        assert!(split_from_state(&test_state("", KINESIS_OFFSET_LATEST)).is_err());
+    }
+
+    #[test]
+    fn test_is_stopping() {
+        let split = |end_position| {
+            KinesisSplit::new(
+                "shardId-000000000000".to_string(),
+                KinesisOffset::Earliest,
+                end_position,
+            )
+        };
+        assert!(!is_stopping("100", &split(KinesisOffset::None)));
+
+        let stop = split(KinesisOffset::SequenceNumber("100".to_string()));
+        assert!(!is_stopping("99", &stop));
+        assert!(is_stopping("100", &stop));
+        assert!(is_stopping("0100", &stop));
+        assert!(is_stopping("1000", &stop));
+    }
+
+    #[tokio::test]
+    async fn test_new_reader() {
+        let mut config = kinesis_demo_properties();
+        config.insert(
+            "kinesis.credentials.access".to_string(),
+            "access".to_string(),
+        );
+        config.insert(
+            "kinesis.credentials.secret".to_string(),
+            "secret".to_string(),
+        );
+        // Without a split, no request is sent to Kinesis.
+        let mut reader = KinesisSplitReader::new(config.clone(), None).await.unwrap();
+        assert_eq!(reader.stream_name, "kinesis_test_stream");
+        assert!(reader.next().await.is_err());
+
+        // Offsets are validated before connecting to Kinesis.
+        assert!(
+            KinesisSplitReader::new(config.clone(), Some(test_state("", "end")))
+                .await
+                .is_err()
+        );
+
+        config.remove("kinesis.stream.name");
+        assert!(KinesisSplitReader::new(config, None).await.is_err());
     }
 }
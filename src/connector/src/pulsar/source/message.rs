@@ -17,6 +17,7 @@ use pulsar::consumer::Message;
 use serde::{Deserialize, Serialize};
 
 use crate::base::{InnerMessage, SourceMessage, SourceOffset};
+use crate::pulsar::split::PulsarMessageId;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PulsarMessage {
@@ -43,8 +44,15 @@ impl From<Message<Vec<u8>>> for InnerMessage {
     fn from(msg: Message<Vec<u8>>) -> Self {
         InnerMessage {
             payload: Some(bytes::Bytes::from(msg.payload.data)),
-            offset: msg.message_id.id.entry_id.to_string(),
+            offset: PulsarMessageId::new(
+                msg.message_id.id.ledger_id,
+                msg.message_id.id.entry_id,
+                msg.message_id.id.batch_index,
+            )
+            .to_string(),
             split_id: msg.topic,
+            key: None,
+            headers: vec![],
         }
     }
 }
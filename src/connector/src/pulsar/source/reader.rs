@@ -13,19 +13,25 @@
 // limitations under the License.
 
 use std::borrow::BorrowMut;
+use std::collections::HashMap;
+use std::time::SystemTime;
 
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use futures::StreamExt;
-use pulsar::{Consumer, Pulsar, TokioExecutor};
+use pulsar::consumer::{ConsumerOptions, InitialPosition};
+use pulsar::{Consumer, Pulsar, SubType, TokioExecutor};
 
 use crate::base::{InnerMessage, SourceReader};
-use crate::pulsar::split::{PulsarOffset, PulsarSplit};
+use crate::pulsar::split::{PulsarMessageId, PulsarOffset, PulsarSplit};
+use crate::pulsar::PULSAR_CONFIG_SERVICE_URL_KEY;
+use crate::ConnectorState;
 
 pub struct PulsarSplitReader {
     pulsar: Pulsar<TokioExecutor>,
     consumer: Consumer<Vec<u8>, TokioExecutor>,
     split: PulsarSplit,
+    stopped: bool,
 }
 
 const PULSAR_MAX_FETCH_MESSAGES: u32 = 1024;
@@ -33,6 +39,9 @@ const PULSAR_MAX_FETCH_MESSAGES: u32 = 1024;
 #[async_trait]
 impl SourceReader for PulsarSplitReader {
     async fn next(&mut self) -> anyhow::Result<Option<Vec<InnerMessage>>> {
+        if self.stopped {
+            return Ok(None);
+        }
         let mut stream = self
             .consumer
             .borrow_mut()
@@ -48,10 +57,24 @@ impl SourceReader for PulsarSplitReader {
         for msg in chunk {
             let msg = msg.map_err(|e| anyhow!(e))?;
 
-            let entry_id = msg.message_id.id.entry_id;
+            let message_id = PulsarMessageId::new(
+                msg.message_id.id.ledger_id,
+                msg.message_id.id.entry_id,
+                msg.message_id.id.batch_index,
+            );
+
+            // The consumer starts from the earliest message, skip those already consumed.
+            let should_skip = match self.split.start_offset {
+                PulsarOffset::MessageID(last_consumed) => message_id <= last_consumed,
+                PulsarOffset::Timestamp(timestamp) => msg.payload.metadata.event_time() < timestamp,
+                PulsarOffset::None => false,
+            };
+            if should_skip {
+                continue;
+            }
 
             let should_stop = match self.split.stop_offset {
-                PulsarOffset::MessageID(id) => entry_id >= id,
+                PulsarOffset::MessageID(id) => message_id >= id,
                 PulsarOffset::Timestamp(timestamp) => {
                     msg.payload.metadata.event_time() >= timestamp
                 }
@@ -73,6 +96,15 @@ impl SourceReader for PulsarSplitReader {
         Ok(Some(ret))
     }
 
+    async fn stop(&mut self) -> anyhow::Result<()> {
+        self.stopped = true;
+        self.consumer
+            .borrow_mut()
+            .unsubscribe()
+            .await
+            .map_err(|e| anyhow!(e))
+    }
+
     // async fn assign_split<'a>(&'a mut self, split: &'a [u8]) -> anyhow::Result<()> {
     //     let split: PulsarSplit = serde_json::from_str(from_utf8(split)?)?;
     //     let consumer: Consumer<Vec<u8>, TokioExecutor> = self
@@ -99,13 +131,121 @@ impl SourceReader for PulsarSplitReader {
     //     todo!("seek offset here")
     // }
 
-    async fn new(
-        _config: std::collections::HashMap<String, String>,
-        _state: Option<crate::ConnectorState>,
-    ) -> Result<Self>
+    /// For Pulsar, state identifier is the sub topic, start offset is the message id of the last
+    /// consumed message and end offset is the message id to stop at. Both offsets may be empty.
+    async fn new(config: HashMap<String, String>, state: Option<ConnectorState>) -> Result<Self>
     where
         Self: Sized,
     {
-        todo!()
+        let service_url = config
+            .get(PULSAR_CONFIG_SERVICE_URL_KEY)
+            .ok_or_else(|| anyhow!("{} not found", PULSAR_CONFIG_SERVICE_URL_KEY))?;
+        let state = state.ok_or_else(|| anyhow!("no split is assigned to pulsar reader"))?;
+        let split = split_from_state(&state)?;
+
+        let pulsar: Pulsar<TokioExecutor> = Pulsar::builder(service_url, TokioExecutor)
+            .build()
+            .await
+            .map_err(|e| anyhow!(e))?;
+        let consumer: Consumer<Vec<u8>, TokioExecutor> = pulsar
+            .consumer()
+            .with_topic(split.sub_topic.as_str())
+            .with_batch_size(PULSAR_MAX_FETCH_MESSAGES)
+            .with_subscription_type(SubType::Exclusive)
+            .with_subscription(format!(
+                "consumer-{}",
+                SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis()
+            ))
+            .with_options(
+                ConsumerOptions::default().with_initial_position(InitialPosition::Earliest),
+            )
+            .build()
+            .await
+            .map_err(|e| anyhow!(e))?;
+
+        Ok(Self {
+            pulsar,
+            consumer,
+            split,
+            stopped: false,
+        })
+    }
+}
+
+/// Builds the split to read from a state. Reading resumes from the message after the last consumed
+/// one, or from the earliest message if the start offset is empty. The start offset of the split is
+/// therefore exclusive, unlike the stop offset.
+fn split_from_state(state: &ConnectorState) -> Result<PulsarSplit> {
+    let sub_topic = String::from_utf8(state.identifier.to_vec())?;
+    let parse_offset = |offset: &str| -> Result<PulsarOffset> {
+        if offset.is_empty() {
+            Ok(PulsarOffset::None)
+        } else {
+            Ok(PulsarOffset::MessageID(offset.parse()?))
+        }
+    };
+
+    let start_offset = parse_offset(&state.start_offset)?;
+    let stop_offset = parse_offset(&state.end_offset)?;
+
+    Ok(PulsarSplit::new(sub_topic, start_offset, stop_offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use maplit::hashmap;
+
+    use super::*;
+
+    fn test_state(start_offset: &str, end_offset: &str) -> ConnectorState {
+        ConnectorState {
+            identifier: Bytes::from("persistent://public/default/t-partition-0"),
+            start_offset: start_offset.to_string(),
+            end_offset: end_offset.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_split_from_state() {
+        let split = split_from_state(&test_state("", "")).unwrap();
+        assert_eq!(split.sub_topic, "persistent://public/default/t-partition-0");
+        assert_eq!(split.start_offset, PulsarOffset::None);
+        assert_eq!(split.stop_offset, PulsarOffset::None);
+
+        let split = split_from_state(&test_state("3:41:-1", "4:100:2")).unwrap();
+        assert_eq!(
+            split.start_offset,
+            PulsarOffset::MessageID(PulsarMessageId::new(3, 41, None))
+        );
+        assert_eq!(
+            split.stop_offset,
+            PulsarOffset::MessageID(PulsarMessageId::new(4, 100, Some(2)))
+        );
+
+        assert!(split_from_state(&test_state("41", "")).is_err());
+        assert!(split_from_state(&test_state("-1:41:-1", "")).is_err());
+        assert!(split_from_state(&test_state("", "end")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_new_reader_missing_config() {
+        let config = hashmap! {
+            "pulsar.topic".to_string() => "t".to_string(),
+        };
+        let err = PulsarSplitReader::new(config, Some(test_state("", "")))
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains(PULSAR_CONFIG_SERVICE_URL_KEY));
+
+        let config = hashmap! {
+            PULSAR_CONFIG_SERVICE_URL_KEY.to_string() => "pulsar://localhost:6650".to_string(),
+        };
+        let err = PulsarSplitReader::new(config, None).await.err().unwrap();
+        assert!(err.to_string().contains("no split"));
     }
 }
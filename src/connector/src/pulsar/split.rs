@@ -12,14 +12,63 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt;
+use std::str::FromStr;
+
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 
 use crate::base::SourceSplit;
 
+/// Position of a message in a Pulsar topic partition. Messages are ordered by ledger, then by entry
+/// within the ledger, then by index within a batched entry, which is the field order here.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct PulsarMessageId {
+    pub ledger_id: u64,
+    pub entry_id: u64,
+    /// -1 for a message that is not part of a batch, as Pulsar does.
+    pub batch_index: i32,
+}
+
+impl PulsarMessageId {
+    pub fn new(ledger_id: u64, entry_id: u64, batch_index: Option<i32>) -> Self {
+        Self {
+            ledger_id,
+            entry_id,
+            batch_index: batch_index.unwrap_or(-1),
+        }
+    }
+}
+
+/// Formats as `ledger_id:entry_id:batch_index`.
+impl fmt::Display for PulsarMessageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.ledger_id, self.entry_id, self.batch_index
+        )
+    }
+}
+
+impl FromStr for PulsarMessageId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.split(':').collect::<Vec<_>>()[..] {
+            [ledger_id, entry_id, batch_index] => Ok(Self {
+                ledger_id: ledger_id.parse()?,
+                entry_id: entry_id.parse()?,
+                batch_index: batch_index.parse()?,
+            }),
+            _ => Err(anyhow!("invalid pulsar message id: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum PulsarOffset {
-    MessageID(u64),
+    MessageID(PulsarMessageId),
     Timestamp(u64),
     None,
 }
@@ -50,3 +99,26 @@ impl SourceSplit for PulsarSplit {
         serde_json::to_string(self).map_err(|e| anyhow!(e))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_id() {
+        let id = PulsarMessageId::new(3, 42, None);
+        assert_eq!(id.to_string(), "3:42:-1");
+        assert_eq!("3:42:-1".parse::<PulsarMessageId>().unwrap(), id);
+        assert_eq!(
+            "3:42:5".parse::<PulsarMessageId>().unwrap(),
+            PulsarMessageId::new(3, 42, Some(5))
+        );
+        assert!("42".parse::<PulsarMessageId>().is_err());
+        assert!("3:42:x".parse::<PulsarMessageId>().is_err());
+
+        // A later ledger comes after any entry of an earlier one.
+        assert!(PulsarMessageId::new(4, 0, None) > PulsarMessageId::new(3, 42, None));
+        assert!(PulsarMessageId::new(3, 42, Some(1)) > PulsarMessageId::new(3, 42, Some(0)));
+        assert!(PulsarMessageId::new(3, 43, Some(0)) > PulsarMessageId::new(3, 42, Some(1)));
+    }
+}
@@ -11,3 +11,158 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Returns the smallest per-second limit under `keys` in the config, or `None` if none of them is
+/// set. A limit must be a positive integer.
+pub(crate) fn rate_limit(config: &HashMap<String, String>, keys: &[&str]) -> Result<Option<u64>> {
+    let mut limit: Option<u64> = None;
+    for key in keys {
+        if let Some(value) = config.get(*key) {
+            let rate = match value.parse::<u64>() {
+                Ok(rate) if rate > 0 => rate,
+                _ => return Err(anyhow!("{} must be a positive integer, got {}", key, value)),
+            };
+            limit = Some(limit.map_or(rate, |limit| limit.min(rate)));
+        }
+    }
+    Ok(limit)
+}
+
+/// Paces a flow to at most `rate` units per second, e.g. messages or bytes.
+///
+/// The bucket starts empty and holds up to one second's worth of tokens. Taking more tokens than
+/// available puts the bucket into debt, and the caller waits until the debt is repaid, so that
+/// the amount taken never exceeds `rate` per second plus the capacity accumulated while idle.
+pub(crate) struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: u64) -> Self {
+        assert!(rate > 0, "rate of token bucket must be positive");
+        Self {
+            rate: rate as f64,
+            tokens: 0.0,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Takes `n` tokens, waiting for the bucket to refill if there are not enough.
+    pub async fn acquire(&mut self, n: u64) {
+        let now = Instant::now();
+        let refilled = now.duration_since(self.last_refill).as_secs_f64() * self.rate;
+        self.tokens = (self.tokens + refilled).min(self.rate) - n as f64;
+        self.last_refill = now;
+
+        if self.tokens < 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(-self.tokens / self.rate)).await;
+        }
+    }
+}
+
+/// Requests a reader to stop from another task, e.g. while the reader is waiting for data.
+#[derive(Default)]
+pub(crate) struct StopSignal {
+    stopped: AtomicBool,
+    notify: Notify,
+}
+
+impl StopSignal {
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once stop is requested.
+    pub async fn stopped(&self) {
+        loop {
+            // Register before checking the flag, so that a stop in between is not missed.
+            let notified = self.notify.notified();
+            if self.is_stopped() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::hashmap;
+
+    use super::*;
+
+    #[test]
+    fn test_rate_limit() {
+        let config = hashmap! {
+            "a".to_string() => "10".to_string(),
+            "b".to_string() => "5".to_string(),
+            "c".to_string() => "0".to_string(),
+        };
+        assert_eq!(rate_limit(&config, &[]).unwrap(), None);
+        assert_eq!(rate_limit(&config, &["d"]).unwrap(), None);
+        assert_eq!(rate_limit(&config, &["a", "d"]).unwrap(), Some(10));
+        assert_eq!(rate_limit(&config, &["a", "b"]).unwrap(), Some(5));
+        assert!(rate_limit(&config, &["a", "c"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_token_bucket() {
+        let rate = 20_000;
+        let mut bucket = TokenBucket::new(rate);
+
+        let start = Instant::now();
+        let mut taken = 0;
+        while start.elapsed() < Duration::from_millis(300) {
+            bucket.acquire(1_000).await;
+            taken += 1_000;
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+
+        assert!(
+            taken as f64 / elapsed <= rate as f64,
+            "took {} tokens in {}s",
+            taken,
+            elapsed
+        );
+        // Not throttled more than necessary, allowing for a slow machine.
+        assert!(taken >= 4_000, "took {} tokens in {}s", taken, elapsed);
+    }
+
+    #[tokio::test]
+    async fn test_stop_signal() {
+        let signal = std::sync::Arc::new(StopSignal::default());
+        assert!(!signal.is_stopped());
+
+        let signal_clone = signal.clone();
+        let mut join_handle = tokio::spawn(async move { signal_clone.stopped().await });
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), &mut join_handle)
+                .await
+                .is_err()
+        );
+
+        signal.stop();
+        tokio::time::timeout(Duration::from_secs(1), join_handle)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(signal.is_stopped());
+        // Resolves at once after stopped.
+        signal.stopped().await;
+    }
+}
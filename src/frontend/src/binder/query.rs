@@ -108,6 +108,13 @@ impl Binder {
         let index = *name_to_index
             .get(&name)
             .ok_or_else(|| ErrorCode::ItemNotFound(format!("output column \"{}\"", name)))?;
-        Ok(FieldOrder { index, direct })
+        let nulls_first = order_by_expr
+            .nulls_first
+            .unwrap_or_else(|| direct.default_nulls_first());
+        Ok(FieldOrder {
+            index,
+            direct,
+            nulls_first,
+        })
     }
 }
@@ -14,6 +14,7 @@
 
 use std::fmt;
 
+use risingwave_pb::plan::exchange_node::OrderingMode;
 use risingwave_pb::plan::plan_node::NodeBody;
 use risingwave_pb::plan::{ColumnOrder, ExchangeNode, MergeSortExchangeNode};
 
@@ -72,22 +73,28 @@ impl ToBatchProst for BatchExchange {
             NodeBody::Exchange(ExchangeNode {
                 sources: vec![],
                 input_schema: self.base.schema.to_prost(),
+                ordering_mode: OrderingMode::PreservePartitionOrder as i32,
             })
         } else {
             NodeBody::MergeSortExchange(MergeSortExchangeNode {
                 exchange_node: Some(ExchangeNode {
                     sources: vec![],
                     input_schema: self.base.schema.to_prost(),
+                    ordering_mode: OrderingMode::PreservePartitionOrder as i32,
                 }),
                 column_orders: self
                     .base
                     .order
-                    .to_protobuf()
+                    .field_order
                     .iter()
-                    .map(|(input_ref, order_type)| ColumnOrder {
-                        order_type: *order_type as i32,
-                        input_ref: Some(input_ref.clone()),
-                        return_type: None,
+                    .map(|field_order| {
+                        let (input_ref, order_type) = field_order.to_protobuf();
+                        ColumnOrder {
+                            order_type: order_type as i32,
+                            input_ref: Some(input_ref),
+                            return_type: None,
+                            nulls_first: field_order.nulls_first,
+                        }
                     })
                     .collect(),
             })
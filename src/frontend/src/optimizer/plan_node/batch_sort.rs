@@ -65,21 +65,19 @@ impl ToDistributedBatch for BatchSort {
 
 impl ToBatchProst for BatchSort {
     fn to_batch_prost_body(&self) -> NodeBody {
-        let column_orders_without_type = self.base.order.to_protobuf();
-        let column_types = self
+        let column_orders = self
             .base
             .order
             .field_order
             .iter()
-            .map(|field_order| self.schema()[field_order.index].data_type.to_protobuf())
-            .collect_vec();
-        let column_orders = column_orders_without_type
-            .into_iter()
-            .zip_eq(column_types.into_iter())
-            .map(|((input_ref, order_type), return_type)| ColumnOrder {
-                order_type: order_type as i32,
-                input_ref: Some(input_ref),
-                return_type: Some(return_type),
+            .map(|field_order| {
+                let (input_ref, order_type) = field_order.to_protobuf();
+                ColumnOrder {
+                    order_type: order_type as i32,
+                    input_ref: Some(input_ref),
+                    return_type: Some(self.schema()[field_order.index].data_type.to_protobuf()),
+                    nulls_first: field_order.nulls_first,
+                }
             })
             .collect_vec();
         NodeBody::OrderBy(OrderByNode { column_orders })
@@ -106,6 +106,7 @@ impl ColPrunable for LogicalTopN {
                 .map(|fo| FieldOrder {
                     index: mapping.map(fo.index),
                     direct: fo.direct,
+                    nulls_first: fo.nulls_first,
                 })
                 .collect(),
         };
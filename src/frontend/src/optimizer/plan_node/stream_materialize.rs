@@ -239,6 +239,7 @@ impl ToStreamProst for StreamMaterialize {
                             column_idx: idx as i32,
                         }),
                         return_type: Some(col.column_desc.data_type.to_protobuf()),
+                        nulls_first: col.order.default_nulls_first(),
                     }
                 })
                 .collect(),
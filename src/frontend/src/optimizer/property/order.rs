@@ -69,27 +69,32 @@ impl fmt::Display for Order {
 pub struct FieldOrder {
     pub index: usize,
     pub direct: Direction,
+    /// Whether nulls are placed before non-null values, regardless of `direct`.
+    pub nulls_first: bool,
 }
 
 impl std::fmt::Debug for FieldOrder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "${} {}", self.index, self.direct)
+        fmt::Display::fmt(self, f)
     }
 }
 
 impl FieldOrder {
-    pub fn ascending(index: usize) -> Self {
+    /// Creates an order with the default placement of nulls for `direct`.
+    pub fn new(index: usize, direct: Direction) -> Self {
         Self {
             index,
-            direct: Direction::Asc,
+            direct,
+            nulls_first: direct.default_nulls_first(),
         }
     }
 
+    pub fn ascending(index: usize) -> Self {
+        Self::new(index, Direction::Asc)
+    }
+
     pub fn descending(index: usize) -> Self {
-        Self {
-            index,
-            direct: Direction::Desc,
-        }
+        Self::new(index, Direction::Desc)
     }
 
     pub fn to_protobuf(&self) -> (InputRefExpr, ProstOrderType) {
@@ -103,7 +108,16 @@ impl FieldOrder {
 
 impl fmt::Display for FieldOrder {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "${} {}", self.index, self.direct)
+        write!(f, "${} {}", self.index, self.direct)?;
+        // Only an explicit placement of nulls is shown.
+        if self.direct != Direction::Any && self.nulls_first != self.direct.default_nulls_first() {
+            f.write_str(if self.nulls_first {
+                " NULLS FIRST"
+            } else {
+                " NULLS LAST"
+            })?;
+        }
+        Ok(())
     }
 }
 
@@ -146,6 +160,11 @@ impl Direction {
 }
 
 impl Direction {
+    /// Whether nulls come first by default, see [`OrderType::default_nulls_first`].
+    pub fn default_nulls_first(&self) -> bool {
+        OrderType::from(*self).default_nulls_first()
+    }
+
     pub fn satisfies(&self, other: &Direction) -> bool {
         match other {
             Direction::Any => true,
@@ -183,6 +202,10 @@ impl Order {
             if order.index != other_order.index || !order.direct.satisfies(&other_order.direct) {
                 return false;
             }
+            if other_order.direct != Direction::Any && order.nulls_first != other_order.nulls_first
+            {
+                return false;
+            }
         }
         true
     }
@@ -204,53 +227,24 @@ mod tests {
     fn test_order_satisfy() {
         let o1 = Order {
             field_order: vec![
-                FieldOrder {
-                    index: 0,
-                    direct: Direction::Asc,
-                },
-                FieldOrder {
-                    index: 1,
-                    direct: Direction::Desc,
-                },
-                FieldOrder {
-                    index: 2,
-                    direct: Direction::Asc,
-                },
+                FieldOrder::ascending(0),
+                FieldOrder::descending(1),
+                FieldOrder::ascending(2),
             ],
         };
         let o2 = Order {
-            field_order: vec![
-                FieldOrder {
-                    index: 0,
-                    direct: Direction::Asc,
-                },
-                FieldOrder {
-                    index: 1,
-                    direct: Direction::Desc,
-                },
-            ],
+            field_order: vec![FieldOrder::ascending(0), FieldOrder::descending(1)],
         };
         let o3 = Order {
-            field_order: vec![
-                FieldOrder {
-                    index: 0,
-                    direct: Direction::Asc,
-                },
-                FieldOrder {
-                    index: 1,
-                    direct: Direction::Asc,
-                },
-            ],
+            field_order: vec![FieldOrder::ascending(0), FieldOrder::ascending(1)],
         };
         let o4 = Order {
             field_order: vec![
-                FieldOrder {
-                    index: 0,
-                    direct: Direction::Asc,
-                },
+                FieldOrder::ascending(0),
                 FieldOrder {
                     index: 1,
                     direct: Direction::Any,
+                    nulls_first: false,
                 },
             ],
         };
@@ -267,4 +261,30 @@ mod tests {
         assert!(!o4.satisfies(&o2));
         assert!(!o4.satisfies(&o3));
     }
+
+    #[test]
+    fn test_order_nulls_first() {
+        let default = Order::new(vec![FieldOrder::ascending(0)]);
+        let nulls_first = Order::new(vec![FieldOrder {
+            nulls_first: true,
+            ..FieldOrder::ascending(0)
+        }]);
+        let any = Order::new(vec![FieldOrder::new(0, Direction::Any)]);
+
+        assert!(!default.satisfies(&nulls_first));
+        assert!(!nulls_first.satisfies(&default));
+        assert!(nulls_first.satisfies(&any));
+        assert!(default.satisfies(&any));
+
+        assert_eq!(default.to_string(), "[$0 ASC]");
+        assert_eq!(nulls_first.to_string(), "[$0 ASC NULLS FIRST]");
+        assert_eq!(
+            FieldOrder {
+                nulls_first: false,
+                ..FieldOrder::descending(1)
+            }
+            .to_string(),
+            "$1 DESC NULLS LAST"
+        );
+    }
 }
@@ -20,6 +20,7 @@ use arc_swap::ArcSwap;
 use risingwave_common::error::ErrorCode::InternalError;
 use risingwave_common::error::Result;
 use risingwave_pb::common::HostAddress;
+use risingwave_pb::plan::exchange_node::OrderingMode;
 use risingwave_pb::plan::plan_node::NodeBody;
 use risingwave_pb::plan::{
     ExchangeNode, ExchangeSource, MergeSortExchangeNode, PlanFragment, PlanNode as PlanNodeProst,
@@ -312,7 +313,7 @@ impl StageRunner {
                     .unwrap();
 
                 match &execution_plan_node.node {
-                    NodeBody::Exchange(_exchange_node) => {
+                    NodeBody::Exchange(exchange_node) => {
                         PlanNodeProst {
                             children: vec![],
                             // TODO: Generate meaningful identify
@@ -320,6 +321,7 @@ impl StageRunner {
                             node_body: Some(NodeBody::Exchange(ExchangeNode {
                                 sources: exchange_sources,
                                 input_schema: execution_plan_node.schema.clone(),
+                                ordering_mode: exchange_node.ordering_mode,
                             })),
                         }
                     }
@@ -332,6 +334,7 @@ impl StageRunner {
                                 exchange_node: Some(ExchangeNode {
                                     sources: exchange_sources,
                                     input_schema: execution_plan_node.schema.clone(),
+                                    ordering_mode: OrderingMode::PreservePartitionOrder as i32,
                                 }),
                                 column_orders: sort_merge_exchange_node.column_orders.clone(),
                             })),
@@ -254,6 +254,7 @@ impl ColIndexMapping {
                 Some(mapped_index) => mapped_field.push(FieldOrder {
                     index: mapped_index,
                     direct: field.direct,
+                    nulls_first: field.nulls_first,
                 }),
                 None => break,
             }
@@ -275,6 +276,7 @@ impl ColIndexMapping {
                 self.try_map(field.index).map(|mapped_index| FieldOrder {
                     index: mapped_index,
                     direct: field.direct,
+                    nulls_first: field.nulls_first,
                 })
             })
             .collect::<Option<Vec<_>>>()
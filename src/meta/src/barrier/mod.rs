@@ -211,7 +211,7 @@ where
         if self.enable_recovery {
             // handle init, here we simply trigger a recovery process to achieve the consistency. We
             // may need to avoid this when we have more state persisted in meta store.
-            let new_epoch = self.env.epoch_generator().generate().into_inner();
+            let new_epoch = self.env.epoch_generator().generate().await.into_inner();
             assert!(new_epoch > state.prev_epoch);
             state.prev_epoch = new_epoch;
 
@@ -241,7 +241,7 @@ where
             // Get a barrier to send.
             let (command, notifiers) = self.scheduled_barriers.pop_or_default().await;
             let info = self.resolve_actor_info(command.creating_table_id()).await;
-            let new_epoch = self.env.epoch_generator().generate().into_inner();
+            let new_epoch = self.env.epoch_generator().generate().await.into_inner();
             assert!(new_epoch > state.prev_epoch);
             let command_ctx = CommandContext::new(
                 self.fragment_manager.clone(),
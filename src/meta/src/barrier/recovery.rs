@@ -73,7 +73,7 @@ where
         let retry_strategy = Self::get_retry_strategy();
         let (new_epoch, responses) = tokio_retry::Retry::spawn(retry_strategy, || async {
             let info = self.resolve_actor_info(None).await;
-            let mut new_epoch = self.env.epoch_generator().generate();
+            let mut new_epoch = self.env.epoch_generator().generate().await;
 
             // Reset all compute nodes, stop and drop existing actors.
             self.reset_compute_nodes(&info, prev_epoch, new_epoch.into_inner())
@@ -96,7 +96,7 @@ where
             }
 
             let prev_epoch = new_epoch.into_inner();
-            new_epoch = self.env.epoch_generator().generate();
+            new_epoch = self.env.epoch_generator().generate().await;
             // checkpoint, used as init barrier to initialize all executors.
             let command_ctx = CommandContext::new(
                 self.fragment_manager.clone(),
@@ -303,6 +303,8 @@ where
         ret
     }
 
+    /// Unpins `pinned_version_ids` of `context_id`. The pinned version record of `context_id` is
+    /// deleted once it has no pinned version left.
     pub async fn unpin_version(
         &self,
         context_id: HummockContextId,
@@ -310,16 +312,17 @@ where
     ) -> Result<()> {
         let mut versioning_guard = self.versioning.write().await;
         let mut pinned_versions = VarTransaction::new(&mut versioning_guard.pinned_versions);
-        let mut context_pinned_version = match pinned_versions.new_entry_txn(context_id) {
+        let context_pinned_version = match pinned_versions.get_mut(&context_id) {
             None => {
                 return Ok(());
             }
             Some(context_pinned_version) => context_pinned_version,
         };
-        for pinned_version_id in pinned_version_ids.as_ref() {
-            context_pinned_version.unpin_version(*pinned_version_id);
+        context_pinned_version.unpin_versions(pinned_version_ids.as_ref());
+        if context_pinned_version.version_id.is_empty() {
+            pinned_versions.remove(&context_id);
         }
-        commit_multi_var!(self, Some(context_id), context_pinned_version)?;
+        commit_multi_var!(self, Some(context_id), pinned_versions)?;
 
         #[cfg(test)]
         {
@@ -520,6 +523,40 @@ where
         Ok(())
     }
 
+    /// Unpins the snapshots pinned by any context whose physical time is older than that of
+    /// `cutoff`, e.g. the ones left by a context that crashed without unpinning them. Contexts
+    /// without pinned snapshots left are removed.
+    pub async fn unpin_snapshots_before(&self, cutoff: HummockEpoch) -> Result<()> {
+        let mut versioning_guard = self.versioning.write().await;
+        let mut pinned_snapshots = VarTransaction::new(&mut versioning_guard.pinned_snapshots);
+
+        let mut to_commit = false;
+        let context_ids = pinned_snapshots.keys().cloned().collect_vec();
+        for context_id in context_ids {
+            let pinned_snapshot = pinned_snapshots.get_mut(&context_id).unwrap();
+            if !pinned_snapshot.unpin_snapshots_before(cutoff) {
+                continue;
+            }
+            to_commit = true;
+            if pinned_snapshot.snapshot_id.is_empty() {
+                pinned_snapshots.remove(&context_id);
+            }
+        }
+        if !to_commit {
+            abort_multi_var!(pinned_snapshots);
+            return Ok(());
+        }
+        commit_multi_var!(self, None, pinned_snapshots)?;
+
+        #[cfg(test)]
+        {
+            drop(versioning_guard);
+            self.check_state_consistency().await;
+        }
+
+        Ok(())
+    }
+
     pub async fn get_compact_task(
         &self,
         assignee_context_id: HummockContextId,
@@ -29,6 +29,7 @@ use risingwave_pb::hummock::{
 
 use crate::hummock::model::CurrentHummockVersionId;
 use crate::hummock::test_utils::*;
+use crate::manager::Epoch;
 use crate::model::MetadataModel;
 
 fn pin_versions_sum(pin_versions: &[HummockPinnedVersion]) -> usize {
@@ -76,6 +77,10 @@ async fn test_hummock_pin_unpin() -> Result<()> {
             pin_versions_sum(&HummockPinnedVersion::list(env.meta_store()).await?),
             0
         );
+        // The record is deleted once no version is pinned.
+        assert!(HummockPinnedVersion::list(env.meta_store())
+            .await?
+            .is_empty());
     }
 
     assert_eq!(
@@ -970,3 +975,67 @@ async fn test_mark_orphan_ssts() {
         )
     );
 }
+
+#[tokio::test]
+async fn test_unpin_snapshots_before() {
+    let (env, hummock_manager, _cluster_manager, worker_node) = setup_compute_env(80).await;
+    let context_id = worker_node.id;
+
+    // Pin one snapshot at physical time 1000, and another one at 2000.
+    let mut pinned_epochs = vec![];
+    let mut last_pinned = INVALID_EPOCH;
+    for millis in [1000, 2000] {
+        let epoch = Epoch::from_physical(millis).into_inner();
+        let test_tables = generate_test_tables(
+            epoch,
+            vec![
+                hummock_manager.get_new_table_id().await.unwrap(),
+                hummock_manager.get_new_table_id().await.unwrap(),
+            ],
+        );
+        hummock_manager
+            .add_tables(context_id, test_tables, epoch)
+            .await
+            .unwrap();
+        hummock_manager.commit_epoch(epoch).await.unwrap();
+        last_pinned = hummock_manager
+            .pin_snapshot(context_id, last_pinned)
+            .await
+            .unwrap()
+            .epoch;
+        pinned_epochs.push(last_pinned);
+    }
+    assert_eq!(
+        pin_snapshots_sum(&HummockPinnedSnapshot::list(env.meta_store()).await.unwrap()),
+        2
+    );
+
+    // Only the snapshot older than the cutoff is unpinned.
+    hummock_manager
+        .unpin_snapshots_before(Epoch::from_physical(1500).into_inner())
+        .await
+        .unwrap();
+    let pinned_snapshots = HummockPinnedSnapshot::list(env.meta_store()).await.unwrap();
+    assert_eq!(pinned_snapshots.len(), 1);
+    assert_eq!(pinned_snapshots[0].snapshot_id, vec![pinned_epochs[1]]);
+
+    // Nothing is unpinned if all snapshots are newer than the cutoff.
+    hummock_manager
+        .unpin_snapshots_before(Epoch::from_physical(2000).into_inner())
+        .await
+        .unwrap();
+    assert_eq!(
+        pin_snapshots_sum(&HummockPinnedSnapshot::list(env.meta_store()).await.unwrap()),
+        1
+    );
+
+    // The context is removed once all of its snapshots are unpinned.
+    hummock_manager
+        .unpin_snapshots_before(Epoch::from_physical(3000).into_inner())
+        .await
+        .unwrap();
+    assert!(HummockPinnedSnapshot::list(env.meta_store())
+        .await
+        .unwrap()
+        .is_empty());
+}
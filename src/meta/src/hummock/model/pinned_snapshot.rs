@@ -12,10 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
+
 use prost::Message;
 use risingwave_hummock_sdk::HummockEpoch;
 use risingwave_pb::hummock::{HummockContextRefId, HummockPinnedSnapshot};
 
+use crate::manager::Epoch;
 use crate::model::MetadataModel;
 
 /// Column family name for hummock pinned snapshot
@@ -54,6 +57,16 @@ pub trait HummockPinnedSnapshotExt {
     fn pin_snapshot(&mut self, new_snapshot_id: HummockEpoch);
 
     fn unpin_snapshot(&mut self, pinned_snapshot_id: HummockEpoch);
+
+    /// Pins all of `epochs`, skipping the ones already pinned or duplicated.
+    fn pin_snapshots(&mut self, epochs: &[HummockEpoch]);
+
+    /// Unpins all of `epochs`. Epochs not pinned are ignored.
+    fn unpin_snapshots(&mut self, epochs: &[HummockEpoch]);
+
+    /// Unpins the snapshots whose physical time is older than that of `cutoff`. Returns whether
+    /// any snapshot is unpinned.
+    fn unpin_snapshots_before(&mut self, cutoff: HummockEpoch) -> bool;
 }
 
 impl HummockPinnedSnapshotExt for HummockPinnedSnapshot {
@@ -70,4 +83,68 @@ impl HummockPinnedSnapshotExt for HummockPinnedSnapshot {
             self.snapshot_id.remove(pos);
         }
     }
+
+    fn pin_snapshots(&mut self, epochs: &[HummockEpoch]) {
+        let mut pinned: HashSet<_> = self.snapshot_id.iter().copied().collect();
+        for &epoch in epochs {
+            if pinned.insert(epoch) {
+                self.snapshot_id.push(epoch);
+            }
+        }
+    }
+
+    fn unpin_snapshots(&mut self, epochs: &[HummockEpoch]) {
+        let unpinned: HashSet<_> = epochs.iter().copied().collect();
+        self.snapshot_id.retain(|epoch| !unpinned.contains(epoch));
+    }
+
+    fn unpin_snapshots_before(&mut self, cutoff: HummockEpoch) -> bool {
+        let cutoff = Epoch::from(cutoff).physical_time();
+        let original_len = self.snapshot_id.len();
+        self.snapshot_id
+            .retain(|&epoch| Epoch::from(epoch).physical_time() >= cutoff);
+        self.snapshot_id.len() != original_len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_unpin_snapshots() {
+        let mut pinned_snapshot = HummockPinnedSnapshot {
+            context_id: 1,
+            snapshot_id: vec![],
+        };
+        pinned_snapshot.pin_snapshot(1);
+        pinned_snapshot.pin_snapshots(&[3, 1, 2, 3, 2]);
+        assert_eq!(pinned_snapshot.snapshot_id, vec![1, 3, 2]);
+
+        pinned_snapshot.unpin_snapshots(&[2, 4, 2]);
+        assert_eq!(pinned_snapshot.snapshot_id, vec![1, 3]);
+        pinned_snapshot.unpin_snapshots(&[]);
+        assert_eq!(pinned_snapshot.snapshot_id, vec![1, 3]);
+        pinned_snapshot.unpin_snapshots(&[1, 3]);
+        assert!(pinned_snapshot.snapshot_id.is_empty());
+    }
+
+    #[test]
+    fn test_unpin_snapshots_before() {
+        let epoch = |millis: u64| Epoch::from_physical(millis).into_inner();
+        let mut pinned_snapshot = HummockPinnedSnapshot {
+            context_id: 1,
+            snapshot_id: vec![epoch(1000) + 1, epoch(3000), epoch(2000) + 5, epoch(1999)],
+        };
+
+        // Epochs are compared by their physical time only.
+        assert!(pinned_snapshot.unpin_snapshots_before(epoch(2000) + 3));
+        assert_eq!(
+            pinned_snapshot.snapshot_id,
+            vec![epoch(3000), epoch(2000) + 5]
+        );
+        assert!(!pinned_snapshot.unpin_snapshots_before(epoch(2000)));
+        assert!(pinned_snapshot.unpin_snapshots_before(epoch(4000)));
+        assert!(pinned_snapshot.snapshot_id.is_empty());
+    }
 }
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
+
 use prost::Message;
 use risingwave_hummock_sdk::HummockVersionId;
 use risingwave_pb::hummock::{HummockContextRefId, HummockPinnedVersion};
@@ -52,7 +54,14 @@ impl MetadataModel for HummockPinnedVersion {
 
 pub trait HummockPinnedVersionExt {
     fn pin_version(&mut self, version_id: HummockVersionId);
+
     fn unpin_version(&mut self, version_id: HummockVersionId);
+
+    /// Pins all of `version_ids`, skipping the ones already pinned or duplicated.
+    fn pin_versions(&mut self, version_ids: &[HummockVersionId]);
+
+    /// Unpins all of `version_ids`. Versions not pinned are ignored.
+    fn unpin_versions(&mut self, version_ids: &[HummockVersionId]);
 }
 
 impl HummockPinnedVersionExt for HummockPinnedVersion {
@@ -69,4 +78,46 @@ impl HummockPinnedVersionExt for HummockPinnedVersion {
             self.version_id.remove(pos);
         }
     }
+
+    fn pin_versions(&mut self, version_ids: &[HummockVersionId]) {
+        let mut pinned: HashSet<_> = self.version_id.iter().copied().collect();
+        for &version_id in version_ids {
+            if pinned.insert(version_id) {
+                self.version_id.push(version_id);
+            }
+        }
+    }
+
+    fn unpin_versions(&mut self, version_ids: &[HummockVersionId]) {
+        let unpinned: HashSet<_> = version_ids.iter().copied().collect();
+        self.version_id
+            .retain(|version_id| !unpinned.contains(version_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_unpin_versions() {
+        let mut pinned_version = HummockPinnedVersion {
+            context_id: 1,
+            version_id: vec![],
+        };
+        pinned_version.pin_version(1);
+        pinned_version.pin_version(1);
+        pinned_version.pin_versions(&[3, 1, 2, 3, 2]);
+        assert_eq!(pinned_version.version_id, vec![1, 3, 2]);
+
+        pinned_version.unpin_version(4);
+        pinned_version.unpin_versions(&[2, 4, 2]);
+        assert_eq!(pinned_version.version_id, vec![1, 3]);
+        pinned_version.unpin_versions(&[]);
+        assert_eq!(pinned_version.version_id, vec![1, 3]);
+        pinned_version.unpin_versions(&[1, 3]);
+        assert!(pinned_version.version_id.is_empty());
+        pinned_version.unpin_version(1);
+        assert!(pinned_version.version_id.is_empty());
+    }
 }
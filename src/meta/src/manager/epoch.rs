@@ -14,32 +14,161 @@
 
 use std::fmt;
 use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use log::{error, warn};
 use parking_lot::Mutex;
+use risingwave_common::error::ErrorCode::InternalError;
+use risingwave_common::error::Result;
+use tokio::sync::{watch, Notify};
+use tokio::task::JoinHandle;
+
+use crate::storage::{self, MetaStore, DEFAULT_COLUMN_FAMILY};
 
 pub const EPOCH_PHYSICAL_SHIFT_BITS: u8 = 16;
 pub const INVALID_EPOCH: u64 = 0;
 
+/// Length in milliseconds of the physical time range reserved by each persisted lease of
+/// [`PersistentEpochGenerator`].
+pub const EPOCH_LEASE_MILLIS: u64 = 10_000;
+const EPOCH_LEASE_KEY: &str = "epoch_lease_end";
+
 #[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd, Hash, Debug)]
 pub struct Epoch(u64);
 
+/// Source of the physical part of [`Epoch`]s.
+pub trait Clock: Sync + Send + 'static {
+    /// Milliseconds elapsed since the unix epoch.
+    fn now_millis(&self) -> u64;
+}
+
+pub type ClockRef = Arc<dyn Clock>;
+
+/// [`Clock`] backed by the system wall clock, which may step backward, e.g. on NTP corrections.
+#[derive(Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    }
+}
+
+/// [`Clock`] that reads the wall clock only once on creation, and then advances with the
+/// monotonic [`Instant`], so it never goes backward even if the wall clock does.
+pub struct MonotonicClock {
+    /// Wall clock time on creation.
+    base_millis: u64,
+    base_instant: Instant,
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        Self {
+            base_millis: SystemClock.now_millis(),
+            base_instant: Instant::now(),
+        }
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now_millis(&self) -> u64 {
+        self.base_millis + self.base_instant.elapsed().as_millis() as u64
+    }
+}
+
+lazy_static! {
+    /// The clock used by [`Epoch`]s when no clock is given.
+    static ref DEFAULT_CLOCK: MonotonicClock = MonotonicClock::new();
+}
+
+/// [`Clock`] that only moves when told to, for deterministic tests.
+#[derive(Default)]
+pub struct MockClock {
+    now_millis: AtomicU64,
+}
+
+impl MockClock {
+    pub fn new(now_millis: u64) -> Self {
+        Self {
+            now_millis: AtomicU64::new(now_millis),
+        }
+    }
+
+    /// Moves the clock forward by `millis`.
+    pub fn advance(&self, millis: u64) {
+        self.now_millis.fetch_add(millis, Ordering::SeqCst);
+    }
+
+    /// Sets the clock to `now_millis`, which may also move it backward.
+    pub fn set(&self, now_millis: u64) {
+        self.now_millis.store(now_millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_millis(&self) -> u64 {
+        self.now_millis.load(Ordering::SeqCst)
+    }
+}
+
 impl Epoch {
     pub fn init() -> Self {
-        Epoch(Epoch::physical_now() << EPOCH_PHYSICAL_SHIFT_BITS)
+        Self::init_with_clock(&*DEFAULT_CLOCK)
+    }
+
+    pub fn init_with_clock(clock: &dyn Clock) -> Self {
+        Epoch(clock.now_millis() << EPOCH_PHYSICAL_SHIFT_BITS)
+    }
+
+    /// Returns the epoch at the wall clock time `millis`, with a zero logical part. It's the
+    /// smallest epoch at that time, e.g. for building scan bounds.
+    pub fn from_physical(millis: u64) -> Self {
+        Epoch(millis << EPOCH_PHYSICAL_SHIFT_BITS)
     }
 
     pub fn physical_time(&self) -> u64 {
         self.0 >> EPOCH_PHYSICAL_SHIFT_BITS
     }
 
+    /// Returns the logical part, which orders epochs within the same millisecond.
+    pub fn logical_seq(&self) -> u64 {
+        self.0 & ((1 << EPOCH_PHYSICAL_SHIFT_BITS) - 1)
+    }
+
+    /// Returns the physical time as milliseconds since the unix epoch.
+    pub fn as_unix_millis(&self) -> u64 {
+        self.physical_time()
+    }
+
+    /// Returns the physical time as a UTC timestamp.
+    pub fn to_datetime(&self) -> DateTime<Utc> {
+        (UNIX_EPOCH + Duration::from_millis(self.as_unix_millis())).into()
+    }
+
     pub fn into_inner(self) -> u64 {
         self.0
     }
 
     pub fn next(&self) -> Epoch {
-        let physical_now = Epoch::physical_now();
+        self.next_with_clock(&*DEFAULT_CLOCK)
+    }
+
+    pub fn next_with_clock(&self, clock: &dyn Clock) -> Epoch {
+        let physical_now = clock.now_millis();
         if physical_now <= self.physical_time() {
             Epoch(self.0 + 1)
         } else {
@@ -47,12 +176,8 @@ impl Epoch {
         }
     }
 
-    // TODO: use a monotonic library to replace SystemTime.
     pub fn physical_now() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64
+        DEFAULT_CLOCK.now_millis()
     }
 }
 
@@ -68,14 +193,16 @@ impl fmt::Display for Epoch {
     }
 }
 
+#[async_trait::async_trait]
 pub trait EpochGenerator: Sync + Send + 'static {
-    fn generate(&self) -> Epoch;
+    async fn generate(&self) -> Epoch;
 }
 
 pub type EpochGeneratorRef = Arc<dyn EpochGenerator>;
 
 pub struct MemEpochGenerator {
     current_epoch: Mutex<Epoch>,
+    clock: ClockRef,
 }
 
 impl Default for MemEpochGenerator {
@@ -86,32 +213,201 @@ impl Default for MemEpochGenerator {
 
 impl MemEpochGenerator {
     pub fn new() -> Self {
+        Self::with_clock(Arc::new(MonotonicClock::new()))
+    }
+
+    /// Creates a generator taking the physical time of epochs from `clock`.
+    pub fn with_clock(clock: ClockRef) -> Self {
         MemEpochGenerator {
-            current_epoch: Mutex::new(Epoch::init()),
+            current_epoch: Mutex::new(Epoch::init_with_clock(clock.as_ref())),
+            clock,
         }
     }
 }
 
+#[async_trait::async_trait]
 impl EpochGenerator for MemEpochGenerator {
-    fn generate(&self) -> Epoch {
+    async fn generate(&self) -> Epoch {
         let mut ce = self.current_epoch.lock();
-        *ce = ce.next();
+        *ce = ce.next_with_clock(self.clock.as_ref());
         *ce
     }
 }
 
+/// Interval between retries of a failed lease renewal.
+const EPOCH_LEASE_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// [`PersistentEpochGenerator`] generates epochs that keep increasing across restarts of the meta
+/// node.
+///
+/// Like [`StoredIdGenerator`](crate::manager::StoredIdGenerator), it preallocates: the end of a
+/// lease of physical time is persisted to the meta store before any epoch within it is issued, and
+/// a restarted generator starts after the persisted lease. A background task renews the lease once
+/// half of it has passed, retrying until the meta store accepts it. If the renewal falls behind,
+/// epochs stay on the end of the lease and only the logical part increases, and once that is used
+/// up too, `generate` waits for the renewal.
+pub struct PersistentEpochGenerator {
+    current_epoch: Mutex<Epoch>,
+    clock: ClockRef,
+    /// Physical time up to which epochs can be issued, which has been persisted.
+    lease_end: watch::Receiver<u64>,
+    /// Largest physical time of the issued epochs when a renewal is requested.
+    renewal_request: Arc<AtomicU64>,
+    /// Wakes up the renewal task.
+    renewal_notify: Arc<Notify>,
+    renewal_handle: JoinHandle<()>,
+}
+
+impl PersistentEpochGenerator {
+    /// Creates a generator starting after the lease persisted in `meta_store`, if any.
+    pub async fn new<S: MetaStore>(meta_store: Arc<S>, clock: ClockRef) -> Result<Self> {
+        let persisted_lease_end = match meta_store
+            .get_cf(DEFAULT_COLUMN_FAMILY, EPOCH_LEASE_KEY.as_bytes())
+            .await
+        {
+            Ok(value) => u64::from_be_bytes(value.as_slice().try_into().map_err(|_| {
+                InternalError(format!("invalid persisted epoch lease {:?}", value))
+            })?),
+            Err(storage::Error::ItemNotFound(_)) => 0,
+            Err(e) => return Err(e.into()),
+        };
+
+        // The largest epoch in the persisted lease, so that every epoch generated from now on has a
+        // greater physical time than the ones issued before the restart.
+        let current_epoch = Epoch(((persisted_lease_end + 1) << EPOCH_PHYSICAL_SHIFT_BITS) - 1);
+        let lease_end = clock.now_millis().max(persisted_lease_end + 1) + EPOCH_LEASE_MILLIS;
+        persist_lease_end(meta_store.as_ref(), lease_end).await?;
+
+        let (lease_end_tx, lease_end_rx) = watch::channel(lease_end);
+        let renewal_request = Arc::new(AtomicU64::new(0));
+        let renewal_notify = Arc::new(Notify::new());
+        let renewal_handle = tokio::spawn(renew_lease(
+            meta_store,
+            clock.clone(),
+            renewal_request.clone(),
+            renewal_notify.clone(),
+            lease_end_tx,
+        ));
+
+        Ok(Self {
+            current_epoch: Mutex::new(current_epoch),
+            clock,
+            lease_end: lease_end_rx,
+            renewal_request,
+            renewal_notify,
+            renewal_handle,
+        })
+    }
+
+    /// Asks the renewal task to extend the lease beyond `physical_time`.
+    fn request_renewal(&self, physical_time: u64) {
+        self.renewal_request
+            .fetch_max(physical_time, Ordering::SeqCst);
+        self.renewal_notify.notify_one();
+    }
+
+    /// Issues the next epoch if it fits in the lease ending at `lease_end`.
+    fn try_generate(&self, lease_end: u64) -> Option<Epoch> {
+        let mut ce = self.current_epoch.lock();
+        let mut next = ce.next_with_clock(self.clock.as_ref());
+        if next.physical_time() > lease_end {
+            next = Epoch((lease_end << EPOCH_PHYSICAL_SHIFT_BITS).max(ce.0 + 1));
+            if next.physical_time() > lease_end {
+                return None;
+            }
+        }
+        *ce = next;
+        Some(next)
+    }
+}
+
+impl Drop for PersistentEpochGenerator {
+    fn drop(&mut self) {
+        self.renewal_handle.abort();
+    }
+}
+
+#[async_trait::async_trait]
+impl EpochGenerator for PersistentEpochGenerator {
+    async fn generate(&self) -> Epoch {
+        loop {
+            let lease_end = *self.lease_end.borrow();
+            if let Some(next) = self.try_generate(lease_end) {
+                if next.physical_time() + EPOCH_LEASE_MILLIS / 2 > lease_end {
+                    self.request_renewal(next.physical_time());
+                }
+                return next;
+            }
+
+            // Every epoch in the lease has been issued.
+            warn!("epoch lease {} is used up, waiting for renewal", lease_end);
+            self.request_renewal(lease_end);
+            let mut lease_end_rx = self.lease_end.clone();
+            loop {
+                let renewed = *lease_end_rx.borrow() > lease_end;
+                if renewed {
+                    break;
+                }
+                lease_end_rx
+                    .changed()
+                    .await
+                    .expect("the renewal task lives as long as the generator");
+            }
+        }
+    }
+}
+
+async fn persist_lease_end<S: MetaStore>(meta_store: &S, lease_end: u64) -> Result<()> {
+    meta_store
+        .put_cf(
+            DEFAULT_COLUMN_FAMILY,
+            EPOCH_LEASE_KEY.as_bytes().to_vec(),
+            lease_end.to_be_bytes().to_vec(),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Body of the renewal task of [`PersistentEpochGenerator`]. On each request, it persists a lease
+/// ending [`EPOCH_LEASE_MILLIS`] after the requested physical time, and only publishes the new end
+/// once persisted. Failures are logged and retried.
+async fn renew_lease<S: MetaStore>(
+    meta_store: Arc<S>,
+    clock: ClockRef,
+    request: Arc<AtomicU64>,
+    notify: Arc<Notify>,
+    lease_end_tx: watch::Sender<u64>,
+) {
+    loop {
+        notify.notified().await;
+        let physical_time = request.load(Ordering::SeqCst);
+        let new_lease_end = (clock.now_millis().max(physical_time) + EPOCH_LEASE_MILLIS)
+            .max(*lease_end_tx.borrow());
+        while let Err(e) = persist_lease_end(meta_store.as_ref(), new_lease_end).await {
+            error!(
+                "failed to renew epoch lease to {}, retrying in {:?}: {}",
+                new_lease_end, EPOCH_LEASE_RETRY_INTERVAL, e
+            );
+            tokio::time::sleep(EPOCH_LEASE_RETRY_INTERVAL).await;
+        }
+        // The generator may have been dropped, in which case the task is going to be aborted.
+        let _ = lease_end_tx.send(new_lease_end);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use risingwave_common::error::Result;
 
     use super::*;
+    use crate::storage::MemStore;
 
-    #[test]
-    fn test_epoch_generator() -> Result<()> {
+    #[tokio::test]
+    async fn test_epoch_generator() -> Result<()> {
         let generator = MemEpochGenerator::new();
-        let mut pre = generator.generate();
+        let mut pre = generator.generate().await;
         loop {
-            let epoch = generator.generate();
+            let epoch = generator.generate().await;
             assert!(epoch > pre);
             if epoch.physical_time() > pre.physical_time() {
                 break;
@@ -120,4 +416,205 @@ mod tests {
         }
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_epoch_generator_with_mock_clock() {
+        let clock = Arc::new(MockClock::new(1000));
+        let generator = MemEpochGenerator::with_clock(clock.clone());
+
+        // Within the same millisecond, only the logical part increases.
+        let first = generator.generate().await;
+        let second = generator.generate().await;
+        assert_eq!(first.physical_time(), 1000);
+        assert_eq!(second.physical_time(), 1000);
+        assert_eq!(second.into_inner(), first.into_inner() + 1);
+
+        // Crossing a millisecond boundary resets the logical part.
+        clock.advance(1);
+        let third = generator.generate().await;
+        assert_eq!(third.physical_time(), 1001);
+        assert_eq!(third.into_inner(), 1001 << EPOCH_PHYSICAL_SHIFT_BITS);
+        assert!(third > second);
+    }
+
+    #[test]
+    fn test_epoch_decode() {
+        // 2022-04-01T00:00:00.123Z
+        let millis = 1_648_771_200_123;
+        let epoch = Epoch::from_physical(millis);
+        assert_eq!(epoch.as_unix_millis(), millis);
+        assert_eq!(epoch.logical_seq(), 0);
+        assert_eq!(
+            epoch
+                .to_datetime()
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "2022-04-01T00:00:00.123Z"
+        );
+
+        let epoch = Epoch(epoch.into_inner() + 42);
+        assert_eq!(epoch.as_unix_millis(), millis);
+        assert_eq!(epoch.logical_seq(), 42);
+        assert!(Epoch::from_physical(millis) < epoch);
+        assert!(epoch < Epoch::from_physical(millis + 1));
+    }
+
+    #[test]
+    fn test_epoch_next() {
+        let clock = MockClock::new(1000);
+        let epoch = Epoch::init_with_clock(&clock);
+
+        // Forward: adopts the new physical time.
+        clock.set(1001);
+        let forward = epoch.next_with_clock(&clock);
+        assert!(forward > epoch);
+        assert_eq!(forward.into_inner(), 1001 << EPOCH_PHYSICAL_SHIFT_BITS);
+
+        // Equal: advances the logical part.
+        let equal = forward.next_with_clock(&clock);
+        assert!(equal > forward);
+        assert_eq!(equal.physical_time(), 1001);
+
+        // Backward: keeps the physical time, and advances the logical part.
+        clock.set(900);
+        let backward = equal.next_with_clock(&clock);
+        assert!(backward > equal);
+        assert_eq!(backward.physical_time(), 1001);
+        assert_eq!(backward.into_inner(), equal.into_inner() + 1);
+    }
+
+    #[tokio::test]
+    async fn test_epoch_generator_with_clock_going_backward() {
+        let clock = Arc::new(MockClock::new(1000));
+        let generator = MemEpochGenerator::with_clock(clock.clone());
+
+        let mut pre = generator.generate().await;
+        for now_millis in [1005, 500, 1010, 0, 1011] {
+            clock.set(now_millis);
+            for _ in 0..3 {
+                let epoch = generator.generate().await;
+                assert!(epoch > pre);
+                assert!(epoch.physical_time() >= pre.physical_time());
+                pre = epoch;
+            }
+        }
+        // The physical time catches up once the clock passes the latest epoch again.
+        assert_eq!(pre.physical_time(), 1011);
+    }
+
+    #[test]
+    fn test_monotonic_clock() {
+        let clock = MonotonicClock::new();
+        let mut pre = clock.now_millis();
+        assert!(pre.abs_diff(SystemClock.now_millis()) < 1000);
+        for _ in 0..100 {
+            let now = clock.now_millis();
+            assert!(now >= pre);
+            pre = now;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_persistent_epoch_generator_restart() {
+        let meta_store = Arc::new(MemStore::default());
+        let clock = Arc::new(MockClock::new(1000));
+        let generator = PersistentEpochGenerator::new(meta_store.clone(), clock.clone())
+            .await
+            .unwrap();
+        let mut pre = generator.generate().await;
+        for _ in 0..10 {
+            clock.advance(1);
+            let epoch = generator.generate().await;
+            assert!(epoch > pre);
+            pre = epoch;
+        }
+        drop(generator);
+
+        // The restarted generator must not regress, even if the clock went backward.
+        clock.set(500);
+        let generator = PersistentEpochGenerator::new(meta_store.clone(), clock.clone())
+            .await
+            .unwrap();
+        let epoch = generator.generate().await;
+        assert!(epoch > pre);
+        assert!(epoch.physical_time() > 1000 + EPOCH_LEASE_MILLIS);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_epoch_generator_lease() {
+        let meta_store = Arc::new(MemStore::default());
+        let clock = Arc::new(MockClock::new(1000));
+        let generator = PersistentEpochGenerator::new(meta_store.clone(), clock.clone())
+            .await
+            .unwrap();
+        let lease_end = 1000 + EPOCH_LEASE_MILLIS;
+
+        // Beyond the lease, epochs stay on the end of it until the lease is renewed.
+        clock.set(lease_end + 100);
+        let first = generator.generate().await;
+        let second = generator.generate().await;
+        assert_eq!(first.physical_time(), lease_end);
+        assert!(second > first);
+
+        // The renewal is persisted before epochs in the new lease are issued.
+        while *generator.lease_end.borrow() == lease_end {
+            tokio::task::yield_now().await;
+        }
+        let persisted = meta_store
+            .get_cf(DEFAULT_COLUMN_FAMILY, EPOCH_LEASE_KEY.as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(
+            u64::from_be_bytes(persisted.as_slice().try_into().unwrap()),
+            lease_end + 100 + EPOCH_LEASE_MILLIS
+        );
+        let third = generator.generate().await;
+        assert_eq!(third.physical_time(), lease_end + 100);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_epoch_generator_lease_used_up() {
+        let meta_store = Arc::new(MemStore::default());
+        let clock = Arc::new(MockClock::new(1000));
+        let generator = PersistentEpochGenerator::new(meta_store.clone(), clock.clone())
+            .await
+            .unwrap();
+        let lease_end = 1000 + EPOCH_LEASE_MILLIS;
+
+        // Use up the logical part of the last millisecond of the lease without giving the renewal
+        // task a chance to run.
+        clock.set(lease_end + 100);
+        let mut pre = generator.try_generate(lease_end).unwrap();
+        while let Some(epoch) = generator.try_generate(lease_end) {
+            pre = epoch;
+        }
+        assert_eq!(pre.physical_time(), lease_end);
+
+        // The next epoch waits for the renewal instead of exceeding the lease.
+        let epoch = generator.generate().await;
+        assert!(epoch > pre);
+        assert_eq!(epoch.physical_time(), lease_end + 100);
+        let persisted = meta_store
+            .get_cf(DEFAULT_COLUMN_FAMILY, EPOCH_LEASE_KEY.as_bytes())
+            .await
+            .unwrap();
+        assert!(u64::from_be_bytes(persisted.as_slice().try_into().unwrap()) >= lease_end + 100);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_epoch_generator_invalid_lease() {
+        let meta_store = Arc::new(MemStore::default());
+        meta_store
+            .put_cf(
+                DEFAULT_COLUMN_FAMILY,
+                EPOCH_LEASE_KEY.as_bytes().to_vec(),
+                vec![1, 2, 3],
+            )
+            .await
+            .unwrap();
+        assert!(
+            PersistentEpochGenerator::new(meta_store, Arc::new(MockClock::new(1000)))
+                .await
+                .is_err()
+        );
+    }
 }
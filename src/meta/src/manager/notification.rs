@@ -141,7 +141,7 @@ impl NotificationManagerCore {
     }
 
     async fn notify_frontend(&mut self, operation: Operation, info: &Info) -> Epoch {
-        let epoch = self.epoch_generator.generate();
+        let epoch = self.epoch_generator.generate().await;
         let mut keys = HashSet::new();
         for (worker_key, sender) in &self.frontend_senders {
             loop {
@@ -172,7 +172,7 @@ impl NotificationManagerCore {
 
     /// Send a `SubscribeResponse` to backend.
     async fn notify_compute(&mut self, operation: Operation, info: &Info) -> Epoch {
-        let epoch = self.epoch_generator.generate();
+        let epoch = self.epoch_generator.generate().await;
         let mut keys = HashSet::new();
         for (worker_key, sender) in &self.compute_senders {
             loop {
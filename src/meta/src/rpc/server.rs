@@ -39,7 +39,8 @@ use crate::cluster::ClusterManager;
 use crate::dashboard::DashboardService;
 use crate::hummock;
 use crate::manager::{
-    CatalogManager, MemEpochGenerator, MetaOpts, MetaSrvEnv, StoredCatalogManager,
+    CatalogManager, MetaOpts, MetaSrvEnv, MonotonicClock, PersistentEpochGenerator,
+    StoredCatalogManager,
 };
 use crate::rpc::metrics::MetaMetrics;
 use crate::rpc::service::catalog_service::CatalogServiceImpl;
@@ -115,7 +116,11 @@ pub async fn rpc_serve_with_store<S: MetaStore>(
     opts: MetaOpts,
 ) -> (JoinHandle<()>, UnboundedSender<()>) {
     let listener = TcpListener::bind(addr).await.unwrap();
-    let epoch_generator = Arc::new(MemEpochGenerator::new());
+    let epoch_generator = Arc::new(
+        PersistentEpochGenerator::new(meta_store.clone(), Arc::new(MonotonicClock::new()))
+            .await
+            .unwrap(),
+    );
     let env = MetaSrvEnv::<S>::new(opts, meta_store.clone(), epoch_generator.clone()).await;
 
     let fragment_manager = Arc::new(FragmentManager::new(meta_store.clone()).await.unwrap());
@@ -39,7 +39,7 @@ impl EpochService for EpochServiceImpl {
         let _req = request.into_inner();
         Ok(Response::new(GetEpochResponse {
             status: None,
-            epoch: self.epoch_generator.generate().into_inner(),
+            epoch: self.epoch_generator.generate().await.into_inner(),
         }))
     }
 }
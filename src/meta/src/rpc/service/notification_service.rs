@@ -97,7 +97,7 @@ where
                     status: None,
                     operation: Operation::Snapshot as i32,
                     info: Some(Info::FeSnapshot(meta_snapshot)),
-                    version: self.env.epoch_generator().generate().into_inner(),
+                    version: self.env.epoch_generator().generate().await.into_inner(),
                 }))
                 .unwrap();
                 self.env
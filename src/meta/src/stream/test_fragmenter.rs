@@ -94,6 +94,7 @@ fn make_column_order(idx: i32) -> ColumnOrder {
             type_name: TypeName::Int64 as i32,
             ..Default::default()
         }),
+        nulls_first: false,
     }
 }
 
@@ -57,7 +57,9 @@ pub use self::task_configure_minio::*;
 pub use self::task_etcd_ready_check::*;
 pub use self::zookeeper_service::*;
 use crate::util::{complete_spin, get_program_args, get_program_name};
-use crate::wait_tcp::{wait_http, wait_http_with_cb, wait_tcp, wait_tcp_available};
+use crate::wait_tcp::{
+    wait_http, wait_http_contains, wait_http_with_cb, wait_tcp, wait_tcp_any, wait_tcp_available,
+};
 
 pub trait Task: 'static + Send {
     /// Execute the task
@@ -167,6 +169,20 @@ where
         Ok(())
     }
 
+    pub fn wait_tcp_any<S: AsRef<str>>(
+        &mut self,
+        servers: impl IntoIterator<Item = S>,
+    ) -> anyhow::Result<()> {
+        wait_tcp_any(
+            servers,
+            &mut self.log,
+            self.status_file.as_ref().unwrap(),
+            self.id.as_ref().unwrap(),
+            Some(Duration::from_secs(30)),
+            true,
+        )
+    }
+
     pub fn wait_http(&mut self, server: impl AsRef<str>) -> anyhow::Result<()> {
         wait_http(
             server,
@@ -178,6 +194,22 @@ where
         )
     }
 
+    pub fn wait_http_contains(
+        &mut self,
+        server: impl AsRef<str>,
+        expected: &str,
+    ) -> anyhow::Result<()> {
+        wait_http_contains(
+            server,
+            &mut self.log,
+            self.status_file.as_ref().unwrap(),
+            self.id.as_ref().unwrap(),
+            Some(Duration::from_secs(30)),
+            true,
+            expected,
+        )
+    }
+
     pub fn wait_http_with_cb(
         &mut self,
         server: impl AsRef<str>,
@@ -13,8 +13,9 @@
 // limitations under the License.
 
 use std::io::Read;
-use std::net::TcpStream;
+use std::net::{SocketAddr, TcpStream};
 use std::path::Path;
+use std::sync::mpsc;
 use std::thread::sleep;
 use std::time::Duration;
 
@@ -22,6 +23,44 @@ use anyhow::anyhow;
 use console::style;
 use isahc::prelude::*;
 use isahc::{Body, Request};
+use itertools::Itertools;
+
+/// Exponential backoff between attempts to connect to a service. The delay starts at
+/// [`Backoff::INITIAL_DELAY`], and doubles up to [`Backoff::MAX_DELAY`].
+struct Backoff {
+    delay: Duration,
+    /// Number of attempts since the delay reached [`Backoff::MAX_DELAY`].
+    attempts_at_max_delay: usize,
+}
+
+impl Backoff {
+    const INITIAL_DELAY: Duration = Duration::from_millis(50);
+    const MAX_DELAY: Duration = Duration::from_secs(1);
+    /// Once the delay is capped, progress is reported every this many attempts.
+    const REPORT_INTERVAL: usize = 10;
+
+    fn new() -> Self {
+        Self {
+            delay: Self::INITIAL_DELAY,
+            attempts_at_max_delay: 0,
+        }
+    }
+
+    /// Returns the delay before the next attempt, and whether to report the progress of waiting,
+    /// which is done on every new tier of delay, and every [`Backoff::REPORT_INTERVAL`] attempts
+    /// after the delay is capped.
+    fn next_delay(&mut self) -> (Duration, bool) {
+        let delay = self.delay;
+        if delay < Self::MAX_DELAY {
+            self.delay = (delay * 2).min(Self::MAX_DELAY);
+            (delay, true)
+        } else {
+            let report = self.attempts_at_max_delay % Self::REPORT_INTERVAL == 0;
+            self.attempts_at_max_delay += 1;
+            (delay, report)
+        }
+    }
+}
 
 pub fn wait_tcp(
     server: impl AsRef<str>,
@@ -31,28 +70,67 @@ pub fn wait_tcp(
     timeout: Option<std::time::Duration>,
     detect_failure: bool,
 ) -> anyhow::Result<()> {
-    let server = server.as_ref();
+    wait_tcp_any([server], f, p, id, timeout, detect_failure)
+}
+
+/// Wait until any of `servers` accepts connections. In each attempt, all servers are connected
+/// at the same time, and it returns as soon as one of them is connected.
+pub fn wait_tcp_any<S: AsRef<str>>(
+    servers: impl IntoIterator<Item = S>,
+    f: &mut impl std::io::Write,
+    p: impl AsRef<Path>,
+    id: &str,
+    timeout: Option<std::time::Duration>,
+    detect_failure: bool,
+) -> anyhow::Result<()> {
+    let servers = servers
+        .into_iter()
+        .map(|server| server.as_ref().to_string())
+        .collect_vec();
+    let addrs = servers
+        .iter()
+        .map(|server| server.parse::<SocketAddr>())
+        .collect::<Result<Vec<_>, _>>()?;
+    if addrs.is_empty() {
+        return Err(anyhow!("no server to wait for"));
+    }
     let p = p.as_ref();
-    let addr = server.parse()?;
     let start_time = std::time::Instant::now();
 
-    writeln!(f, "Waiting for online: {}", server)?;
+    writeln!(f, "Waiting for online: {}", servers.join(", "))?;
 
-    let mut last_error;
+    let mut last_errors = servers.iter().map(|_| None).collect_vec();
+    let mut backoff = Backoff::new();
 
     loop {
-        match TcpStream::connect_timeout(&addr, Duration::from_secs(1)) {
-            Ok(_) => {
-                return Ok(());
-            }
-            Err(err) => {
-                last_error = Some(err);
+        let (tx, rx) = mpsc::channel();
+        for (idx, addr) in addrs.iter().copied().enumerate() {
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                let _ = tx.send((
+                    idx,
+                    TcpStream::connect_timeout(&addr, Duration::from_secs(1)),
+                ));
+            });
+        }
+        drop(tx);
+        for (idx, result) in rx {
+            match result {
+                Ok(_) => {
+                    return Ok(());
+                }
+                Err(err) => {
+                    last_errors[idx] = Some(err);
+                }
             }
         }
 
         if let Some(ref timeout) = timeout {
             if std::time::Instant::now() - start_time >= *timeout {
-                return Err(anyhow!("failed to connect, last error: {:?}", last_error));
+                return Err(anyhow!(
+                    "failed to connect, last errors: {}",
+                    format_last_errors(&servers, &last_errors)
+                ));
             }
         }
 
@@ -67,10 +145,29 @@ pub fn wait_tcp(
             ));
         }
 
-        sleep(Duration::from_millis(30));
+        let (delay, report) = backoff.next_delay();
+        if report {
+            writeln!(
+                f,
+                "Still waiting for {} after {:?}, retrying in {:?}, last errors: {}",
+                servers.join(", "),
+                start_time.elapsed(),
+                delay,
+                format_last_errors(&servers, &last_errors)
+            )?;
+        }
+        sleep(delay);
     }
 }
 
+fn format_last_errors(servers: &[String], last_errors: &[Option<std::io::Error>]) -> String {
+    servers
+        .iter()
+        .zip_eq(last_errors)
+        .map(|(server, err)| format!("{}: {:?}", server, err))
+        .join(", ")
+}
+
 pub fn wait_http_with_cb(
     server: impl AsRef<str>,
     f: &mut impl std::io::Write,
@@ -87,6 +184,7 @@ pub fn wait_http_with_cb(
     writeln!(f, "Waiting for online: {}", server)?;
 
     let mut last_error;
+    let mut backoff = Backoff::new();
 
     loop {
         match Request::get(server)
@@ -123,12 +221,23 @@ pub fn wait_http_with_cb(
             std::fs::File::open(p)?.read_to_string(&mut buf)?;
             return Err(anyhow!(
                 "{} exited while waiting for connection: {}",
-                id,
+                style(id).red().bold(),
                 buf
             ));
         }
 
-        sleep(Duration::from_millis(30));
+        let (delay, report) = backoff.next_delay();
+        if report {
+            writeln!(
+                f,
+                "Still waiting for {} after {:?}, retrying in {:?}, last error: {:?}",
+                server,
+                start_time.elapsed(),
+                delay,
+                last_error
+            )?;
+        }
+        sleep(delay);
     }
 }
 
@@ -144,6 +253,22 @@ pub fn wait_http(
     wait_http_with_cb(server, f, p, id, timeout, detect_failure, |_| true)
 }
 
+/// Wait until `server` responds with a success status, and a body containing `expected`.
+pub fn wait_http_contains(
+    server: impl AsRef<str>,
+    f: &mut impl std::io::Write,
+    p: impl AsRef<Path>,
+    id: &str,
+    timeout: Option<std::time::Duration>,
+    detect_failure: bool,
+    expected: &str,
+) -> anyhow::Result<()> {
+    wait_http_with_cb(server, f, p, id, timeout, detect_failure, |mut body| {
+        let mut buf = String::new();
+        body.read_to_string(&mut buf).is_ok() && buf.contains(expected)
+    })
+}
+
 pub fn wait_tcp_available(
     server: impl AsRef<str>,
     timeout: Option<std::time::Duration>,
@@ -169,3 +294,187 @@ pub fn wait_tcp_available(
         sleep(Duration::from_millis(50));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let mut backoff = Backoff::new();
+        let delays = (0..6).map(|_| backoff.next_delay()).collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            [50, 100, 200, 400, 800, 1000]
+                .into_iter()
+                .map(|millis| (Duration::from_millis(millis), true))
+                .collect::<Vec<_>>()
+        );
+        let reports = (0..20).filter(|_| backoff.next_delay().1).count();
+        assert_eq!(reports, 2);
+    }
+
+    /// Starts an HTTP server, which responds with 503 until `ready_after`, and then 200 with
+    /// `body`.
+    fn start_http_server(ready_after: Duration, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let start_time = Instant::now();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                // Read the request header.
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let (status, body) = if start_time.elapsed() < ready_after {
+                    ("503 Service Unavailable", "")
+                } else {
+                    ("200 OK", body)
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://{}/", addr)
+    }
+
+    #[test]
+    fn test_wait_http() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = Vec::new();
+
+        let server = start_http_server(Duration::from_millis(200), "ready");
+        let start_time = Instant::now();
+        wait_http(
+            &server,
+            &mut log,
+            dir.path().join("status"),
+            "test",
+            Some(Duration::from_secs(5)),
+            true,
+        )
+        .unwrap();
+        assert!(start_time.elapsed() >= Duration::from_millis(200));
+        wait_http_contains(
+            &server,
+            &mut log,
+            dir.path().join("status"),
+            "test",
+            Some(Duration::from_secs(5)),
+            true,
+            "ready",
+        )
+        .unwrap();
+
+        // Times out if the body never contains the expected text.
+        assert!(wait_http_contains(
+            &server,
+            &mut log,
+            dir.path().join("status"),
+            "test",
+            Some(Duration::from_millis(200)),
+            true,
+            "healthy",
+        )
+        .is_err());
+
+        // Fails at once if the process exits.
+        std::fs::write(dir.path().join("status"), "exited").unwrap();
+        let server = start_http_server(Duration::from_secs(60), "ready");
+        let err = wait_http(
+            &server,
+            &mut log,
+            dir.path().join("status"),
+            "test",
+            None,
+            true,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("exited"));
+    }
+
+    #[test]
+    fn test_wait_tcp_with_backoff() {
+        // Find a free port, and start listening on it after a while.
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let server = std::thread::spawn(move || {
+            sleep(Duration::from_millis(200));
+            let listener = TcpListener::bind(addr).unwrap();
+            listener.accept().unwrap();
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = Vec::new();
+        let start_time = Instant::now();
+        wait_tcp(
+            addr.to_string(),
+            &mut log,
+            dir.path().join("status"),
+            "test",
+            Some(Duration::from_secs(5)),
+            true,
+        )
+        .unwrap();
+        assert!(start_time.elapsed() < Duration::from_millis(600));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_wait_tcp_any() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = Vec::new();
+        let dead = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let live_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let live = live_listener.local_addr().unwrap().to_string();
+
+        let start_time = Instant::now();
+        wait_tcp_any(
+            [&dead, &live],
+            &mut log,
+            dir.path().join("status"),
+            "test",
+            Some(Duration::from_secs(5)),
+            true,
+        )
+        .unwrap();
+        assert!(start_time.elapsed() < Duration::from_secs(1));
+
+        // All addresses and their errors are reported on timeout.
+        drop(live_listener);
+        let err = wait_tcp_any(
+            [&dead, &live],
+            &mut log,
+            dir.path().join("status"),
+            "test",
+            Some(Duration::from_millis(200)),
+            true,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains(&format!("{}: Some(", dead)));
+        assert!(err.contains(&format!("{}: Some(", live)));
+    }
+}
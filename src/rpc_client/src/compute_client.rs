@@ -13,7 +13,8 @@
 // limitations under the License.
 
 use std::fmt::{Debug, Formatter};
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use futures::StreamExt;
 use log::trace;
@@ -21,6 +22,7 @@ use risingwave_common::array::DataChunk;
 use risingwave_common::error::ErrorCode::InternalError;
 use risingwave_common::error::{Result, ToRwResult};
 use risingwave_common::util::addr::HostAddr;
+use risingwave_pb::data::StreamMessage;
 use risingwave_pb::plan::exchange_info::DistributionMode;
 use risingwave_pb::plan::{ExchangeInfo, PlanFragment, PlanNode, TaskId, TaskOutputId};
 use risingwave_pb::task_service::exchange_service_client::ExchangeServiceClient;
@@ -29,9 +31,15 @@ use risingwave_pb::task_service::{
     CreateTaskRequest, CreateTaskResponse, GetDataRequest, GetDataResponse, GetStreamRequest,
     GetStreamResponse,
 };
+use tokio_retry::strategy::ExponentialBackoff;
 use tonic::transport::{Channel, Endpoint};
 use tonic::Streaming;
 
+use crate::{ComputeClientPool, ExchangeMetrics};
+
+/// Timeout of connecting to a compute node, unless specified otherwise.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Clone)]
 pub struct ComputeClient {
     pub exchange_client: ExchangeServiceClient<Channel>,
@@ -41,9 +49,23 @@ pub struct ComputeClient {
 
 impl ComputeClient {
     pub async fn new(addr: HostAddr) -> Result<Self> {
-        let channel = Endpoint::from_shared(format!("http://{}", &addr))
+        Self::with_timeout(addr, DEFAULT_CONNECT_TIMEOUT, None).await
+    }
+
+    /// Connects to `addr` within `connect_timeout`. If `request_timeout` is set, each request
+    /// fails if no response arrives within it.
+    pub async fn with_timeout(
+        addr: HostAddr,
+        connect_timeout: Duration,
+        request_timeout: Option<Duration>,
+    ) -> Result<Self> {
+        let mut endpoint = Endpoint::from_shared(format!("http://{}", &addr))
             .map_err(|e| InternalError(format!("{}", e)))?
-            .connect_timeout(Duration::from_secs(5))
+            .connect_timeout(connect_timeout);
+        if let Some(request_timeout) = request_timeout {
+            endpoint = endpoint.timeout(request_timeout);
+        }
+        let channel = endpoint
             .connect()
             .await
             .to_rw_result_with(|| format!("failed to connect to {}", &addr))?;
@@ -62,6 +84,7 @@ impl ComputeClient {
             stream,
             task_id: output_id.get_task_id().unwrap().clone(),
             output_id,
+            metrics: None,
         })
     }
 
@@ -157,12 +180,104 @@ pub struct GrpcExchangeSource {
 
     output_id: TaskOutputId,
     task_id: TaskId,
+
+    /// Metrics of the received data, labeled by the address of the upstream node.
+    metrics: Option<(Arc<ExchangeMetrics>, String)>,
+}
+
+/// Retry and timeout policy of creating a [`GrpcExchangeSource`], so that a query survives a
+/// briefly unavailable upstream node.
+#[derive(Clone, Copy, Debug)]
+pub struct ExchangeRetryConfig {
+    /// Number of attempts in total, including the first one.
+    pub max_attempts: usize,
+    /// Delay before the first retry, which doubles on each further retry.
+    pub base_delay: Duration,
+    /// Timeout of connecting to the upstream node in each attempt.
+    pub connect_timeout: Duration,
+    /// Deadline of the `get_data` request in each attempt, if any.
+    pub request_timeout: Option<Duration>,
+}
+
+impl ExchangeRetryConfig {
+    /// Delays between attempts, which are `base_delay * 2^n`.
+    fn backoff(&self) -> impl Iterator<Item = Duration> {
+        ExponentialBackoff::from_millis(2)
+            .factor(self.base_delay.as_millis() as u64 / 2)
+            .take(self.max_attempts.saturating_sub(1))
+    }
+}
+
+impl Default for ExchangeRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: None,
+        }
+    }
 }
 
 impl GrpcExchangeSource {
-    pub async fn create(addr: HostAddr, output_id: TaskOutputId) -> Result<Self> {
-        let client = ComputeClient::new(addr).await?;
-        client.get_data(output_id).await
+    pub async fn create(
+        addr: HostAddr,
+        output_id: TaskOutputId,
+        metrics: Arc<ExchangeMetrics>,
+    ) -> Result<Self> {
+        Self::create_with_retry(addr, output_id, ExchangeRetryConfig::default(), metrics).await
+    }
+
+    /// Connects to `addr` and requests the data of `output_id`, retrying with exponential backoff
+    /// on failure. The error of the last attempt is returned if all attempts fail.
+    pub async fn create_with_retry(
+        addr: HostAddr,
+        output_id: TaskOutputId,
+        config: ExchangeRetryConfig,
+        metrics: Arc<ExchangeMetrics>,
+    ) -> Result<Self> {
+        let source = tokio_retry::Retry::spawn(config.backoff(), || async {
+            let client = ComputeClient::with_timeout(
+                addr.clone(),
+                config.connect_timeout,
+                config.request_timeout,
+            )
+            .await?;
+            client.get_data(output_id.clone()).await
+        })
+        .await?;
+        Ok(source.with_metrics(metrics, &addr))
+    }
+
+    /// Like [`create_with_retry`](Self::create_with_retry), but shares the client of `addr` in
+    /// `pool` with other sources. The client is evicted from the pool on failure, so that the next
+    /// attempt reconnects instead of reusing a broken connection.
+    ///
+    /// Clients are created with the timeouts of `pool` rather than those of `config`.
+    pub async fn create_from_pool(
+        pool: &ComputeClientPool,
+        addr: HostAddr,
+        output_id: TaskOutputId,
+        config: ExchangeRetryConfig,
+        metrics: Arc<ExchangeMetrics>,
+    ) -> Result<Self> {
+        let source = tokio_retry::Retry::spawn(config.backoff(), || async {
+            let client = pool.get_client_for_addr(addr.clone()).await?;
+            match client.get_data(output_id.clone()).await {
+                Ok(source) => Ok(source),
+                Err(e) => {
+                    pool.invalidate(&addr).await;
+                    Err(e)
+                }
+            }
+        })
+        .await?;
+        Ok(source.with_metrics(metrics, &addr))
+    }
+
+    fn with_metrics(mut self, metrics: Arc<ExchangeMetrics>, addr: &HostAddr) -> Self {
+        self.metrics = Some((metrics, addr.to_string()));
+        self
     }
 }
 
@@ -177,12 +292,21 @@ impl Debug for GrpcExchangeSource {
 #[async_trait::async_trait]
 impl ExchangeSource for GrpcExchangeSource {
     async fn take_data(&mut self) -> Result<Option<DataChunk>> {
+        let start_time = Instant::now();
         let res = match self.stream.next().await {
-            None => return Ok(None),
+            None => {
+                if let Some((metrics, addr)) = &self.metrics {
+                    metrics.report_take_data(addr, None, start_time.elapsed());
+                }
+                return Ok(None);
+            }
             Some(r) => r,
         };
         let task_data = res.to_rw_result()?;
         let data = DataChunk::from_protobuf(task_data.get_record_batch()?)?.compact()?;
+        if let Some((metrics, addr)) = &self.metrics {
+            metrics.report_take_data(addr, Some(&data), start_time.elapsed());
+        }
 
         trace!(
             "Receiver task: {:?}, output = {:?}, data = {:?}",
@@ -194,3 +318,61 @@ impl ExchangeSource for GrpcExchangeSource {
         Ok(Some(data))
     }
 }
+
+/// Use grpc client as the source of a streaming exchange, which receives the messages sent from
+/// the actors of the upstream fragment to the downstream fragment.
+pub struct GrpcStreamSource {
+    stream: Streaming<GetStreamResponse>,
+
+    up_fragment_id: u32,
+    down_fragment_id: u32,
+}
+
+impl GrpcStreamSource {
+    pub async fn create(
+        addr: HostAddr,
+        up_fragment_id: u32,
+        down_fragment_id: u32,
+    ) -> Result<Self> {
+        let client = ComputeClient::new(addr).await?;
+        let stream = client.get_stream(up_fragment_id, down_fragment_id).await?;
+        Ok(Self {
+            stream,
+            up_fragment_id,
+            down_fragment_id,
+        })
+    }
+
+    /// Takes the next message, which is either a stream chunk or a barrier. Returns `None` once
+    /// the upstream ends the stream.
+    pub async fn take_message(&mut self) -> Result<Option<StreamMessage>> {
+        let res = match self.stream.next().await {
+            None => return Ok(None),
+            Some(r) => r,
+        };
+        let message = res.to_rw_result()?.message.ok_or_else(|| {
+            InternalError(format!(
+                "no message in stream response from fragment {} to fragment {}",
+                self.up_fragment_id, self.down_fragment_id
+            ))
+        })?;
+
+        trace!(
+            "Receiver fragment: {}, upstream fragment: {}, message = {:?}",
+            self.down_fragment_id,
+            self.up_fragment_id,
+            message
+        );
+
+        Ok(Some(message))
+    }
+}
+
+impl Debug for GrpcStreamSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GrpcStreamSource")
+            .field("up_fragment_id", &self.up_fragment_id)
+            .field("down_fragment_id", &self.down_fragment_id)
+            .finish()
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use moka::future::Cache;
+use risingwave_common::error::ErrorCode::InternalError;
+use risingwave_common::error::Result;
+use risingwave_common::util::addr::HostAddr;
+
+use crate::compute_client::DEFAULT_CONNECT_TIMEOUT;
+use crate::ComputeClient;
+
+/// [`ComputeClientPool`] caches a [`ComputeClient`] for each address, so that all requests to the
+/// same compute node are multiplexed over one HTTP/2 connection.
+#[derive(Clone)]
+pub struct ComputeClientPool {
+    cache: Cache<HostAddr, ComputeClient>,
+    connect_timeout: Duration,
+    request_timeout: Option<Duration>,
+}
+
+impl ComputeClientPool {
+    pub fn new(cache_capacity: u64) -> Self {
+        Self::with_timeout(cache_capacity, DEFAULT_CONNECT_TIMEOUT, None)
+    }
+
+    /// Creates a pool whose clients are created by [`ComputeClient::with_timeout`].
+    pub fn with_timeout(
+        cache_capacity: u64,
+        connect_timeout: Duration,
+        request_timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            cache: Cache::new(cache_capacity),
+            connect_timeout,
+            request_timeout,
+        }
+    }
+
+    /// Get a compute client from the pool.
+    pub async fn get_client_for_addr(&self, addr: HostAddr) -> Result<ComputeClient> {
+        self.cache
+            .try_get_with(addr.clone(), async {
+                ComputeClient::with_timeout(addr, self.connect_timeout, self.request_timeout).await
+            })
+            .await
+            .map_err(|e| InternalError(format!("failed to create compute client: {}", e)).into())
+    }
+
+    /// Evicts the client of `addr`, e.g. after its connection breaks, so that the next
+    /// [`get_client_for_addr`](Self::get_client_for_addr) reconnects.
+    pub async fn invalidate(&self, addr: &HostAddr) {
+        self.cache.invalidate(addr).await;
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use prometheus::{
+    histogram_opts, register_histogram_vec_with_registry, register_int_counter_vec_with_registry,
+    HistogramVec, IntCounterVec, Registry, DEFAULT_BUCKETS,
+};
+use risingwave_common::array::DataChunk;
+
+/// Data transferred by [`ExchangeSource`](crate::ExchangeSource)s, labeled by the source, i.e. the
+/// address of a remote source or the task output of a local one.
+pub struct ExchangeMetrics {
+    /// num of chunks received
+    pub exchange_recv_chunk_counts: IntCounterVec,
+    /// num of rows received
+    pub exchange_recv_row_counts: IntCounterVec,
+    /// latency of each `take_data`
+    pub exchange_take_data_duration: HistogramVec,
+}
+
+impl ExchangeMetrics {
+    pub fn new(registry: Registry) -> Self {
+        let exchange_recv_chunk_counts = register_int_counter_vec_with_registry!(
+            "batch_exchange_recv_chunk_counts",
+            "Total number of chunks received by exchange sources",
+            &["source"],
+            registry
+        )
+        .unwrap();
+
+        let exchange_recv_row_counts = register_int_counter_vec_with_registry!(
+            "batch_exchange_recv_row_counts",
+            "Total number of rows received by exchange sources",
+            &["source"],
+            registry
+        )
+        .unwrap();
+
+        let opts = histogram_opts!(
+            "batch_exchange_take_data_duration",
+            "Time spent waiting for the next chunk of exchange sources",
+            DEFAULT_BUCKETS.to_vec()
+        );
+        let exchange_take_data_duration =
+            register_histogram_vec_with_registry!(opts, &["source"], registry).unwrap();
+
+        Self {
+            exchange_recv_chunk_counts,
+            exchange_recv_row_counts,
+            exchange_take_data_duration,
+        }
+    }
+
+    /// Create a new `ExchangeMetrics` instance used in tests or other places.
+    pub fn unused() -> Self {
+        Self::new(Registry::new())
+    }
+
+    /// Records a `take_data` of `source` that took `duration` and returned `chunk`.
+    pub fn report_take_data(&self, source: &str, chunk: Option<&DataChunk>, duration: Duration) {
+        self.exchange_take_data_duration
+            .with_label_values(&[source])
+            .observe(duration.as_secs_f64());
+        if let Some(chunk) = chunk {
+            self.exchange_recv_chunk_counts
+                .with_label_values(&[source])
+                .inc();
+            self.exchange_recv_row_counts
+                .with_label_values(&[source])
+                .inc_by(chunk.cardinality() as u64);
+        }
+    }
+}
@@ -30,6 +30,12 @@
 mod meta_client;
 pub use meta_client::{GrpcMetaClient, MetaClient, NotificationStream};
 mod compute_client;
-pub use compute_client::{ComputeClient, ExchangeSource, GrpcExchangeSource};
+mod compute_client_pool;
+mod exchange_metrics;
+pub use compute_client::{
+    ComputeClient, ExchangeRetryConfig, ExchangeSource, GrpcExchangeSource, GrpcStreamSource,
+};
+pub use compute_client_pool::ComputeClientPool;
+pub use exchange_metrics::ExchangeMetrics;
 mod hummock_meta_client;
 pub use hummock_meta_client::HummockMetaClient;
@@ -75,6 +75,7 @@ impl ConnectorSource {
                 let mut events = Vec::with_capacity(batch.len());
                 for msg in batch {
                     if let Some(content) = msg.payload {
+                        self.parser.prepare(content.deref()).await?;
                         events.push(self.parser.parse(content.deref(), &self.column_descs)?);
                     }
                 }
@@ -331,6 +331,7 @@ impl StreamSourceReader for HighLevelKafkaSourceStreamReader {
                 for msg in batch {
                     let msg = msg.map_err(|e| RwError::from(InternalError(e.to_string())))?;
                     if let Some(payload) = msg.payload() {
+                        self.parser.prepare(payload).await?;
                         events.push(self.parser.parse(payload, &self.columns)?);
                     }
                 }
@@ -435,6 +436,7 @@ impl BatchSourceReader for HighLevelKafkaSourceBatchReader {
                         }
 
                         if let Some(payload) = msg.payload() {
+                            self.parser.prepare(payload).await?;
                             events.push(self.parser.parse(payload, &self.columns)?);
                         }
 
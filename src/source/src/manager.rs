@@ -30,8 +30,8 @@ use risingwave_pb::catalog::{RowFormatType, StreamSourceInfo};
 use crate::connector_source::ConnectorSource;
 use crate::table_v2::TableSourceV2;
 use crate::{
-    DebeziumJsonParser, HighLevelKafkaSource, JSONParser, ProtobufParser, SourceConfig,
-    SourceFormat, SourceImpl, SourceParser,
+    AvroParser, ConfluentSchemaRegistry, DebeziumJsonParser, HighLevelKafkaSource, JSONParser,
+    ProtobufParser, SourceConfig, SourceFormat, SourceImpl, SourceParser,
 };
 
 pub type SourceRef = Arc<SourceImpl>;
@@ -344,6 +344,12 @@ fn build_source_parser(
             let parser: Arc<dyn SourceParser + Send + Sync> = Arc::new(DebeziumJsonParser {});
             Ok(parser)
         }
+        SourceFormat::Avro => {
+            let registry = ConfluentSchemaRegistry::from_properties(properties)?;
+            let parser: Arc<dyn SourceParser + Send + Sync> =
+                Arc::new(AvroParser::new(Arc::new(registry)));
+            Ok(parser)
+        }
         _ => Err(RwError::from(InternalError(
             "format not support".to_string(),
         ))),
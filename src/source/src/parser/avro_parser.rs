@@ -0,0 +1,372 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use apache_avro::types::Value;
+use apache_avro::{from_avro_datum, Schema};
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use risingwave_common::array::Op;
+use risingwave_common::error::ErrorCode::{InternalError, ProtocolError};
+use risingwave_common::error::{Result, RwError};
+use risingwave_common::types::{
+    DataType, Datum, NaiveDateTimeWrapper, NaiveDateWrapper, OrderedF32, OrderedF64, ScalarImpl,
+};
+
+use super::schema_registry::SchemaRegistry;
+use crate::{Event, SourceColumnDesc, SourceParser};
+
+/// Magic byte leading every message framed by the Confluent serializers.
+const CONFLUENT_MAGIC_BYTE: u8 = 0;
+
+/// Parser for Avro records framed in the Confluent wire format, i.e. a zero magic byte, followed
+/// by the 4-byte big-endian id of the writer schema in the schema registry, and the Avro binary
+/// encoding of the record.
+///
+/// Writer schemas are fetched from the schema registry in [`SourceParser::prepare`] on their first
+/// occurrence, and cached afterwards. Record fields are mapped to columns by name, and a column
+/// without a corresponding field is null.
+#[derive(Debug)]
+pub struct AvroParser {
+    registry: Arc<dyn SchemaRegistry>,
+    schemas: RwLock<HashMap<i32, Arc<Schema>>>,
+}
+
+impl AvroParser {
+    pub fn new(registry: Arc<dyn SchemaRegistry>) -> Self {
+        Self {
+            registry,
+            schemas: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+/// Returns the id of the writer schema in the header of `payload`.
+fn schema_id(payload: &[u8]) -> Result<i32> {
+    if payload.len() < 5 || payload[0] != CONFLUENT_MAGIC_BYTE {
+        return Err(RwError::from(ProtocolError(
+            "avro message does not start with the confluent magic byte".to_string(),
+        )));
+    }
+    Ok(i32::from_be_bytes(payload[1..5].try_into().unwrap()))
+}
+
+#[async_trait]
+impl SourceParser for AvroParser {
+    async fn prepare(&self, payload: &[u8]) -> Result<()> {
+        let id = schema_id(payload)?;
+        let cached = self.schemas.read().contains_key(&id);
+        if cached {
+            return Ok(());
+        }
+
+        let text = self.registry.get_schema(id).await?;
+        let schema = Schema::parse_str(&text)
+            .map_err(|e| RwError::from(ProtocolError(format!("invalid avro schema: {}", e))))?;
+        self.schemas.write().insert(id, Arc::new(schema));
+        Ok(())
+    }
+
+    fn parse(&self, payload: &[u8], columns: &[SourceColumnDesc]) -> Result<Event> {
+        let id = schema_id(payload)?;
+        let schema = self.schemas.read().get(&id).cloned().ok_or_else(|| {
+            RwError::from(InternalError(format!(
+                "avro schema {} is not prepared before parsing",
+                id
+            )))
+        })?;
+
+        let mut datum = &payload[5..];
+        let fields = match from_avro_datum(&schema, &mut datum, None) {
+            Ok(Value::Record(fields)) => fields,
+            Ok(_) => {
+                return Err(RwError::from(ProtocolError(format!(
+                    "avro schema {} is not a record",
+                    id
+                ))))
+            }
+            Err(e) => {
+                return Err(RwError::from(ProtocolError(format!(
+                    "failed to decode avro message: {}",
+                    e
+                ))))
+            }
+        };
+
+        let row = columns
+            .iter()
+            .map(|column| {
+                if column.skip_parse {
+                    return Ok(None);
+                }
+                match fields.iter().find(|(name, _)| *name == column.name) {
+                    Some((_, value)) => avro_value_to_datum(column, value),
+                    None => Ok(None),
+                }
+            })
+            .collect::<Result<Vec<Datum>>>()?;
+
+        Ok(Event {
+            ops: vec![Op::Insert],
+            rows: vec![row],
+        })
+    }
+}
+
+fn avro_value_to_datum(column: &SourceColumnDesc, value: &Value) -> Result<Datum> {
+    let mismatch = || {
+        RwError::from(ProtocolError(format!(
+            "avro value {:?} can't be converted to {:?} for column {}",
+            value, column.data_type, column.name
+        )))
+    };
+    let timestamp = |micros: i64| {
+        NaiveDateTimeWrapper::new_with_secs_nsecs(
+            micros.div_euclid(1_000_000),
+            (micros.rem_euclid(1_000_000) * 1000) as u32,
+        )
+        .map_err(|_| mismatch())
+    };
+
+    let scalar = match (&column.data_type, value) {
+        (_, Value::Null) => return Ok(None),
+        (_, Value::Union(.., inner)) => return avro_value_to_datum(column, inner),
+        (DataType::Boolean, Value::Boolean(v)) => ScalarImpl::Bool(*v),
+        (DataType::Int16, Value::Int(v)) => {
+            ScalarImpl::Int16(i16::try_from(*v).map_err(|_| mismatch())?)
+        }
+        (DataType::Int32, Value::Int(v)) => ScalarImpl::Int32(*v),
+        (DataType::Int64, Value::Int(v)) => ScalarImpl::Int64(*v as i64),
+        (DataType::Int64, Value::Long(v)) => ScalarImpl::Int64(*v),
+        (DataType::Float32, Value::Float(v)) => ScalarImpl::Float32(OrderedF32::from(*v)),
+        (DataType::Float64, Value::Float(v)) => ScalarImpl::Float64(OrderedF64::from(*v as f64)),
+        (DataType::Float64, Value::Double(v)) => ScalarImpl::Float64(OrderedF64::from(*v)),
+        (DataType::Varchar, Value::String(v)) => ScalarImpl::Utf8(v.clone()),
+        (DataType::Varchar, Value::Enum(_, symbol)) => ScalarImpl::Utf8(symbol.clone()),
+        (DataType::Date, Value::Date(days)) => {
+            ScalarImpl::NaiveDate(NaiveDateWrapper::from_protobuf(*days)?)
+        }
+        (DataType::Timestamp, Value::TimestampMillis(millis)) => {
+            ScalarImpl::NaiveDateTime(timestamp(millis.checked_mul(1000).ok_or_else(mismatch)?)?)
+        }
+        (DataType::Timestamp, Value::TimestampMicros(micros)) => {
+            ScalarImpl::NaiveDateTime(timestamp(*micros)?)
+        }
+        _ => return Err(mismatch()),
+    };
+    Ok(Some(scalar))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use risingwave_common::catalog::ColumnId;
+
+    use super::*;
+    use crate::parser::common::str_to_date;
+
+    #[derive(Debug, Default)]
+    struct MockSchemaRegistry {
+        schemas: HashMap<i32, String>,
+        requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SchemaRegistry for MockSchemaRegistry {
+        async fn get_schema(&self, id: i32) -> Result<String> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            self.schemas
+                .get(&id)
+                .cloned()
+                .ok_or_else(|| RwError::from(ProtocolError(format!("schema {} not found", id))))
+        }
+    }
+
+    fn header(id: i32) -> Vec<u8> {
+        let mut payload = vec![CONFLUENT_MAGIC_BYTE];
+        payload.extend(id.to_be_bytes());
+        payload
+    }
+
+    fn encode_long(n: i64, buf: &mut Vec<u8>) {
+        let mut n = ((n << 1) ^ (n >> 63)) as u64;
+        while n >= 0x80 {
+            buf.push((n as u8) | 0x80);
+            n >>= 7;
+        }
+        buf.push(n as u8);
+    }
+
+    fn encode_str(s: &str, buf: &mut Vec<u8>) {
+        encode_long(s.len() as i64, buf);
+        buf.extend(s.as_bytes());
+    }
+
+    fn column(name: &str, data_type: DataType, column_id: i32) -> SourceColumnDesc {
+        SourceColumnDesc {
+            name: name.to_string(),
+            data_type,
+            column_id: ColumnId::from(column_id),
+            skip_parse: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_avro_parser() {
+        let schema = r#"{
+            "type": "record",
+            "name": "user",
+            "fields": [
+                {"name": "id", "type": "int"},
+                {"name": "name", "type": "string"},
+                {"name": "score", "type": ["null", "double"]},
+                {"name": "birthday", "type": {"type": "int", "logicalType": "date"}},
+                {"name": "created_at", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+                {"name": "ignored", "type": "boolean"}
+            ]
+        }"#;
+        let registry = Arc::new(MockSchemaRegistry {
+            schemas: HashMap::from([(7, schema.to_string())]),
+            ..Default::default()
+        });
+        let parser = AvroParser::new(registry.clone());
+        let columns = vec![
+            column("id", DataType::Int64, 0),
+            column("name", DataType::Varchar, 1),
+            column("score", DataType::Float64, 2),
+            column("birthday", DataType::Date, 3),
+            column("created_at", DataType::Timestamp, 4),
+            column("missing", DataType::Int32, 5),
+        ];
+
+        let mut payload = header(7);
+        encode_long(-42, &mut payload);
+        encode_str("abc", &mut payload);
+        encode_long(1, &mut payload);
+        payload.extend(1.5f64.to_le_bytes());
+        encode_long(18628, &mut payload);
+        encode_long(1_000_000_123, &mut payload);
+        payload.push(1);
+
+        // Schemas are only fetched when preparing.
+        assert!(parser.parse(&payload, &columns).is_err());
+        assert_eq!(registry.requests.load(Ordering::SeqCst), 0);
+        parser.prepare(&payload).await.unwrap();
+        let event = parser.parse(&payload, &columns).unwrap();
+        assert_eq!(event.ops, vec![Op::Insert]);
+        assert_eq!(
+            event.rows,
+            vec![vec![
+                Some(ScalarImpl::Int64(-42)),
+                Some(ScalarImpl::Utf8("abc".to_string())),
+                Some(ScalarImpl::Float64(1.5.into())),
+                Some(ScalarImpl::NaiveDate(str_to_date("2021-01-01").unwrap())),
+                Some(ScalarImpl::NaiveDateTime(
+                    NaiveDateTimeWrapper::new_with_secs_nsecs(1_000_000, 123_000_000).unwrap()
+                )),
+                None,
+            ]]
+        );
+
+        // The null branch of the union, and the schema is served from the cache.
+        let mut payload = header(7);
+        encode_long(1, &mut payload);
+        encode_long(0, &mut payload);
+        encode_long(0, &mut payload);
+        encode_long(0, &mut payload);
+        encode_long(0, &mut payload);
+        payload.push(0);
+        parser.prepare(&payload).await.unwrap();
+        let event = parser.parse(&payload, &columns).unwrap();
+        assert_eq!(event.rows[0][1], Some(ScalarImpl::Utf8("".to_string())));
+        assert_eq!(event.rows[0][2], None);
+        assert_eq!(registry.requests.load(Ordering::SeqCst), 1);
+
+        // Truncated message.
+        assert!(parser.parse(&payload[..8], &columns).is_err());
+        // Unknown schema id.
+        payload[4] = 8;
+        assert!(parser.prepare(&payload).await.is_err());
+        assert!(parser.parse(&payload, &columns).is_err());
+        // Missing magic byte.
+        payload[0] = 1;
+        assert!(parser.prepare(&payload).await.is_err());
+        assert!(parser.parse(&payload, &columns).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_avro_complex_types() {
+        let schema = r#"{
+            "type": "record",
+            "name": "event",
+            "namespace": "test",
+            "fields": [
+                {"name": "kind", "type": {"type": "enum", "name": "kind", "symbols": ["CREATE", "DELETE"]}},
+                {"name": "tags", "type": {"type": "array", "items": "string"}},
+                {"name": "attrs", "type": {"type": "map", "values": "long"}},
+                {"name": "hash", "type": {"type": "fixed", "name": "md5", "size": 4}},
+                {"name": "origin", "type": {"type": "record", "name": "point", "fields": [{"name": "x", "type": "int"}]}},
+                {"name": "target", "type": ["null", "point"]},
+                {"name": "name", "type": "string"}
+            ]
+        }"#;
+        let parser = AvroParser::new(Arc::new(MockSchemaRegistry {
+            schemas: HashMap::from([(9, schema.to_string())]),
+            ..Default::default()
+        }));
+        let columns = vec![
+            column("kind", DataType::Varchar, 0),
+            column("name", DataType::Varchar, 1),
+        ];
+
+        let mut payload = header(9);
+        // kind: DELETE
+        encode_long(1, &mut payload);
+        // tags: ["a"]
+        encode_long(1, &mut payload);
+        encode_str("a", &mut payload);
+        encode_long(0, &mut payload);
+        // attrs: {"k": 5}
+        encode_long(1, &mut payload);
+        encode_str("k", &mut payload);
+        encode_long(5, &mut payload);
+        encode_long(0, &mut payload);
+        // hash
+        payload.extend([1, 2, 3, 4]);
+        // origin: {x: 3}
+        encode_long(3, &mut payload);
+        // target: the point branch, {x: 4}
+        encode_long(1, &mut payload);
+        encode_long(4, &mut payload);
+        // name
+        encode_str("n", &mut payload);
+
+        parser.prepare(&payload).await.unwrap();
+        let event = parser.parse(&payload, &columns).unwrap();
+        assert_eq!(
+            event.rows,
+            vec![vec![
+                Some(ScalarImpl::Utf8("DELETE".to_string())),
+                Some(ScalarImpl::Utf8("n".to_string())),
+            ]]
+        );
+
+        // Complex values can't be converted to scalar columns.
+        let columns = vec![column("tags", DataType::Varchar, 0)];
+        assert!(parser.parse(&payload, &columns).is_err());
+    }
+}
@@ -14,19 +14,24 @@
 
 use std::fmt::Debug;
 
+use async_trait::async_trait;
+pub use avro_parser::*;
 pub use debezium::*;
 pub use json_parser::*;
 pub use protobuf_parser::*;
 use risingwave_common::array::Op;
 use risingwave_common::error::Result;
 use risingwave_common::types::Datum;
+pub use schema_registry::*;
 
 use crate::SourceColumnDesc;
 
+mod avro_parser;
 mod common;
 mod debezium;
 mod json_parser;
 mod protobuf_parser;
+mod schema_registry;
 
 #[derive(Debug, Default)]
 pub struct Event {
@@ -38,7 +43,14 @@ pub struct Event {
 /// one by one through `SourceParser` and assemble them into `DataChunk`
 /// Note that the `skip_parse` parameter in `SourceColumnDesc`, when it is true, should skip the
 /// parse and return `Datum` of `None`
+#[async_trait]
 pub trait SourceParser: Send + Sync + Debug + 'static {
+    /// Does the async work needed before `payload` can be parsed, e.g. fetching its schema.
+    /// Callers must call it before [`SourceParser::parse`] on each payload.
+    async fn prepare(&self, _payload: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
     /// parse needs to be a member method because some format like Protobuf needs to be pre-compiled
     fn parse(&self, payload: &[u8], columns: &[SourceColumnDesc]) -> Result<Event>;
 }
@@ -0,0 +1,264 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use hyper::client::HttpConnector;
+use hyper::header::{ACCEPT, AUTHORIZATION, LOCATION};
+use hyper::{Body, Client, Request, Response};
+use hyper_tls::HttpsConnector;
+use risingwave_common::error::ErrorCode::{InternalError, ProtocolError};
+use risingwave_common::error::{Result, RwError};
+use url::Url;
+
+pub const SCHEMA_REGISTRY_URL_KEY: &str = "schema.registry.url";
+pub const SCHEMA_REGISTRY_USERNAME_KEY: &str = "schema.registry.username";
+pub const SCHEMA_REGISTRY_PASSWORD_KEY: &str = "schema.registry.password";
+
+const SCHEMA_REGISTRY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Max number of redirects followed by one request.
+const SCHEMA_REGISTRY_MAX_REDIRECTS: usize = 5;
+
+/// Looks up writer schemas by the id embedded in each message.
+#[async_trait]
+pub trait SchemaRegistry: Send + Sync + Debug + 'static {
+    /// Returns the schema registered with `id`, in its original text form.
+    async fn get_schema(&self, id: i32) -> Result<String>;
+}
+
+/// Client of the [Confluent Schema Registry](https://docs.confluent.io/platform/current/schema-registry/develop/api.html).
+#[derive(Debug)]
+pub struct ConfluentSchemaRegistry {
+    client: Client<HttpsConnector<HttpConnector>>,
+    url: Url,
+    /// Username and password for basic authentication.
+    credential: Option<(String, String)>,
+}
+
+impl ConfluentSchemaRegistry {
+    pub fn new(url: &str, credential: Option<(String, String)>) -> Result<Self> {
+        let url = Url::parse(url)
+            .map_err(|e| InternalError(format!("failed to parse url ({}): {}", url, e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(RwError::from(ProtocolError(format!(
+                "schema registry scheme {} is not supported",
+                url.scheme()
+            ))));
+        }
+        Ok(Self {
+            client: Client::builder().build(HttpsConnector::new()),
+            url,
+            credential,
+        })
+    }
+
+    /// Creates a client from the source properties.
+    pub fn from_properties(properties: &HashMap<String, String>) -> Result<Self> {
+        let url = properties.get(SCHEMA_REGISTRY_URL_KEY).ok_or_else(|| {
+            RwError::from(ProtocolError(format!(
+                "{} not found in properties",
+                SCHEMA_REGISTRY_URL_KEY
+            )))
+        })?;
+        let credential = match (
+            properties.get(SCHEMA_REGISTRY_USERNAME_KEY),
+            properties.get(SCHEMA_REGISTRY_PASSWORD_KEY),
+        ) {
+            (Some(username), Some(password)) => Some((username.clone(), password.clone())),
+            (None, None) => None,
+            _ => {
+                return Err(RwError::from(ProtocolError(format!(
+                    "{} and {} must be provided together",
+                    SCHEMA_REGISTRY_USERNAME_KEY, SCHEMA_REGISTRY_PASSWORD_KEY
+                ))))
+            }
+        };
+        Self::new(url, credential)
+    }
+
+    /// Sends a GET request for `path` and follows redirects, returning the body of the final
+    /// successful response.
+    async fn get(&self, path: &str) -> Result<Bytes> {
+        let mut url = self
+            .url
+            .join(path)
+            .map_err(|e| InternalError(format!("failed to build url for {}: {}", path, e)))?;
+        for _ in 0..=SCHEMA_REGISTRY_MAX_REDIRECTS {
+            let response = tokio::time::timeout(SCHEMA_REGISTRY_TIMEOUT, self.request(&url))
+                .await
+                .map_err(|_| {
+                    RwError::from(ProtocolError(format!(
+                        "request to schema registry {} timed out",
+                        url
+                    )))
+                })??;
+            let status = response.status();
+            if status.is_redirection() {
+                let location = response
+                    .headers()
+                    .get(LOCATION)
+                    .and_then(|location| location.to_str().ok())
+                    .ok_or_else(|| {
+                        RwError::from(ProtocolError(format!(
+                            "schema registry {} redirected without a location",
+                            url
+                        )))
+                    })?;
+                url = url.join(location).map_err(|e| {
+                    ProtocolError(format!("invalid redirect location {}: {}", location, e))
+                })?;
+                continue;
+            }
+            if !status.is_success() {
+                return Err(RwError::from(ProtocolError(format!(
+                    "schema registry {} responded with {}: {}",
+                    url,
+                    status,
+                    String::from_utf8_lossy(response.body())
+                ))));
+            }
+            return Ok(response.into_body());
+        }
+        Err(RwError::from(ProtocolError(format!(
+            "too many redirects from schema registry {}",
+            self.url
+        ))))
+    }
+
+    /// Sends a single GET request to `url` and reads the whole response.
+    async fn request(&self, url: &Url) -> Result<Response<Bytes>> {
+        let mut request =
+            Request::get(url.as_str()).header(ACCEPT, "application/vnd.schemaregistry.v1+json");
+        if let Some((username, password)) = &self.credential {
+            request = request.header(
+                AUTHORIZATION,
+                format!(
+                    "Basic {}",
+                    base64::encode(format!("{}:{}", username, password))
+                ),
+            );
+        }
+        let request = request
+            .body(Body::empty())
+            .map_err(|e| InternalError(format!("failed to build request to {}: {}", url, e)))?;
+
+        let request_err = |e: hyper::Error| {
+            RwError::from(ProtocolError(format!(
+                "failed to request schema registry {}: {}",
+                url, e
+            )))
+        };
+        let (parts, body) = self
+            .client
+            .request(request)
+            .await
+            .map_err(request_err)?
+            .into_parts();
+        let body = hyper::body::to_bytes(body).await.map_err(request_err)?;
+        Ok(Response::from_parts(parts, body))
+    }
+}
+
+#[async_trait]
+impl SchemaRegistry for ConfluentSchemaRegistry {
+    async fn get_schema(&self, id: i32) -> Result<String> {
+        let body = self.get(&format!("schemas/ids/{}", id)).await?;
+        let response: serde_json::Value = serde_json::from_slice(&body)
+            .map_err(|e| RwError::from(ProtocolError(e.to_string())))?;
+        response
+            .get("schema")
+            .and_then(|schema| schema.as_str())
+            .map(ToString::to_string)
+            .ok_or_else(|| {
+                RwError::from(ProtocolError(format!(
+                    "schema {} not found in schema registry response",
+                    id
+                )))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Server, StatusCode};
+    use maplit::hashmap;
+
+    use super::*;
+
+    #[test]
+    fn test_from_properties() {
+        let registry = ConfluentSchemaRegistry::from_properties(&hashmap! {
+            SCHEMA_REGISTRY_URL_KEY.to_string() => "http://localhost:8081".to_string(),
+        })
+        .unwrap();
+        assert!(registry.credential.is_none());
+
+        assert!(ConfluentSchemaRegistry::from_properties(&hashmap! {
+            SCHEMA_REGISTRY_URL_KEY.to_string() => "http://localhost:8081".to_string(),
+            SCHEMA_REGISTRY_USERNAME_KEY.to_string() => "user".to_string(),
+        })
+        .is_err());
+        assert!(ConfluentSchemaRegistry::from_properties(&HashMap::new()).is_err());
+        assert!(ConfluentSchemaRegistry::new("https://localhost:8081", None).is_ok());
+        assert!(ConfluentSchemaRegistry::new("ftp://localhost:8081", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_schema() {
+        let make_service = make_service_fn(|_| async {
+            Ok::<_, hyper::Error>(service_fn(|request: Request<Body>| async move {
+                let authorized = request
+                    .headers()
+                    .get(AUTHORIZATION)
+                    .map(|value| value.as_bytes())
+                    == Some(b"Basic dXNlcjpwYXNz");
+                let response = match request.uri().path() {
+                    "/old/schemas/ids/1" => Response::builder()
+                        .status(StatusCode::MOVED_PERMANENTLY)
+                        .header(LOCATION, "/schemas/ids/1")
+                        .body(Body::empty()),
+                    "/schemas/ids/1" if authorized => {
+                        Response::builder().body(Body::from(r#"{"schema": "\"int\""}"#))
+                    }
+                    "/schemas/ids/1" => Response::builder()
+                        .status(StatusCode::UNAUTHORIZED)
+                        .body(Body::empty()),
+                    _ => Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(Body::from(r#"{"error_code": 40403}"#)),
+                };
+                Ok::<_, hyper::Error>(response.unwrap())
+            }))
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        let registry = ConfluentSchemaRegistry::new(
+            &format!("http://{}/old/", addr),
+            Some(("user".to_string(), "pass".to_string())),
+        )
+        .unwrap();
+        assert_eq!(registry.get_schema(1).await.unwrap(), "\"int\"");
+        assert!(registry.get_schema(2).await.is_err());
+
+        let registry = ConfluentSchemaRegistry::new(&format!("http://{}/", addr), None).unwrap();
+        assert!(registry.get_schema(1).await.is_err());
+    }
+}
@@ -12,13 +12,84 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use bytes::{BufMut, Bytes, BytesMut};
+use std::fmt;
+
+use bytes::{BufMut, Bytes};
 use risingwave_common::catalog::TableId;
 use risingwave_hummock_sdk::key::next_key;
 
 use crate::error::StorageResult;
+use crate::storage_value::StorageValue;
 use crate::StateStore;
 
+/// A part of the prefix of a [`Keyspace`]. Segments are only kept for introspection, and the
+/// prefix is always the concatenation of their encodings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// Root shared by all executors of an operator, displayed as `s/<id>`.
+    SharedExecutor(u64),
+    /// Root of an executor, displayed as `e/<id>`.
+    Executor(u64),
+    /// Root of a table, displayed as `t/<id>`.
+    Table(u32),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    /// A string in memcomparable encoding.
+    Str(String),
+    /// Raw bytes, displayed in hex.
+    Bytes(Vec<u8>),
+}
+
+impl Segment {
+    /// Appends the encoding of the segment to `buf`.
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            Self::SharedExecutor(id) => {
+                buf.put_u8(b's');
+                buf.put_u64(*id);
+            }
+            Self::Executor(id) => {
+                buf.put_u8(b'e');
+                buf.put_u64(*id);
+            }
+            Self::Table(id) => {
+                buf.put_u8(b't');
+                buf.put_u32(*id);
+            }
+            Self::U8(val) => buf.put_u8(*val),
+            Self::U16(val) => buf.put_u16(*val),
+            Self::U32(val) => buf.put_u32(*val),
+            Self::U64(val) => buf.put_u64(*val),
+            Self::Str(val) => {
+                buf.append(&mut memcomparable::to_vec(val).expect("failed to serialize string"))
+            }
+            Self::Bytes(bytes) => buf.extend_from_slice(bytes),
+        }
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SharedExecutor(id) => write!(f, "s/{}", id),
+            Self::Executor(id) => write!(f, "e/{}", id),
+            Self::Table(id) => write!(f, "t/{}", id),
+            Self::U8(val) => write!(f, "{}", val),
+            Self::U16(val) => write!(f, "{}", val),
+            Self::U32(val) => write!(f, "{}", val),
+            Self::U64(val) => write!(f, "{}", val),
+            Self::Str(val) => write!(f, "{:?}", val),
+            Self::Bytes(bytes) => write!(f, "0x{}", to_hex(bytes)),
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 /// Provides API to read key-value pairs of a prefix in the storage backend.
 #[derive(Clone)]
 pub struct Keyspace<S: StateStore> {
@@ -26,6 +97,9 @@ pub struct Keyspace<S: StateStore> {
 
     /// Encoded representation for all segments.
     prefix: Vec<u8>,
+
+    /// Segments producing the prefix, for introspection.
+    segments: Vec<Segment>,
 }
 
 impl<S: StateStore> Keyspace<S> {
@@ -38,56 +112,100 @@ impl<S: StateStore> Keyspace<S> {
     /// Note: when using shared keyspace, be caution to scan the keyspace since states of other
     /// executors might be scanned as well.
     pub fn shared_executor_root(store: S, operator_id: u64) -> Self {
-        let prefix = {
-            let mut buf = BytesMut::with_capacity(9);
-            buf.put_u8(b's');
-            buf.put_u64(operator_id);
-            buf.to_vec()
-        };
-        Self { store, prefix }
+        Self::root(store, Segment::SharedExecutor(operator_id))
+    }
+
+    /// Creates a root [`Keyspace`] for one parallel fragment of an operator.
+    ///
+    /// The returned keyspace is nested under [`Keyspace::shared_executor_root`] of the operator,
+    /// so different fragments of the same operator never overlap with each other.
+    pub fn fragment_root(store: S, operator_id: u64, fragment_id: u32) -> Self {
+        Self::shared_executor_root(store, operator_id).append_u32(fragment_id)
     }
 
     /// Creates a root [`Keyspace`] for an executor.
     pub fn executor_root(store: S, executor_id: u64) -> Self {
-        let prefix = {
-            let mut buf = BytesMut::with_capacity(9);
-            buf.put_u8(b'e');
-            buf.put_u64(executor_id);
-            buf.to_vec()
-        };
-        Self { store, prefix }
+        Self::root(store, Segment::Executor(executor_id))
     }
 
     /// Creates a root [`Keyspace`] for a table.
     pub fn table_root(store: S, id: &TableId) -> Self {
-        let prefix = {
-            let mut buf = BytesMut::with_capacity(5);
-            buf.put_u8(b't');
-            buf.put_u32(id.table_id);
-            buf.to_vec()
-        };
-        Self { store, prefix }
+        Self::root(store, Segment::Table(id.table_id))
     }
 
-    /// Appends more bytes to the prefix and returns a new `Keyspace`
+    fn root(store: S, segment: Segment) -> Self {
+        let mut prefix = Vec::new();
+        segment.encode(&mut prefix);
+        Self {
+            store,
+            prefix,
+            segments: vec![segment],
+        }
+    }
+
+    /// Appends the encoding of `segment` to the prefix and returns a new `Keyspace`
     #[must_use]
-    pub fn append(&self, mut bytes: Vec<u8>) -> Self {
+    pub fn with_segment(&self, segment: Segment) -> Self {
         let mut prefix = self.prefix.clone();
-        prefix.append(&mut bytes);
+        segment.encode(&mut prefix);
+        let mut segments = self.segments.clone();
+        segments.push(segment);
         Self {
             store: self.store.clone(),
             prefix,
+            segments,
         }
     }
 
+    /// Appends more bytes to the prefix and returns a new `Keyspace`
+    #[must_use]
+    pub fn append(&self, bytes: Vec<u8>) -> Self {
+        self.with_segment(Segment::Bytes(bytes))
+    }
+
     #[must_use]
     pub fn append_u8(&self, val: u8) -> Self {
-        self.append(val.to_be_bytes().to_vec())
+        self.with_segment(Segment::U8(val))
     }
 
     #[must_use]
     pub fn append_u16(&self, val: u16) -> Self {
-        self.append(val.to_be_bytes().to_vec())
+        self.with_segment(Segment::U16(val))
+    }
+
+    #[must_use]
+    pub fn append_u32(&self, val: u32) -> Self {
+        self.with_segment(Segment::U32(val))
+    }
+
+    #[must_use]
+    pub fn append_u64(&self, val: u64) -> Self {
+        self.with_segment(Segment::U64(val))
+    }
+
+    /// Appends a signed integer with its sign bit flipped, so that the byte order of the encoded
+    /// prefixes matches the numeric order. The segment is recorded as the flipped unsigned integer.
+    #[must_use]
+    pub fn append_i32(&self, val: i32) -> Self {
+        self.append_u32(val as u32 ^ (1 << 31))
+    }
+
+    /// See [`Keyspace::append_i32`].
+    #[must_use]
+    pub fn append_i64(&self, val: i64) -> Self {
+        self.append_u64(val as u64 ^ (1 << 63))
+    }
+
+    #[must_use]
+    pub fn append_bool(&self, val: bool) -> Self {
+        self.append_u8(val as u8)
+    }
+
+    /// Appends a variable-length string in memcomparable encoding, so that no appended string is
+    /// a prefix of another one, and the encoded prefixes sort the same as the strings.
+    #[must_use]
+    pub fn append_str(&self, val: &str) -> Self {
+        self.with_segment(Segment::Str(val.to_owned()))
     }
 
     /// Treats the keyspace as a single key, and returns the key.
@@ -95,6 +213,16 @@ impl<S: StateStore> Keyspace<S> {
         &self.prefix
     }
 
+    /// Returns the segments producing the prefix, from the root.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns the prefix in lowercase hex, for debugging.
+    pub fn prefix_hex(&self) -> String {
+        to_hex(&self.prefix)
+    }
+
     /// Treats the keyspace as a single key, and gets its value.
     /// The returned value is based on a snapshot corresponding to the given `epoch`
     pub async fn value(&self, epoch: u64) -> StorageResult<Option<Bytes>> {
@@ -112,6 +240,40 @@ impl<S: StateStore> Keyspace<S> {
         self.store.get(&self.prefixed_key(key), epoch).await
     }
 
+    /// Gets the values of the `prefixed_key` of each given key in a single batch. The returned
+    /// values line up with `keys`, with `None` for keys not found.
+    /// The returned values are based on a snapshot corresponding to the given `epoch`
+    pub async fn multi_get(
+        &self,
+        keys: &[impl AsRef<[u8]>],
+        epoch: u64,
+    ) -> StorageResult<Vec<Option<Bytes>>> {
+        let keys = keys.iter().map(|key| self.prefixed_key(key)).collect();
+        self.store.multi_get(keys, epoch).await
+    }
+
+    /// Counts the keys in the keyspace, without reading their values.
+    /// The result is based on a snapshot corresponding to the given `epoch`
+    pub async fn count(&self, epoch: u64) -> StorageResult<usize> {
+        self.store.count_prefix(self.prefix.clone(), epoch).await
+    }
+
+    /// Checks whether there is any key in the keyspace, without reading its value.
+    /// The result is based on a snapshot corresponding to the given `epoch`
+    pub async fn exists(&self, epoch: u64) -> StorageResult<bool> {
+        self.store.prefix_exists(self.prefix.clone(), epoch).await
+    }
+
+    /// Checks whether the `prefixed_key` of given key exists in the keyspace.
+    /// The result is based on a snapshot corresponding to the given `epoch`
+    ///
+    /// This is a point get that drops the value. `prefix_exists` can't be used because it also
+    /// matches longer keys, and a point get, unlike a range check, can skip tables by their bloom
+    /// filters, which outweighs reading one value.
+    pub async fn contains(&self, key: impl AsRef<[u8]>, epoch: u64) -> StorageResult<bool> {
+        Ok(self.get(key, epoch).await?.is_some())
+    }
+
     /// Scans `limit` keys from the keyspace and get their values. If `limit` is None, all keys of
     /// the given prefix will be scanned.
     /// The returned values are based on a snapshot corresponding to the given `epoch`
@@ -124,6 +286,18 @@ impl<S: StateStore> Keyspace<S> {
         self.store.scan(range, limit, epoch).await
     }
 
+    /// Scans `limit` keys from the keyspace in descending key order and get their values. If
+    /// `limit` is None, all keys of the given prefix will be scanned.
+    /// The returned values are based on a snapshot corresponding to the given `epoch`
+    pub async fn scan_reverse(
+        &self,
+        limit: Option<usize>,
+        epoch: u64,
+    ) -> StorageResult<Vec<(Bytes, Bytes)>> {
+        let range = self.prefix.to_owned()..next_key(self.prefix.as_slice());
+        self.store.reverse_scan(range, limit, epoch).await
+    }
+
     /// Scans `limit` keys from the keyspace using an inclusive `start_key` and get their values. If
     /// `limit` is None, all keys of the given prefix will be scanned.
     /// The returned values are based on a snapshot corresponding to the given `epoch`
@@ -143,6 +317,32 @@ impl<S: StateStore> Keyspace<S> {
         self.store.scan(range, limit, epoch).await
     }
 
+    /// Scans `limit` keys within `[start, end)` of the keyspace and get their values, where both
+    /// bounds are relative to the prefix of this keyspace. A `None` bound extends to the start or
+    /// end of the keyspace. If `limit` is None, all keys in the range will be scanned.
+    /// The returned values are based on a snapshot corresponding to the given `epoch`
+    pub async fn scan_range(
+        &self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        limit: Option<usize>,
+        epoch: u64,
+    ) -> StorageResult<Vec<(Bytes, Bytes)>> {
+        let start = match start {
+            Some(start) => self.prefixed_key(start),
+            None => self.prefix.to_owned(),
+        };
+        let end = match end {
+            Some(end) => self.prefixed_key(end),
+            None => next_key(self.prefix.as_slice()),
+        };
+        // Bail out early on an empty range, which some state stores don't accept.
+        if start >= end {
+            return Ok(vec![]);
+        }
+        self.store.scan(start..end, limit, epoch).await
+    }
+
     /// Scans from the keyspace, and then strips the prefix of this keyspace.
     /// The returned values are based on a snapshot corresponding to the given `epoch`
     ///
@@ -166,8 +366,355 @@ impl<S: StateStore> Keyspace<S> {
         self.store.iter(range, epoch).await
     }
 
+    /// Deletes the `prefixed_key` of given key from the keyspace at the given `epoch`.
+    pub async fn delete(&self, key: impl AsRef<[u8]>, epoch: u64) -> StorageResult<()> {
+        let key = Bytes::from(self.prefixed_key(key));
+        self.store
+            .ingest_batch(vec![(key, StorageValue::new_default_delete())], epoch)
+            .await
+    }
+
+    /// Deletes all keys of this keyspace at the given `epoch`, by writing a tombstone for every key
+    /// visible in the snapshot of `epoch`.
+    ///
+    /// Only keys starting with the full prefix are deleted, so a sibling keyspace sharing some
+    /// leading bytes with this one is not affected.
+    pub async fn delete_prefix(&self, epoch: u64) -> StorageResult<()> {
+        let tombstones = self
+            .scan(None, epoch)
+            .await?
+            .into_iter()
+            .map(|(key, _)| (key, StorageValue::new_default_delete()))
+            .collect::<Vec<_>>();
+        if tombstones.is_empty() {
+            return Ok(());
+        }
+        self.store.ingest_batch(tombstones, epoch).await
+    }
+
     /// Gets the underlying state store.
     pub fn state_store(&self) -> S {
         self.store.clone()
     }
 }
+
+/// Displays the segments of the keyspace as a path, like `e/1/2/"name"`.
+impl<S: StateStore> fmt::Display for Keyspace<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            write!(f, "{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::memory::MemoryStateStore;
+
+    #[test]
+    fn test_fragment_root() {
+        let store = MemoryStateStore::new();
+        let operator = Keyspace::shared_executor_root(store.clone(), 233);
+        let fragment_1 = Keyspace::fragment_root(store.clone(), 233, 1);
+        let fragment_2 = Keyspace::fragment_root(store.clone(), 233, 2);
+
+        assert!(fragment_1.key().starts_with(operator.key()));
+        assert!(fragment_2.key().starts_with(operator.key()));
+        assert_ne!(fragment_1.key(), fragment_2.key());
+        assert!(!fragment_1.key().starts_with(fragment_2.key()));
+        assert!(!fragment_2.key().starts_with(fragment_1.key()));
+
+        let other_operator = Keyspace::fragment_root(store, 234, 1);
+        assert!(!other_operator.key().starts_with(operator.key()));
+    }
+
+    #[test]
+    fn test_display_and_prefix_hex() {
+        let store = MemoryStateStore::new();
+        let keyspace = Keyspace::executor_root(store.clone(), 1)
+            .with_segment(Segment::U16(2))
+            .append_str("ab")
+            .append(vec![0xca, 0xfe]);
+
+        assert_eq!(keyspace.to_string(), "e/1/2/\"ab\"/0xcafe");
+        assert_eq!(
+            keyspace.prefix_hex(),
+            format!(
+                "6500000000000000010002{}cafe",
+                to_hex(&memcomparable::to_vec(&"ab").unwrap())
+            )
+        );
+        assert_eq!(keyspace.segments().len(), 4);
+
+        // Recording segments must not change the encoding.
+        let mut prefix = b"e".to_vec();
+        prefix.extend_from_slice(&1u64.to_be_bytes());
+        prefix.extend_from_slice(&2u16.to_be_bytes());
+        prefix.extend(memcomparable::to_vec(&"ab").unwrap());
+        prefix.extend_from_slice(&[0xca, 0xfe]);
+        assert_eq!(keyspace.key(), prefix.as_slice());
+
+        let table = Keyspace::table_root(store.clone(), &TableId::new(3));
+        assert_eq!(table.to_string(), "t/3");
+        assert_eq!(table.prefix_hex(), "7400000003");
+        assert_eq!(Keyspace::fragment_root(store, 4, 5).to_string(), "s/4/5");
+    }
+
+    #[test]
+    fn test_table_root() {
+        let store = MemoryStateStore::new();
+        let ids = [0, 1, 9, 10, 255, 256, 65536, u32::MAX];
+        let roots = ids
+            .iter()
+            .map(|id| Keyspace::table_root(store.clone(), &TableId::new(*id)))
+            .collect_vec();
+
+        // The prefix is `t` followed by the big-endian table id, which must stay stable.
+        assert_eq!(roots[5].key(), b"t\x00\x00\x01\x00");
+        for (lhs, rhs) in roots.iter().tuple_windows() {
+            assert!(lhs.key() < rhs.key());
+        }
+        for (lhs, rhs) in roots.iter().tuple_combinations() {
+            assert!(!lhs.key().starts_with(rhs.key()));
+            assert!(!rhs.key().starts_with(lhs.key()));
+        }
+    }
+
+    fn assert_sorted_like<T: Ord + Debug>(
+        values: &[T],
+        append: impl Fn(&T) -> Keyspace<MemoryStateStore>,
+    ) {
+        for (lhs, rhs) in values.iter().tuple_windows() {
+            assert!(lhs < rhs, "test values must be strictly increasing");
+            let (lhs_key, rhs_key) = (append(lhs), append(rhs));
+            assert!(
+                lhs_key.key() < rhs_key.key(),
+                "{:?} is not encoded before {:?}",
+                lhs,
+                rhs
+            );
+            assert!(!rhs_key.key().starts_with(lhs_key.key()));
+        }
+    }
+
+    #[test]
+    fn test_append_typed() {
+        let root = Keyspace::executor_root(MemoryStateStore::new(), 1);
+
+        assert_sorted_like(&[0, 1, 255, 256, u32::MAX], |v| root.append_u32(*v));
+        assert_sorted_like(&[0, 1, 1 << 32, u64::MAX], |v| root.append_u64(*v));
+        assert_sorted_like(&[i32::MIN, -256, -1, 0, 1, 256, i32::MAX], |v| {
+            root.append_i32(*v)
+        });
+        assert_sorted_like(&[i64::MIN, -(1 << 40), -1, 0, 1, 1 << 40, i64::MAX], |v| {
+            root.append_i64(*v)
+        });
+        assert_sorted_like(&[false, true], |v| root.append_bool(*v));
+        assert_sorted_like(&["", "a", "ab", "abcdefghij", "b"], |v| root.append_str(v));
+    }
+
+    async fn put(keyspace: &Keyspace<MemoryStateStore>, key: &[u8], epoch: u64) {
+        keyspace
+            .state_store()
+            .ingest_batch(
+                vec![(
+                    Bytes::from(keyspace.prefixed_key(key)),
+                    StorageValue::new_default_put(key.to_vec()),
+                )],
+                epoch,
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_multi_get() {
+        let root = Keyspace::executor_root(MemoryStateStore::new(), 1);
+        let keyspace = root.append_u8(1);
+        put(&keyspace, b"a", 1).await;
+        put(&keyspace, b"c", 1).await;
+        put(&keyspace, b"d", 1).await;
+        keyspace.delete(b"d", 2).await.unwrap();
+        // The same key in a sibling keyspace is invisible.
+        put(&root.append_u8(2), b"b", 1).await;
+
+        let values = keyspace
+            .multi_get(&[b"c", b"b", b"a", b"d", b"c"], 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                Some(Bytes::from_static(b"c")),
+                None,
+                Some(Bytes::from_static(b"a")),
+                None,
+                Some(Bytes::from_static(b"c")),
+            ]
+        );
+        // Snapshots before the deletion still see the deleted key.
+        assert_eq!(
+            keyspace.multi_get(&[b"d"], 1).await.unwrap(),
+            vec![Some(Bytes::from_static(b"d"))]
+        );
+        assert!(keyspace
+            .multi_get(&[] as &[&[u8]], 1)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_count() {
+        let root = Keyspace::executor_root(MemoryStateStore::new(), 1);
+        let keyspace = root.append_u8(1);
+        assert_eq!(keyspace.count(1).await.unwrap(), 0);
+
+        for i in 0..10u8 {
+            put(&keyspace, &[i], 1).await;
+        }
+        // Keys of sibling keyspaces are not counted.
+        put(&root.append_u8(0), b"a", 1).await;
+        put(&root.append_u8(2), b"a", 1).await;
+        keyspace.delete(&[0u8], 2).await.unwrap();
+
+        assert_eq!(keyspace.count(1).await.unwrap(), 10);
+        assert_eq!(keyspace.count(2).await.unwrap(), 9);
+        assert_eq!(
+            keyspace.count(2).await.unwrap(),
+            keyspace.scan(None, 2).await.unwrap().len()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exists_and_contains() {
+        let root = Keyspace::executor_root(MemoryStateStore::new(), 1);
+        let keyspace = root.append_u8(1);
+        assert!(!keyspace.exists(1).await.unwrap());
+        assert!(!keyspace.contains(b"a", 1).await.unwrap());
+
+        // Keys of a sibling keyspace don't cause a false positive.
+        put(&root.append_u8(2), b"a", 1).await;
+        assert!(!keyspace.exists(1).await.unwrap());
+        assert!(!keyspace.contains(b"a", 1).await.unwrap());
+
+        put(&keyspace, b"a", 1).await;
+        assert!(keyspace.exists(1).await.unwrap());
+        assert!(keyspace.contains(b"a", 1).await.unwrap());
+        assert!(!keyspace.contains(b"ab", 1).await.unwrap());
+        assert!(!keyspace.contains(b"", 1).await.unwrap());
+
+        // A key extending the given one is not a match.
+        put(&keyspace, b"bc", 1).await;
+        assert!(!keyspace.contains(b"b", 1).await.unwrap());
+
+        keyspace.delete(b"a", 2).await.unwrap();
+        assert!(!keyspace.exists(2).await.unwrap());
+        assert!(!keyspace.contains(b"a", 2).await.unwrap());
+        assert!(keyspace.exists(1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_scan_range() {
+        let root = Keyspace::executor_root(MemoryStateStore::new(), 1);
+        let keyspace = root.append_u8(1);
+        for key in [b"a", b"b", b"c", b"d"] {
+            put(&keyspace, key, 1).await;
+        }
+        // Keys of siblings must never be scanned.
+        put(&root.append_u8(0), b"a", 1).await;
+        put(&root.append_u8(2), b"a", 1).await;
+
+        let scan = |start: Option<&'static str>, end: Option<&'static str>, limit| {
+            let keyspace = keyspace.clone();
+            async move {
+                keyspace
+                    .scan_range(start.map(str::as_bytes), end.map(str::as_bytes), limit, 1)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|(_, v)| v)
+                    .collect_vec()
+            }
+        };
+
+        assert_eq!(scan(Some("b"), Some("d"), None).await, vec!["b", "c"]);
+        assert_eq!(scan(Some("b"), Some("d"), Some(1)).await, vec!["b"]);
+        assert_eq!(scan(None, Some("c"), None).await, vec!["a", "b"]);
+        assert_eq!(scan(Some("bb"), None, None).await, vec!["c", "d"]);
+        assert_eq!(scan(None, None, None).await, vec!["a", "b", "c", "d"]);
+
+        // Empty ranges yield nothing.
+        assert!(scan(Some("b"), Some("b"), None).await.is_empty());
+        assert!(scan(Some("c"), Some("b"), None).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_reverse() {
+        let root = Keyspace::executor_root(MemoryStateStore::new(), 1);
+        let keyspace = root.append_u8(1);
+        for key in 0..10u8 {
+            put(&keyspace, &[key], 1).await;
+        }
+        put(&root.append_u8(2), &[0], 1).await;
+
+        let keys = keyspace
+            .scan_reverse(Some(3), 1)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(k, _)| k[keyspace.key().len()..].to_vec())
+            .collect_vec();
+        assert_eq!(keys, vec![vec![9], vec![8], vec![7]]);
+        assert_eq!(keyspace.scan_reverse(None, 1).await.unwrap().len(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_delete() {
+        let keyspace = Keyspace::executor_root(MemoryStateStore::new(), 1);
+        put(&keyspace, b"a", 1).await;
+        put(&keyspace, b"b", 1).await;
+
+        keyspace.delete(b"a", 2).await.unwrap();
+        assert_eq!(keyspace.get(b"a", 2).await.unwrap(), None);
+        assert_eq!(
+            keyspace.get(b"b", 2).await.unwrap(),
+            Some(Bytes::from_static(b"b"))
+        );
+        // Snapshots before the deletion are not affected.
+        assert_eq!(
+            keyspace.get(b"a", 1).await.unwrap(),
+            Some(Bytes::from_static(b"a"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_prefix() {
+        let root = Keyspace::executor_root(MemoryStateStore::new(), 1);
+        // The two siblings share their first byte, and only differ in the second one.
+        let keyspace = root.append_u16(0x0100);
+        let sibling = root.append_u16(0x0101);
+        for key in [b"a", b"b", b"c"] {
+            put(&keyspace, key, 1).await;
+            put(&sibling, key, 1).await;
+        }
+
+        keyspace.delete_prefix(2).await.unwrap();
+        assert!(keyspace.scan(None, 2).await.unwrap().is_empty());
+        assert_eq!(sibling.scan(None, 2).await.unwrap().len(), 3);
+        assert_eq!(keyspace.scan(None, 1).await.unwrap().len(), 3);
+
+        // Deleting an empty keyspace is a no-op.
+        keyspace.delete_prefix(3).await.unwrap();
+        assert_eq!(sibling.scan(None, 3).await.unwrap().len(), 3);
+    }
+}
@@ -61,7 +61,7 @@ pub mod tikv;
 #[path = "tikv_mock.rs"]
 pub mod tikv;
 
-pub use keyspace::Keyspace;
+pub use keyspace::{Keyspace, Segment};
 pub use store::{StateStore, StateStoreIter};
 pub use store_impl::StateStoreImpl;
 
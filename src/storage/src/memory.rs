@@ -98,6 +98,68 @@ impl StateStore for MemoryStateStore {
         }
     }
 
+    fn multi_get(&self, keys: Vec<Vec<u8>>, epoch: u64) -> Self::MultiGetFuture<'_> {
+        async move {
+            let inner = self.inner.lock().await;
+            Ok(keys
+                .into_iter()
+                .map(|key| {
+                    // The first version of the key with an epoch not larger than `epoch`.
+                    let key = Bytes::from(key);
+                    inner
+                        .range((key.clone(), Reverse(epoch))..=(key, Reverse(0)))
+                        .next()
+                        .and_then(|(_, value)| value.clone())
+                })
+                .collect())
+        }
+    }
+
+    fn count_prefix(&self, prefix: Vec<u8>, epoch: u64) -> Self::CountPrefixFuture<'_> {
+        async move {
+            let inner = self.inner.lock().await;
+            let mut count = 0;
+            let mut last_key = None;
+            // Only look at the keys and the presence of values, without cloning them.
+            for ((key, Reverse(key_epoch)), value) in
+                inner.range(to_bytes_range(prefix_range(&prefix)))
+            {
+                if *key_epoch > epoch {
+                    continue;
+                }
+                if Some(key) != last_key {
+                    if value.is_some() {
+                        count += 1;
+                    }
+                    last_key = Some(key);
+                }
+            }
+            Ok(count)
+        }
+    }
+
+    fn prefix_exists(&self, prefix: Vec<u8>, epoch: u64) -> Self::PrefixExistsFuture<'_> {
+        async move {
+            let inner = self.inner.lock().await;
+            let mut last_key = None;
+            for ((key, Reverse(key_epoch)), value) in
+                inner.range(to_bytes_range(prefix_range(&prefix)))
+            {
+                if *key_epoch > epoch {
+                    continue;
+                }
+                if Some(key) != last_key {
+                    // The visible version of a key is the first one, which may be a tombstone.
+                    if value.is_some() {
+                        return Ok(true);
+                    }
+                    last_key = Some(key);
+                }
+            }
+            Ok(false)
+        }
+    }
+
     fn scan<R, B>(
         &self,
         key_range: R,
@@ -136,15 +198,25 @@ impl StateStore for MemoryStateStore {
 
     fn reverse_scan<R, B>(
         &self,
-        _key_range: R,
-        _limit: Option<usize>,
-        _epoch: u64,
+        key_range: R,
+        limit: Option<usize>,
+        epoch: u64,
     ) -> Self::ReverseScanFuture<'_, R, B>
     where
         R: RangeBounds<B> + Send,
         B: AsRef<[u8]> + Send,
     {
-        async move { unimplemented!() }
+        async move {
+            // Versions of a key are ordered by descending epoch, which makes picking the visible
+            // version while iterating backward awkward. As it's only for tests, simply reverse the
+            // forward scan.
+            let mut data = self.scan(key_range, None, epoch).await?;
+            data.reverse();
+            if let Some(limit) = limit {
+                data.truncate(limit);
+            }
+            Ok(data)
+        }
     }
 
     fn ingest_batch(
@@ -228,6 +300,93 @@ impl StateStoreIter for MemoryStateStoreIter {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_count_prefix() {
+        let state_store = MemoryStateStore::new();
+        let put = |key: &'static [u8]| (Bytes::from(key), StorageValue::new_default_put(key));
+        state_store
+            .ingest_batch(vec![put(b"a1"), put(b"a2"), put(b"a3"), put(b"b1")], 0)
+            .await
+            .unwrap();
+        state_store
+            .ingest_batch(
+                vec![
+                    (Bytes::from(&b"a1"[..]), StorageValue::new_default_delete()),
+                    put(b"a2"),
+                    put(b"a4"),
+                ],
+                1,
+            )
+            .await
+            .unwrap();
+
+        // Overwritten keys are counted once, and deleted keys are not counted.
+        assert_eq!(state_store.count_prefix(b"a".to_vec(), 0).await.unwrap(), 3);
+        assert_eq!(state_store.count_prefix(b"a".to_vec(), 1).await.unwrap(), 3);
+        assert_eq!(state_store.count_prefix(b"b".to_vec(), 1).await.unwrap(), 1);
+        assert_eq!(state_store.count_prefix(vec![], 1).await.unwrap(), 4);
+        assert_eq!(state_store.count_prefix(b"c".to_vec(), 1).await.unwrap(), 0);
+        for (prefix, epoch) in [(b"a".to_vec(), 0), (b"a".to_vec(), 1), (vec![], 1)] {
+            assert_eq!(
+                count_prefix_by_iter(&state_store, prefix.clone(), epoch)
+                    .await
+                    .unwrap(),
+                state_store.count_prefix(prefix, epoch).await.unwrap()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefix_exists() {
+        let state_store = MemoryStateStore::new();
+        state_store
+            .ingest_batch(
+                vec![
+                    (
+                        Bytes::from(&b"a1"[..]),
+                        StorageValue::new_default_put(&b"v"[..]),
+                    ),
+                    (
+                        Bytes::from(&b"b1"[..]),
+                        StorageValue::new_default_put(&b"v"[..]),
+                    ),
+                ],
+                1,
+            )
+            .await
+            .unwrap();
+        state_store
+            .ingest_batch(
+                vec![(Bytes::from(&b"a1"[..]), StorageValue::new_default_delete())],
+                2,
+            )
+            .await
+            .unwrap();
+
+        for (prefix, epoch, exists) in [
+            (&b"a"[..], 0, false),
+            (&b"a"[..], 1, true),
+            (&b"a"[..], 2, false),
+            (&b"b"[..], 2, true),
+            (&b"b12"[..], 2, false),
+            (&b""[..], 2, true),
+        ] {
+            assert_eq!(
+                state_store
+                    .prefix_exists(prefix.to_vec(), epoch)
+                    .await
+                    .unwrap(),
+                exists
+            );
+            assert_eq!(
+                prefix_exists_by_iter(&state_store, prefix.to_vec(), epoch)
+                    .await
+                    .unwrap(),
+                exists
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_snapshot_isolation() {
         let state_store = MemoryStateStore::new();
@@ -275,6 +434,20 @@ mod tests {
             state_store.scan("a"..="b", None, 1).await.unwrap(),
             vec![(b"a".to_vec().into(), b"v2".to_vec().into())]
         );
+        assert_eq!(
+            state_store.reverse_scan("a"..="b", None, 0).await.unwrap(),
+            vec![
+                (b"b".to_vec().into(), b"v1".to_vec().into()),
+                (b"a".to_vec().into(), b"v1".to_vec().into())
+            ]
+        );
+        assert_eq!(
+            state_store
+                .reverse_scan("a"..="b", Some(1), 1)
+                .await
+                .unwrap(),
+            vec![(b"a".to_vec().into(), b"v2".to_vec().into())]
+        );
         assert_eq!(
             state_store.get(b"a", 0).await.unwrap(),
             Some(b"v1".to_vec().into())
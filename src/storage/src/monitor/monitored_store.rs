@@ -87,6 +87,18 @@ where
         }
     }
 
+    fn multi_get(&self, keys: Vec<Vec<u8>>, epoch: u64) -> Self::MultiGetFuture<'_> {
+        async move { self.inner.multi_get(keys, epoch).await }
+    }
+
+    fn count_prefix(&self, prefix: Vec<u8>, epoch: u64) -> Self::CountPrefixFuture<'_> {
+        async move { self.inner.count_prefix(prefix, epoch).await }
+    }
+
+    fn prefix_exists(&self, prefix: Vec<u8>, epoch: u64) -> Self::PrefixExistsFuture<'_> {
+        async move { self.inner.prefix_exists(prefix, epoch).await }
+    }
+
     fn scan<R, B>(
         &self,
         key_range: R,
@@ -122,7 +134,7 @@ where
     {
         async move {
             let timer = self.stats.range_reverse_scan_duration.start_timer();
-            let result = self.inner.scan(key_range, limit, epoch).await?;
+            let result = self.inner.reverse_scan(key_range, limit, epoch).await?;
             timer.observe_duration();
 
             self.stats
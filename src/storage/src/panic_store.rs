@@ -37,6 +37,24 @@ impl StateStore for PanicStateStore {
         }
     }
 
+    fn multi_get(&self, _keys: Vec<Vec<u8>>, _epoch: u64) -> Self::MultiGetFuture<'_> {
+        async move {
+            panic!("should not read from the state store!");
+        }
+    }
+
+    fn count_prefix(&self, _prefix: Vec<u8>, _epoch: u64) -> Self::CountPrefixFuture<'_> {
+        async move {
+            panic!("should not read from the state store!");
+        }
+    }
+
+    fn prefix_exists(&self, _prefix: Vec<u8>, _epoch: u64) -> Self::PrefixExistsFuture<'_> {
+        async move {
+            panic!("should not read from the state store!");
+        }
+    }
+
     fn scan<R, B>(
         &self,
         _key_range: R,
@@ -57,6 +57,18 @@ impl StateStore for RocksDBStateStore {
         async move { self.storage().await.get(key).await }
     }
 
+    fn multi_get(&self, keys: Vec<Vec<u8>>, epoch: u64) -> Self::MultiGetFuture<'_> {
+        multi_get_one_by_one(self, keys, epoch)
+    }
+
+    fn count_prefix(&self, prefix: Vec<u8>, epoch: u64) -> Self::CountPrefixFuture<'_> {
+        count_prefix_by_iter(self, prefix, epoch)
+    }
+
+    fn prefix_exists(&self, prefix: Vec<u8>, epoch: u64) -> Self::PrefixExistsFuture<'_> {
+        prefix_exists_by_iter(self, prefix, epoch)
+    }
+
     fn scan<R, B>(
         &self,
         key_range: R,
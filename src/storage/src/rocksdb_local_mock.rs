@@ -43,6 +43,18 @@ impl StateStore for RocksDBStateStore {
         async move { unimplemented!() }
     }
 
+    fn multi_get(&self, _keys: Vec<Vec<u8>>, _epoch: u64) -> Self::MultiGetFuture<'_> {
+        async move { unimplemented!() }
+    }
+
+    fn count_prefix(&self, _prefix: Vec<u8>, _epoch: u64) -> Self::CountPrefixFuture<'_> {
+        async move { unimplemented!() }
+    }
+
+    fn prefix_exists(&self, _prefix: Vec<u8>, _epoch: u64) -> Self::PrefixExistsFuture<'_> {
+        async move { unimplemented!() }
+    }
+
     fn scan<R, B>(
         &self,
         _key_range: R,
@@ -12,10 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use std::future::Future;
+use std::ops::Bound::{self, Included, Unbounded};
 use std::ops::RangeBounds;
 use std::sync::Arc;
 
 use bytes::Bytes;
+use risingwave_hummock_sdk::key::next_key;
 
 use crate::error::StorageResult;
 use crate::monitor::{MonitoredStateStore, StateStoreMetrics};
@@ -23,13 +25,19 @@ use crate::storage_value::StorageValue;
 use crate::write_batch::WriteBatch;
 
 pub trait GetFutureTrait<'a> = Future<Output = StorageResult<Option<Bytes>>> + Send;
+pub trait MultiGetFutureTrait<'a> = Future<Output = StorageResult<Vec<Option<Bytes>>>> + Send;
 pub trait ScanFutureTrait<'a, R, B> = Future<Output = StorageResult<Vec<(Bytes, Bytes)>>> + Send;
+pub trait CountFutureTrait<'a> = Future<Output = StorageResult<usize>> + Send;
+pub trait ExistsFutureTrait<'a> = Future<Output = StorageResult<bool>> + Send;
 pub trait EmptyFutureTrait<'a> = Future<Output = StorageResult<()>> + Send;
 
 #[macro_export]
 macro_rules! define_state_store_associated_type {
     () => {
         type GetFuture<'a> = impl GetFutureTrait<'a>;
+        type MultiGetFuture<'a> = impl MultiGetFutureTrait<'a>;
+        type CountPrefixFuture<'a> = impl CountFutureTrait<'a>;
+        type PrefixExistsFuture<'a> = impl ExistsFutureTrait<'a>;
         type ScanFuture<'a, R, B> = impl ScanFutureTrait<'a, R, B> where R: 'static + Send, B: 'static + Send;
         type ReverseScanFuture<'a, R, B> = impl ScanFutureTrait<'a, R, B> where R: 'static + Send, B: 'static + Send;
         type IngestBatchFuture<'a> = impl EmptyFutureTrait<'a>;
@@ -48,6 +56,12 @@ pub trait StateStore: Send + Sync + 'static + Clone {
 
     type GetFuture<'a>: GetFutureTrait<'a>;
 
+    type MultiGetFuture<'a>: MultiGetFutureTrait<'a>;
+
+    type CountPrefixFuture<'a>: CountFutureTrait<'a>;
+
+    type PrefixExistsFuture<'a>: ExistsFutureTrait<'a>;
+
     type ScanFuture<'a, R, B>: ScanFutureTrait<'a, R, B>
     where
         R: 'static + Send,
@@ -80,6 +94,26 @@ pub trait StateStore: Send + Sync + 'static + Clone {
     /// The result is based on a snapshot corresponding to the given `epoch`.
     fn get<'a>(&'a self, key: &'a [u8], epoch: u64) -> Self::GetFuture<'_>;
 
+    /// Point gets values of multiple keys from the state store. The returned values line up with
+    /// `keys`, with `None` for keys not found.
+    /// The result is based on a snapshot corresponding to the given `epoch`.
+    ///
+    /// State stores without native batched point gets may use [`multi_get_one_by_one`].
+    fn multi_get(&self, keys: Vec<Vec<u8>>, epoch: u64) -> Self::MultiGetFuture<'_>;
+
+    /// Counts the keys starting with `prefix`, without returning their values.
+    /// The result is based on a snapshot corresponding to the given `epoch`.
+    ///
+    /// State stores without a native way to count keys may use [`count_prefix_by_iter`].
+    fn count_prefix(&self, prefix: Vec<u8>, epoch: u64) -> Self::CountPrefixFuture<'_>;
+
+    /// Checks whether any key starts with `prefix`, returning on the first one found without
+    /// reading its value.
+    /// The result is based on a snapshot corresponding to the given `epoch`.
+    ///
+    /// State stores without a native way to do so may use [`prefix_exists_by_iter`].
+    fn prefix_exists(&self, prefix: Vec<u8>, epoch: u64) -> Self::PrefixExistsFuture<'_>;
+
     /// Scans `limit` number of keys from a key range. If `limit` is `None`, scans all elements.
     /// The result is based on a snapshot corresponding to the given `epoch`.
     ///
@@ -170,3 +204,52 @@ pub trait StateStoreIter: Send {
 
     fn next(&mut self) -> Self::NextFuture<'_>;
 }
+
+/// Implements [`StateStore::multi_get`] by calling [`StateStore::get`] for each key in turn.
+pub async fn multi_get_one_by_one<S: StateStore>(
+    store: &S,
+    keys: Vec<Vec<u8>>,
+    epoch: u64,
+) -> StorageResult<Vec<Option<Bytes>>> {
+    let mut values = Vec::with_capacity(keys.len());
+    for key in keys {
+        values.push(store.get(&key, epoch).await?);
+    }
+    Ok(values)
+}
+
+/// Returns the key range of all keys starting with `prefix`.
+pub(crate) fn prefix_range(prefix: &[u8]) -> (Bound<Vec<u8>>, Bound<Vec<u8>>) {
+    let end = next_key(prefix);
+    if end.is_empty() {
+        // `prefix` is empty or consists of `0xff`s only, so it has no upper bound.
+        (Included(prefix.to_vec()), Unbounded)
+    } else {
+        (Included(prefix.to_vec()), Bound::Excluded(end))
+    }
+}
+
+/// Implements [`StateStore::count_prefix`] by iterating over the keys with the given prefix.
+pub async fn count_prefix_by_iter<S: StateStore>(
+    store: &S,
+    prefix: Vec<u8>,
+    epoch: u64,
+) -> StorageResult<usize> {
+    let mut iter = store.iter(prefix_range(&prefix), epoch).await?;
+    let mut count = 0;
+    while iter.next().await?.is_some() {
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Implements [`StateStore::prefix_exists`] by taking the first key with the given prefix from an
+/// iterator.
+pub async fn prefix_exists_by_iter<S: StateStore>(
+    store: &S,
+    prefix: Vec<u8>,
+    epoch: u64,
+) -> StorageResult<bool> {
+    let mut iter = store.iter(prefix_range(&prefix), epoch).await?;
+    Ok(iter.next().await?.is_some())
+}
@@ -154,7 +154,7 @@ mod tests {
     use super::WriteBatch;
     use crate::memory::MemoryStateStore;
     use crate::storage_value::StorageValue;
-    use crate::Keyspace;
+    use crate::{Keyspace, StateStore};
 
     #[tokio::test]
     async fn test_invalid_write_batch() {
@@ -174,4 +174,48 @@ mod tests {
             .await
             .expect_err("Should panic here because of duplicate key.");
     }
+
+    #[tokio::test]
+    async fn test_write_batch_prefixify() {
+        let state_store = MemoryStateStore::new();
+        let key_space_a = Keyspace::executor_root(state_store.clone(), 0x118);
+        let key_space_b = Keyspace::executor_root(state_store.clone(), 0x119);
+
+        let mut write_batch = WriteBatch::new(state_store.clone());
+        let mut batch_a = write_batch.prefixify(&key_space_a);
+        batch_a.put(Bytes::from("aa"), StorageValue::new_default_put("111"));
+        batch_a.put(Bytes::from("bb"), StorageValue::new_default_put("222"));
+        let mut batch_b = write_batch.prefixify(&key_space_b);
+        batch_b.put_single(StorageValue::new_default_put("333"));
+        assert_eq!(write_batch.len(), 3);
+        write_batch.ingest(1).await.unwrap();
+
+        // Keys are prefixed by their own keyspaces.
+        assert_eq!(
+            state_store
+                .get(&key_space_a.prefixed_key("aa"), 1)
+                .await
+                .unwrap(),
+            Some(Bytes::from("111"))
+        );
+        assert_eq!(key_space_a.get("bb", 1).await.unwrap(), Some("222".into()));
+        assert_eq!(key_space_b.get("aa", 1).await.unwrap(), None);
+        assert_eq!(key_space_b.value(1).await.unwrap(), Some("333".into()));
+
+        // Mixed puts and deletes in one batch.
+        let mut write_batch = WriteBatch::new(state_store.clone());
+        let mut batch_a = write_batch.prefixify(&key_space_a);
+        batch_a.delete(Bytes::from("aa"));
+        batch_a.put(Bytes::from("cc"), StorageValue::new_default_put("444"));
+        let mut batch_b = write_batch.prefixify(&key_space_b);
+        batch_b.delete_single();
+        write_batch.ingest(2).await.unwrap();
+
+        assert_eq!(key_space_a.get("aa", 2).await.unwrap(), None);
+        assert_eq!(key_space_a.get("bb", 2).await.unwrap(), Some("222".into()));
+        assert_eq!(key_space_a.get("cc", 2).await.unwrap(), Some("444".into()));
+        assert_eq!(key_space_b.value(2).await.unwrap(), None);
+        // Earlier epochs are not affected.
+        assert_eq!(key_space_a.get("aa", 1).await.unwrap(), Some("111".into()));
+    }
 }
@@ -637,6 +637,63 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_managed_max_state_retract() {
+        let keyspace = Keyspace::executor_root(MemoryStateStore::new(), 0x2333);
+        // Only the maximum is kept in memory after each flush.
+        let mut managed_state = ManagedMaxState::<_, I64Array>::new(
+            keyspace,
+            DataType::Int64,
+            Some(1),
+            0,
+            PkDataTypes::new(),
+        )
+        .await
+        .unwrap();
+
+        let mut epoch: u64 = 0;
+        async fn apply_and_flush(
+            managed_state: &mut ManagedMaxState<MemoryStateStore, I64Array>,
+            ops: Ops<'_>,
+            values: &[Option<i64>],
+            epoch: u64,
+        ) -> Datum {
+            let data = I64Array::from_slice(values).unwrap().into();
+            managed_state
+                .apply_batch(ops, None, &[&data], epoch)
+                .await
+                .unwrap();
+            let mut write_batch = managed_state.keyspace.state_store().start_write_batch();
+            managed_state.flush(&mut write_batch).unwrap();
+            write_batch.ingest(epoch).await.unwrap();
+            managed_state.get_output(epoch).await.unwrap()
+        }
+
+        let output = apply_and_flush(
+            &mut managed_state,
+            &[Op::Insert; 3],
+            &[Some(10), Some(30), Some(20)],
+            epoch,
+        )
+        .await;
+        assert_eq!(output, Some(ScalarImpl::Int64(30)));
+
+        // Retracting the maximum recovers the second largest from the state store.
+        epoch += 1;
+        let output = apply_and_flush(&mut managed_state, &[Op::Delete], &[Some(30)], epoch).await;
+        assert_eq!(output, Some(ScalarImpl::Int64(20)));
+
+        epoch += 1;
+        let output = apply_and_flush(
+            &mut managed_state,
+            &[Op::UpdateDelete, Op::UpdateInsert],
+            &[Some(20), Some(5)],
+            epoch,
+        )
+        .await;
+        assert_eq!(output, Some(ScalarImpl::Int64(10)));
+    }
+
     #[tokio::test]
     async fn test_replicated_value_min() {
         test_replicated_value_not_null::<{ variants::EXTREME_MIN }>().await
@@ -0,0 +1,157 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use risingwave_common::error::{Result, RwError};
+use risingwave_storage::StateStore;
+
+use super::ManagedState;
+
+/// [`StateFlushController`] flushes a set of dirty states into one shared write batch, which is
+/// ingested into the state store at once.
+///
+/// The write is all-or-nothing for the dirty flags as well: if flushing any state or ingesting the
+/// batch fails, every collected state is marked dirty again, so that the next flush writes all of
+/// them.
+pub struct StateFlushController<'a, S: StateStore> {
+    store: S,
+
+    /// The dirty states to flush.
+    states: Vec<&'a mut dyn ManagedState<S>>,
+}
+
+impl<'a, S: StateStore> StateFlushController<'a, S> {
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            states: vec![],
+        }
+    }
+
+    /// Add `state` to be flushed. Clean states are skipped, as there is nothing to write.
+    pub fn add(&mut self, state: &'a mut dyn ManagedState<S>) {
+        if state.is_dirty() {
+            self.states.push(state);
+        }
+    }
+
+    /// Returns the number of dirty states to flush.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Flush all collected states into one write batch, and ingest it at `epoch`.
+    pub async fn flush(mut self, epoch: u64) -> Result<()> {
+        if self.states.is_empty() {
+            return Ok(());
+        }
+
+        let mut write_batch = self.store.start_write_batch();
+        let result = async {
+            for state in &mut self.states {
+                state.flush(&mut write_batch)?;
+            }
+            write_batch.ingest(epoch).await?;
+            Ok::<_, RwError>(())
+        }
+        .await;
+
+        if result.is_err() {
+            for state in &mut self.states {
+                state.mark_dirty();
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use risingwave_common::array::{ArrayImpl, I64Array, Op};
+    use risingwave_common::types::{DataType, ScalarImpl};
+    use risingwave_common::util::value_encoding::serialize_cell;
+    use risingwave_storage::Keyspace;
+
+    use super::*;
+    use crate::executor::managed_state::aggregation::ManagedValueState;
+    use crate::executor::{AggArgs, AggCall};
+    use crate::executor_v2::test_utils::FailingStateStore;
+
+    fn create_test_count_state() -> AggCall {
+        AggCall {
+            kind: risingwave_expr::expr::AggKind::Count,
+            args: AggArgs::Unary(DataType::Int64, 0),
+            return_type: DataType::Int64,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_state_flush_controller() {
+        let store = FailingStateStore::new();
+        let keyspaces = (0..3)
+            .map(|i| Keyspace::executor_root(store.clone(), 0x2333 + i))
+            .collect::<Vec<_>>();
+
+        let mut states = vec![];
+        for (i, keyspace) in keyspaces.iter().enumerate() {
+            let mut state =
+                ManagedValueState::new(create_test_count_state(), keyspace.clone(), Some(0))
+                    .await
+                    .unwrap();
+            // Insert `i + 1` rows, so that each state has a different output.
+            let ops = vec![Op::Insert; i + 1];
+            let data: ArrayImpl = I64Array::from_slice(&vec![Some(0); i + 1]).unwrap().into();
+            state.apply_batch(&ops, None, &[&data]).await.unwrap();
+            states.push(state);
+        }
+
+        // A failed write keeps all states dirty.
+        store.fail_ingest(1);
+        let mut controller = StateFlushController::new(store.clone());
+        for state in &mut states {
+            controller.add(state);
+        }
+        assert_eq!(controller.len(), 3);
+        assert!(controller.flush(0).await.is_err());
+        assert!(states.iter().all(|state| state.is_dirty()));
+        assert_eq!(store.ingested_batches(), 0);
+
+        // A successful write ingests all states in one batch.
+        let mut controller = StateFlushController::new(store.clone());
+        for state in &mut states {
+            controller.add(state);
+        }
+        controller.flush(0).await.unwrap();
+        assert!(states.iter().all(|state| !state.is_dirty()));
+        assert_eq!(store.ingested_batches(), 1);
+        for (i, keyspace) in keyspaces.iter().enumerate() {
+            assert_eq!(
+                keyspace.value(0).await.unwrap().unwrap(),
+                serialize_cell(&Some(ScalarImpl::Int64(i as i64 + 1))).unwrap()
+            );
+        }
+
+        // Clean states are skipped, and nothing is written.
+        let mut controller = StateFlushController::new(store.clone());
+        for state in &mut states {
+            controller.add(state);
+        }
+        assert!(controller.is_empty());
+        controller.flush(0).await.unwrap();
+        assert_eq!(store.ingested_batches(), 1);
+    }
+}
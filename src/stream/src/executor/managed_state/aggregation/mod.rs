@@ -14,11 +14,13 @@
 
 //! Aggregators with state store support
 
+use async_trait::async_trait;
 pub use extreme::*;
+pub use flush_controller::*;
 use risingwave_common::array::stream_chunk::Ops;
-use risingwave_common::array::ArrayImpl;
+use risingwave_common::array::{ArrayImpl, Op};
 use risingwave_common::buffer::Bitmap;
-use risingwave_common::error::{ErrorCode, Result};
+use risingwave_common::error::{ErrorCode, Result, RwError};
 use risingwave_common::types::Datum;
 use risingwave_expr::expr::AggKind;
 use risingwave_storage::write_batch::WriteBatch;
@@ -29,6 +31,7 @@ use super::super::{AggCall, PkDataTypes};
 
 mod extreme;
 mod extreme_serializer;
+mod flush_controller;
 mod string_agg;
 mod value;
 
@@ -47,6 +50,72 @@ pub fn verify_batch(
     all_lengths.iter().min() == all_lengths.iter().max()
 }
 
+/// Verify if the update ops in the visible rows come in pairs, i.e., every `UpdateDelete` is
+/// immediately followed by an `UpdateInsert`, ignoring invisible rows. An unpaired update op
+/// corrupts the aggregation state silently, so the error tells the offending row.
+///
+/// Unlike [`verify_batch`], this walks through all rows, so only use it where the cost is fine.
+pub fn verify_ops(ops: Ops<'_>, visibility: Option<&Bitmap>) -> Result<()> {
+    let mut pending_delete = None;
+    for (idx, op) in ops.iter().enumerate() {
+        if let Some(visibility) = visibility && !visibility.is_set(idx)? {
+            continue;
+        }
+        match op {
+            Op::UpdateDelete => {
+                if let Some(pending) = pending_delete.replace(idx) {
+                    return Err(unpaired_update_delete(pending));
+                }
+            }
+            Op::UpdateInsert => {
+                if pending_delete.take().is_none() {
+                    return Err(ErrorCode::InternalError(format!(
+                        "UpdateInsert at row {} is not preceded by an UpdateDelete",
+                        idx
+                    ))
+                    .into());
+                }
+            }
+            Op::Insert | Op::Delete => {
+                if let Some(pending) = pending_delete {
+                    return Err(unpaired_update_delete(pending));
+                }
+            }
+        }
+    }
+    match pending_delete {
+        Some(pending) => Err(unpaired_update_delete(pending)),
+        None => Ok(()),
+    }
+}
+
+fn unpaired_update_delete(idx: usize) -> RwError {
+    ErrorCode::InternalError(format!(
+        "UpdateDelete at row {} is not followed by an UpdateInsert",
+        idx
+    ))
+    .into()
+}
+
+/// Output and flush of a managed aggregation state, so that an operator can drive states of
+/// different kinds uniformly, e.g. as `Vec<Box<dyn ManagedState<S>>>`. Prefer the inherent methods
+/// where the concrete type is known, which don't box futures.
+#[async_trait]
+pub trait ManagedState<S: StateStore>: Send + Sync + 'static {
+    /// Get the output of the state. Must flush before getting output.
+    async fn get_output(&mut self, epoch: u64) -> Result<Datum>;
+
+    /// Check if this state needs a flush.
+    fn is_dirty(&self) -> bool;
+
+    /// Flush the internal state to a write batch.
+    fn flush(&mut self, write_batch: &mut WriteBatch<S>) -> Result<()>;
+
+    /// Mark the state as dirty again, after the write batch it was flushed to failed to be
+    /// ingested. The state must still be able to flush its content next time.
+    fn mark_dirty(&mut self);
+}
+
 /// All managed state for aggregation. The managed state will manage the cache and integrate
 /// the state with the underlying state store. Managed states can only be evicted from outer cache
 /// when they are not dirty.
@@ -154,3 +223,42 @@ impl<S: StateStore> ManagedStateImpl<S> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_ops() {
+        use Op::*;
+
+        let ops = [
+            Insert,
+            UpdateDelete,
+            UpdateInsert,
+            Delete,
+            UpdateDelete,
+            UpdateInsert,
+        ];
+        verify_ops(&ops, None).unwrap();
+
+        // An unpaired update op.
+        for ops in [
+            &[Insert, UpdateDelete, Delete][..],
+            &[UpdateDelete, UpdateDelete, UpdateInsert],
+            &[Insert, UpdateInsert],
+            &[UpdateDelete],
+        ] {
+            assert!(verify_ops(ops, None).is_err(), "{:?}", ops);
+        }
+        let err = verify_ops(&[Insert, UpdateDelete, Delete], None).unwrap_err();
+        assert!(err.to_string().contains("row 1"), "{}", err);
+
+        // Invisible rows are ignored.
+        let ops = [UpdateDelete, Insert, UpdateInsert];
+        let visibility = Bitmap::try_from(vec![true, false, true]).unwrap();
+        verify_ops(&ops, Some(&visibility)).unwrap();
+        let visibility = Bitmap::try_from(vec![true, true, false]).unwrap();
+        assert!(verify_ops(&ops, Some(&visibility)).is_err());
+    }
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use async_trait::async_trait;
 use risingwave_common::array::stream_chunk::Ops;
 use risingwave_common::array::ArrayImpl;
 use risingwave_common::buffer::Bitmap;
@@ -22,11 +23,13 @@ use risingwave_storage::storage_value::StorageValue;
 use risingwave_storage::write_batch::WriteBatch;
 use risingwave_storage::{Keyspace, StateStore};
 
+use super::ManagedState;
+use crate::executor::managed_state::write_amplification::WriteAmplification;
 use crate::executor::{create_streaming_agg_state, AggCall, StreamingAggStateImpl};
 
 /// A wrapper around [`StreamingAggStateImpl`], which fetches data from the state store and helps
-/// update the state. We don't use any trait to wrap around all `ManagedXxxState`, so as to reduce
-/// the overhead of creating boxed async future.
+/// update the state. The inherent methods are used where possible, so as to reduce the overhead
+/// of creating boxed async future. [`ManagedState`] is implemented for driving it generically.
 pub struct ManagedValueState<S: StateStore> {
     /// The internal single-value state.
     state: Box<dyn StreamingAggStateImpl>,
@@ -37,6 +40,9 @@ pub struct ManagedValueState<S: StateStore> {
     /// Indicates whether this managed state is dirty. If this state is dirty, we cannot evict the
     /// state from memory.
     is_dirty: bool,
+
+    /// Write amplification of the last flush.
+    write_amplification: WriteAmplification,
 }
 
 impl<S: StateStore> ManagedValueState<S> {
@@ -73,6 +79,7 @@ impl<S: StateStore> ManagedValueState<S> {
             )?,
             is_dirty: false,
             keyspace,
+            write_amplification: WriteAmplification::default(),
         })
     }
 
@@ -108,12 +115,41 @@ impl<S: StateStore> ManagedValueState<S> {
         debug_assert!(self.is_dirty());
 
         let mut local = write_batch.prefixify(&self.keyspace);
-        let v = self.state.get_output()?;
+        let v = serialize_cell(&self.state.get_output()?)?;
+        self.write_amplification = WriteAmplification {
+            kv_bytes: self.keyspace.key().len() + v.len(),
+            logical_bytes: v.len(),
+        };
         // TODO(Yuanxin): Implement value meta
-        local.put_single(StorageValue::new_default_put(serialize_cell(&v)?));
+        local.put_single(StorageValue::new_default_put(v));
         self.is_dirty = false;
         Ok(())
     }
+
+    /// Returns the write amplification of the last flush.
+    pub fn write_amplification(&self) -> WriteAmplification {
+        self.write_amplification
+    }
+}
+
+#[async_trait]
+impl<S: StateStore> ManagedState<S> for ManagedValueState<S> {
+    async fn get_output(&mut self, _epoch: u64) -> Result<Datum> {
+        ManagedValueState::get_output(self).await
+    }
+
+    fn is_dirty(&self) -> bool {
+        ManagedValueState::is_dirty(self)
+    }
+
+    fn flush(&mut self, write_batch: &mut WriteBatch<S>) -> Result<()> {
+        ManagedValueState::flush(self, write_batch)
+    }
+
+    fn mark_dirty(&mut self) {
+        // The whole value is serialized on every flush, so nothing but the flag has to be restored.
+        self.is_dirty = true;
+    }
 }
 
 #[cfg(test)]
@@ -160,6 +196,18 @@ mod tests {
         let mut write_batch = keyspace.state_store().start_write_batch();
         managed_state.flush(&mut write_batch).unwrap();
         write_batch.ingest(epoch).await.unwrap();
+        // The single value is written to the key of the keyspace.
+        let kv_pairs = keyspace.scan(None, epoch).await.unwrap();
+        assert_eq!(kv_pairs.len(), 1);
+        assert_eq!(kv_pairs[0].0, keyspace.key());
+        let value_len = serialize_cell(&Some(ScalarImpl::Int64(3))).unwrap().len();
+        assert_eq!(
+            managed_state.write_amplification(),
+            WriteAmplification {
+                kv_bytes: keyspace.key().len() + value_len,
+                logical_bytes: value_len,
+            }
+        );
 
         // get output
         assert_eq!(
@@ -176,4 +224,39 @@ mod tests {
             Some(ScalarImpl::Int64(3))
         );
     }
+
+    #[tokio::test]
+    async fn test_managed_value_state_as_trait_object() {
+        let keyspace = create_in_memory_keyspace();
+        let mut managed_state =
+            ManagedValueState::new(create_test_count_state(), keyspace.clone(), Some(0))
+                .await
+                .unwrap();
+        managed_state
+            .apply_batch(
+                &[Op::Insert, Op::Insert],
+                None,
+                &[&I64Array::from_slice(&[Some(0), Some(1)]).unwrap().into()],
+            )
+            .await
+            .unwrap();
+
+        let mut states: Vec<Box<dyn ManagedState<_>>> = vec![Box::new(managed_state)];
+        let mut write_batch = keyspace.state_store().start_write_batch();
+        for state in &mut states {
+            assert!(state.is_dirty());
+            state.flush(&mut write_batch).unwrap();
+            assert!(!state.is_dirty());
+        }
+        write_batch.ingest(0).await.unwrap();
+
+        assert_eq!(
+            states[0].get_output(0).await.unwrap(),
+            Some(ScalarImpl::Int64(2))
+        );
+        assert_eq!(
+            keyspace.value(0).await.unwrap().unwrap(),
+            serialize_cell(&Some(ScalarImpl::Int64(2))).unwrap()
+        );
+    }
 }
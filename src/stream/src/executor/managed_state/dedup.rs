@@ -0,0 +1,136 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeSet;
+
+use bytes::Bytes;
+use risingwave_common::array::Row;
+use risingwave_common::collection::evictable::EvictableHashMap;
+use risingwave_common::error::Result;
+use risingwave_common::util::value_encoding::serialize_cell;
+use risingwave_storage::storage_value::StorageValue;
+use risingwave_storage::write_batch::WriteBatch;
+use risingwave_storage::{Keyspace, StateStore};
+
+/// Records the unique keys that have been seen, so that a sink can skip rows that were already
+/// emitted before a restart.
+///
+/// Every seen key is persisted under the keyspace with an empty value. Only the most recently seen
+/// keys are cached in memory, and a cache miss falls back to a point get on the state store.
+pub struct ManagedDedupState<S: StateStore> {
+    /// The keyspace to operate on.
+    keyspace: Keyspace<S>,
+
+    /// Index of the unique column in the input rows.
+    key_index: usize,
+
+    /// Recently seen keys, which are either flushed or in `flush_buffer`.
+    cache: EvictableHashMap<Vec<u8>, ()>,
+
+    /// Newly seen keys that will be written on next flush.
+    flush_buffer: BTreeSet<Vec<u8>>,
+}
+
+impl<S: StateStore> ManagedDedupState<S> {
+    /// Create a [`ManagedDedupState`] keyed on the column at `key_index`, caching at most
+    /// `cache_capacity` keys after each flush.
+    pub fn new(keyspace: Keyspace<S>, key_index: usize, cache_capacity: usize) -> Self {
+        Self {
+            keyspace,
+            key_index,
+            cache: EvictableHashMap::new(cache_capacity),
+            flush_buffer: BTreeSet::new(),
+        }
+    }
+
+    /// Check whether the key of `row` has never been seen before, and mark it as seen. Returns
+    /// `true` only on the first occurrence of a key, including occurrences before a restart.
+    pub async fn insert(&mut self, row: &Row, epoch: u64) -> Result<bool> {
+        let key = serialize_cell(&row[self.key_index])?;
+
+        if self.cache.get(&key).is_some() {
+            return Ok(false);
+        }
+        if self.keyspace.get(&key, epoch).await?.is_some() {
+            self.cache.put(key, ());
+            return Ok(false);
+        }
+
+        self.cache.put(key.clone(), ());
+        self.flush_buffer.insert(key);
+        Ok(true)
+    }
+
+    /// Check if this state needs a flush.
+    pub fn is_dirty(&self) -> bool {
+        !self.flush_buffer.is_empty()
+    }
+
+    /// Flush the newly seen keys to a write batch, and shrink the cache to its capacity.
+    pub fn flush(&mut self, write_batch: &mut WriteBatch<S>) -> Result<()> {
+        let mut local = write_batch.prefixify(&self.keyspace);
+        for key in std::mem::take(&mut self.flush_buffer) {
+            // TODO(Yuanxin): Implement value meta
+            local.put(key, StorageValue::new_default_put(Bytes::new()));
+        }
+        self.cache.evict_to_target_cap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use risingwave_common::types::ScalarImpl;
+
+    use super::*;
+    use crate::executor::test_utils::create_in_memory_keyspace;
+
+    fn row(key: i64) -> Row {
+        Row(vec![
+            Some(ScalarImpl::Int64(key)),
+            Some(ScalarImpl::Int64(key * 10)),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_managed_dedup_state() {
+        let keyspace = create_in_memory_keyspace();
+        let mut new_keys = vec![];
+
+        let mut state = ManagedDedupState::new(keyspace.clone(), 0, 1);
+        for key in [1, 2, 1, 3, 2] {
+            if state.insert(&row(key), 0).await.unwrap() {
+                new_keys.push(key);
+            }
+        }
+        assert!(state.is_dirty());
+        let mut write_batch = keyspace.state_store().start_write_batch();
+        state.flush(&mut write_batch).unwrap();
+        write_batch.ingest(0).await.unwrap();
+        assert!(!state.is_dirty());
+
+        // Keys evicted from the cache are still recognized through the state store.
+        assert!(!state.insert(&row(1), 1).await.unwrap());
+
+        // Simulate a restart by recovering the state from the same keyspace.
+        let mut state = ManagedDedupState::new(keyspace, 0, 1);
+        for key in [3, 4, 1, 4] {
+            if state.insert(&row(key), 1).await.unwrap() {
+                new_keys.push(key);
+            }
+        }
+
+        assert_eq!(new_keys, vec![1, 2, 3, 4]);
+    }
+}
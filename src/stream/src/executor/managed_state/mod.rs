@@ -13,6 +13,8 @@
 // limitations under the License.
 
 pub mod aggregation;
+pub mod dedup;
 pub mod flush_status;
 pub mod join;
 pub mod top_n;
+pub mod write_amplification;
@@ -0,0 +1,35 @@
+// Copyright 2022 Singularity Data
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Compares the bytes a flush writes to the state store with the logical size of the flushed data,
+/// which shows how much a storage layout (e.g. one key per cell) costs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WriteAmplification {
+    /// Total size of keys (including keyspace prefix) and values written to the state store.
+    pub kv_bytes: usize,
+
+    /// Size of the flushed data itself, i.e. each primary key once plus the encoded values.
+    pub logical_bytes: usize,
+}
+
+impl WriteAmplification {
+    /// Returns `kv_bytes / logical_bytes`, or `0.0` if nothing was flushed.
+    pub fn ratio(&self) -> f64 {
+        if self.logical_bytes == 0 {
+            0.0
+        } else {
+            self.kv_bytes as f64 / self.logical_bytes as f64
+        }
+    }
+}
@@ -17,7 +17,7 @@ use futures_async_stream::try_stream;
 use itertools::Itertools;
 use risingwave_common::array::Op::*;
 use risingwave_common::array::Row;
-use risingwave_common::catalog::{ColumnId, Schema};
+use risingwave_common::catalog::{ColumnDesc, ColumnId, Schema};
 use risingwave_common::util::sort_util::OrderPair;
 use risingwave_storage::{Keyspace, StateStore};
 
@@ -55,9 +55,14 @@ impl<S: StateStore> MaterializeExecutor<S> {
         let arrange_columns: Vec<usize> = keys.iter().map(|k| k.column_idx).collect();
         let arrange_order_types = keys.iter().map(|k| k.order_type).collect();
         let schema = input.schema().clone();
+        let column_descs = column_ids
+            .into_iter()
+            .zip_eq(schema.fields.iter())
+            .map(|(column_id, field)| ColumnDesc::unnamed(column_id, field.data_type()))
+            .collect();
         Self {
             input,
-            local_state: ManagedMViewState::new(keyspace, column_ids, arrange_order_types),
+            local_state: ManagedMViewState::new(keyspace, column_descs, arrange_order_types),
             arrange_columns: arrange_columns.clone(),
             info: ExecutorInfo {
                 schema,
@@ -71,6 +76,8 @@ impl<S: StateStore> MaterializeExecutor<S> {
     #[try_stream(ok = Message, error = TracedStreamExecutorError)]
     async fn execute_inner(mut self) {
         let input = self.input.execute();
+        // Mutations belong to the current epoch of the last barrier.
+        let mut epoch = 0;
         #[for_await]
         for msg in input {
             let msg = msg?;
@@ -103,10 +110,16 @@ impl<S: StateStore> MaterializeExecutor<S> {
 
                         match op {
                             Insert | UpdateInsert => {
-                                self.local_state.put(arrange_row, row);
+                                self.local_state
+                                    .put(arrange_row, row, epoch)
+                                    .await
+                                    .map_err(StreamExecutorError::ExecutorV1)?;
                             }
                             Delete | UpdateDelete => {
-                                self.local_state.delete(arrange_row);
+                                self.local_state
+                                    .delete(arrange_row, epoch)
+                                    .await
+                                    .map_err(StreamExecutorError::ExecutorV1)?;
                             }
                         }
                     }
@@ -119,6 +132,7 @@ impl<S: StateStore> MaterializeExecutor<S> {
                         .flush(b.epoch.prev)
                         .await
                         .map_err(StreamExecutorError::ExecutorV1)?;
+                    epoch = b.epoch.curr;
                     Message::Barrier(b)
                 }
             }
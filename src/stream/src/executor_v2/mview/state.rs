@@ -12,23 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
+use std::collections::{hash_map, BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
 
-use risingwave_common::array::Row;
-use risingwave_common::catalog::ColumnId;
-use risingwave_common::error::Result;
+use bytes::Bytes;
+use itertools::Itertools;
+use risingwave_common::array::{Row, RowDeserializer};
+use risingwave_common::catalog::{ColumnDesc, ColumnId};
+use risingwave_common::error::{ErrorCode, Result};
+use risingwave_common::types::{Datum, ScalarImpl};
 use risingwave_common::util::ordered::*;
 use risingwave_common::util::sort_util::OrderType;
+use risingwave_storage::cell_based_row_deserializer::CellBasedRowDeserializer;
 use risingwave_storage::storage_value::StorageValue;
 use risingwave_storage::{Keyspace, StateStore};
 
 use crate::executor::managed_state::flush_status::HashMapFlushStatus as FlushStatus;
+use crate::executor::managed_state::write_amplification::WriteAmplification;
 
 /// `ManagedMViewState` buffers recent mutations. Data will be written
 /// to backend storage on calling `flush`.
 pub struct ManagedMViewState<S: StateStore> {
     keyspace: Keyspace<S>,
 
+    /// Column descriptors of each column in the input schema, for deserializing rows on reads.
+    column_descs: Vec<ColumnDesc>,
+
     /// Column IDs of each column in the input schema
     column_ids: Vec<ColumnId>,
 
@@ -40,71 +50,509 @@ pub struct ManagedMViewState<S: StateStore> {
 
     /// Cached key/values
     cache: HashMap<Row, FlushStatus<Row>>,
+
+    /// Estimated memory usage of `cache` in bytes.
+    cache_size: usize,
+
+    /// If set, the cache is flushed once its estimated size exceeds this many bytes on mutations.
+    flush_threshold_bytes: Option<usize>,
+
+    /// Max number of tasks to serialize cells with on flush. Serialization is done in place if it
+    /// is `1`.
+    flush_parallelism: usize,
+
+    /// Write amplification of the last flush.
+    write_amplification: WriteAmplification,
+
+    /// If set, keys are prefixed by the time bucket of a timestamp pk column.
+    time_bucket: Option<TimeBucket>,
+
+    /// How rows are laid out in storage.
+    row_encoding: RowEncoding,
+}
+
+/// Layout of the rows of a [`ManagedMViewState`] in storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowEncoding {
+    /// Each non-null datum of a row is stored as a cell under the pk and its column id, along
+    /// with a sentinel cell. This is the default, and what the cell-based tables read.
+    Cell,
+    /// A row is stored as a single value under the pk. This saves repeating the pk for every
+    /// column, but the rows can't be read by the cell-based tables.
+    Whole,
+}
+
+/// Groups rows by a timestamp pk column into buckets of fixed width, so that rows of the same
+/// bucket are stored in a contiguous key range, ordered by bucket.
+#[derive(Clone, Copy, Debug)]
+struct TimeBucket {
+    /// Index of the timestamp column in pk.
+    pk_index: usize,
+    width_micros: i64,
+}
+
+impl TimeBucket {
+    /// Returns the key segment of the bucket `pk` falls in, which sorts in the order of buckets.
+    fn key_prefix(&self, pk: &Row) -> Result<[u8; 8]> {
+        let micros = match &pk[self.pk_index] {
+            Some(ScalarImpl::NaiveDateTime(timestamp)) => {
+                timestamp.0.timestamp() * 1_000_000 + timestamp.0.timestamp_subsec_micros() as i64
+            }
+            datum => {
+                return Err(ErrorCode::InternalError(format!(
+                    "time bucket column {} must be a timestamp, but got {:?}",
+                    self.pk_index, datum
+                ))
+                .into())
+            }
+        };
+        let bucket = micros.div_euclid(self.width_micros);
+        // Flip the sign bit so that negative buckets sort before positive ones.
+        Ok(((bucket as u64) ^ (1 << 63)).to_be_bytes())
+    }
+}
+
+/// Estimate the memory usage of `row` in bytes. Only the heap allocation of strings is counted
+/// besides the datums themselves.
+fn estimate_row_size(row: &Row) -> usize {
+    row.0
+        .iter()
+        .map(|datum| {
+            std::mem::size_of::<Datum>()
+                + match datum {
+                    Some(ScalarImpl::Utf8(s)) => s.len(),
+                    _ => 0,
+                }
+        })
+        .sum()
+}
+
+/// Serialize `pk` into the key segment of its row, prefixed by the time bucket if any.
+fn serialize_arrange_key(
+    pk: &Row,
+    key_serializer: &OrderedRowSerializer,
+    time_bucket: Option<TimeBucket>,
+) -> Result<Vec<u8>> {
+    let pk_buf = serialize_pk(pk, key_serializer)?;
+    match &time_bucket {
+        Some(time_bucket) => Ok([&time_bucket.key_prefix(pk)?[..], &pk_buf].concat()),
+        None => Ok(pk_buf),
+    }
 }
 
+/// A pk and the mutation on it.
+type CacheEntry = (Row, FlushStatus<Row>);
+
+/// Serialized cells of some rows, as pairs of cell key and value (`None` for deletion), along with
+/// the logical size of the rows.
+type SerializedCells = (Vec<(Vec<u8>, Option<Vec<u8>>)>, usize);
+
 impl<S: StateStore> ManagedMViewState<S> {
     /// Create a [`ManagedMViewState`].
     pub fn new(
         keyspace: Keyspace<S>,
-        column_ids: Vec<ColumnId>,
+        column_descs: Vec<ColumnDesc>,
         order_types: Vec<OrderType>,
     ) -> Self {
         // TODO(eric): refactor this later...
         Self {
             keyspace,
-            column_ids,
+            column_ids: column_descs.iter().map(|d| d.column_id).collect(),
+            column_descs,
             cache: HashMap::new(),
+            cache_size: 0,
+            flush_threshold_bytes: None,
             order_types: order_types.clone(),
             key_serializer: OrderedRowSerializer::new(order_types),
+            flush_parallelism: 1,
+            write_amplification: WriteAmplification::default(),
+            time_bucket: None,
+            row_encoding: RowEncoding::Cell,
         }
     }
 
+    /// Lay out rows in storage with `row_encoding`. The encoding must not change for the same
+    /// keyspace, as rows written with a different encoding can't be read.
+    #[must_use]
+    pub fn with_row_encoding(mut self, row_encoding: RowEncoding) -> Self {
+        self.row_encoding = row_encoding;
+        self
+    }
+
+    /// Serialize cells with at most `parallelism` blocking tasks on flush. This helps with very
+    /// wide schemas where serialization is CPU-bound.
+    #[must_use]
+    pub fn with_flush_parallelism(mut self, parallelism: usize) -> Self {
+        assert!(parallelism >= 1, "flush parallelism must be positive");
+        self.flush_parallelism = parallelism;
+        self
+    }
+
+    /// Flush automatically on `put` or `delete` once the buffered mutations are estimated to take
+    /// more than `bytes` of memory, so that bursts of updates within an epoch don't pile up.
+    #[must_use]
+    pub fn with_flush_threshold(mut self, bytes: usize) -> Self {
+        self.flush_threshold_bytes = Some(bytes);
+        self
+    }
+
+    /// Prefix keys by the time bucket of the timestamp column `pk_index` of pk, with buckets of
+    /// `width`. This keeps rows of a time range together in storage, which helps compaction and
+    /// range scans of append-only time series. Readers must be aware of the extra key segment.
+    #[must_use]
+    pub fn with_time_bucket(mut self, pk_index: usize, width: Duration) -> Self {
+        assert!(
+            pk_index < self.order_types.len(),
+            "time bucket column out of pk"
+        );
+        let width_micros = width.as_micros() as i64;
+        assert!(
+            width_micros > 0,
+            "time bucket width must be at least 1 microsecond"
+        );
+        self.time_bucket = Some(TimeBucket {
+            pk_index,
+            width_micros,
+        });
+        self
+    }
+
     /// Put a key into the managed mview state. `arrange_keys` is composed of group keys and
-    /// primary keys.
-    pub fn put(&mut self, pk: Row, value: Row) {
+    /// primary keys. `epoch` is the epoch the mutation belongs to, which is used if the mutation
+    /// triggers a flush.
+    pub async fn put(&mut self, pk: Row, value: Row, epoch: u64) -> Result<()> {
         assert_eq!(self.order_types.len(), pk.size());
         assert_eq!(self.column_ids.len(), value.size());
+        self.check_pk(&pk)?;
 
+        self.cache_size -= self.cache_entry_size(&pk);
+        self.cache_size += estimate_row_size(&pk) + estimate_row_size(&value);
         FlushStatus::do_insert(self.cache.entry(pk), value);
+        self.flush_if_oversized(epoch).await
     }
 
     /// Delete a key from the managed mview state. `arrange_keys` is composed of group keys and
-    /// primary keys.
-    pub fn delete(&mut self, pk: Row) {
+    /// primary keys. `epoch` is the epoch the mutation belongs to, which is used if the mutation
+    /// triggers a flush.
+    pub async fn delete(&mut self, pk: Row, epoch: u64) -> Result<()> {
         assert_eq!(self.order_types.len(), pk.size());
+        self.check_pk(&pk)?;
 
+        // Deleting a newly inserted pk cancels out the insertion, otherwise a deletion remains.
+        let cancelled = self
+            .cache
+            .get(&pk)
+            .map_or(false, |status| status.is_insert());
+        self.cache_size -= self.cache_entry_size(&pk);
+        if !cancelled {
+            self.cache_size += estimate_row_size(&pk);
+        }
         FlushStatus::do_delete(self.cache.entry(pk));
+        self.flush_if_oversized(epoch).await
+    }
+
+    /// Returns the estimated memory usage of the buffered mutations in bytes.
+    pub fn approximate_size(&self) -> usize {
+        self.cache_size
+    }
+
+    fn cache_entry_size(&self, pk: &Row) -> usize {
+        self.cache.get(pk).map_or(0, |status| {
+            estimate_row_size(pk) + status.as_option().map_or(0, estimate_row_size)
+        })
+    }
+
+    async fn flush_if_oversized(&mut self, epoch: u64) -> Result<()> {
+        match self.flush_threshold_bytes {
+            Some(threshold) if self.cache_size > threshold => self.flush(epoch).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// A null in pk would make the cell-based key encoding ambiguous, so it's always rejected.
+    /// The time bucket column, if any, must be a timestamp.
+    fn check_pk(&self, pk: &Row) -> Result<()> {
+        if let Some(idx) = pk.0.iter().position(|datum| datum.is_none()) {
+            return Err(ErrorCode::InternalError(format!(
+                "primary key column {} of materialized view must not be null",
+                idx
+            ))
+            .into());
+        }
+        if let Some(time_bucket) = &self.time_bucket {
+            time_bucket.key_prefix(pk)?;
+        }
+        Ok(())
     }
 
+    /// Write all buffered mutations to the state store. If the write fails, the mutations are
+    /// kept in the cache, so that they're retried on the next flush.
     pub async fn flush(&mut self, epoch: u64) -> Result<()> {
+        let snapshot = self.take_snapshot();
+        let result = snapshot.write(epoch).await;
+        self.finish_flush(snapshot, result)
+    }
+
+    /// Swap out all buffered mutations as a snapshot to be written by
+    /// [`MViewStateSnapshot::write`]. The snapshot doesn't borrow the state, so new mutations can
+    /// be buffered while it's being written. The result of the write must be passed to
+    /// [`Self::finish_flush`].
+    pub fn take_snapshot(&mut self) -> MViewStateSnapshot<S> {
+        self.cache_size = 0;
+        MViewStateSnapshot {
+            keyspace: self.keyspace.clone(),
+            column_ids: self.column_ids.clone(),
+            key_serializer: self.key_serializer.clone(),
+            flush_parallelism: self.flush_parallelism,
+            time_bucket: self.time_bucket,
+            row_encoding: self.row_encoding,
+            entries: Arc::new(std::mem::take(&mut self.cache).into_iter().collect_vec()),
+        }
+    }
+
+    /// Complete the flush of `snapshot` with the `result` of writing it. If the write failed, the
+    /// mutations of the snapshot are merged back into the cache, except for the pks mutated since
+    /// the snapshot was taken, whose newer mutations supersede them.
+    pub fn finish_flush(
+        &mut self,
+        snapshot: MViewStateSnapshot<S>,
+        result: Result<WriteAmplification>,
+    ) -> Result<()> {
+        match result {
+            Ok(write_amplification) => {
+                self.write_amplification = write_amplification;
+                Ok(())
+            }
+            Err(e) => {
+                // The entries are no longer shared once all serialization tasks are joined. But a
+                // task may still hold them if the write was dropped halfway, so copy them then.
+                let entries = Arc::try_unwrap(snapshot.entries)
+                    .unwrap_or_else(|entries| entries.as_ref().clone());
+                for (pk, status) in entries {
+                    if let hash_map::Entry::Vacant(entry) = self.cache.entry(pk) {
+                        self.cache_size += estimate_row_size(entry.key())
+                            + status.as_option().map_or(0, estimate_row_size);
+                        entry.insert(status);
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the write amplification of the last flush.
+    pub fn write_amplification(&self) -> WriteAmplification {
+        self.write_amplification
+    }
+
+    /// Get the row of `pk`, with the buffered mutations applied. Mutations in a snapshot that's
+    /// being written are not visible until the write succeeds, or they're merged back.
+    pub async fn get(&self, pk: &Row, epoch: u64) -> Result<Option<Row>> {
+        if let Some(status) = self.cache.get(pk) {
+            return Ok(status.as_option().cloned());
+        }
+
+        let arrange_key = serialize_arrange_key(pk, &self.key_serializer, self.time_bucket)?;
+        let cells = self.keyspace.append(arrange_key).scan(None, epoch).await?;
+        let mut rows = self.deserialize_rows(cells)?;
+        assert!(rows.len() <= 1, "multiple rows found under pk {:?}", pk);
+        Ok(rows.pop().map(|(_, row)| row))
+    }
+
+    /// Returns all rows in the order of pk, with the buffered mutations applied. The same
+    /// visibility rule as [`Self::get`] applies.
+    pub async fn iter_rows(&self, epoch: u64) -> Result<Vec<Row>> {
+        let cells = self.keyspace.scan(None, epoch).await?;
+        let prefix_len = self.keyspace.key().len();
+        let mut rows: BTreeMap<_, _> = self
+            .deserialize_rows(cells)?
+            .into_iter()
+            .map(|(key, row)| (key[prefix_len..].to_vec(), row))
+            .collect();
+
+        for (pk, status) in &self.cache {
+            let arrange_key = serialize_arrange_key(pk, &self.key_serializer, self.time_bucket)?;
+            match status.as_option() {
+                Some(row) => rows.insert(arrange_key, row.clone()),
+                None => rows.remove(&arrange_key),
+            };
+        }
+        Ok(rows.into_values().collect())
+    }
+
+    /// Reassemble rows from key-value pairs in key order, and returns them along with their keys
+    /// without the cell ids.
+    fn deserialize_rows(&self, kvs: Vec<(Bytes, Bytes)>) -> Result<Vec<(Vec<u8>, Row)>> {
+        match self.row_encoding {
+            RowEncoding::Cell => {
+                let mut deserializer = CellBasedRowDeserializer::new(self.column_descs.clone());
+                let mut rows = vec![];
+                for (key, value) in kvs {
+                    if let Some(row) = deserializer.deserialize(&key, &value)? {
+                        rows.push(row);
+                    }
+                }
+                rows.extend(deserializer.take());
+                Ok(rows)
+            }
+            RowEncoding::Whole => {
+                let deserializer = RowDeserializer::new(
+                    self.column_descs
+                        .iter()
+                        .map(|desc| desc.data_type.clone())
+                        .collect(),
+                );
+                kvs.into_iter()
+                    .map(|(key, value)| Ok((key.to_vec(), deserializer.deserialize(&value)?)))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Buffered mutations swapped out of a [`ManagedMViewState`] on flush, along with what's needed
+/// to write them.
+pub struct MViewStateSnapshot<S: StateStore> {
+    keyspace: Keyspace<S>,
+    column_ids: Vec<ColumnId>,
+    key_serializer: OrderedRowSerializer,
+    flush_parallelism: usize,
+    time_bucket: Option<TimeBucket>,
+    row_encoding: RowEncoding,
+    entries: Arc<Vec<CacheEntry>>,
+}
+
+impl<S: StateStore> MViewStateSnapshot<S> {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Write the mutations to the state store, and returns the write amplification.
+    pub async fn write(&self, epoch: u64) -> Result<WriteAmplification> {
         let mut batch = self.keyspace.state_store().start_write_batch();
-        batch.reserve(self.cache.len() * self.column_ids.len());
+        let kvs_per_row = match self.row_encoding {
+            RowEncoding::Cell => self.column_ids.len(),
+            RowEncoding::Whole => 1,
+        };
+        batch.reserve(self.entries.len() * kvs_per_row);
         let mut local = batch.prefixify(&self.keyspace);
 
-        for (arrange_keys, cells) in self.cache.drain() {
-            let row = cells.into_option();
-            let arrange_key_buf = serialize_pk(&arrange_keys, &self.key_serializer)?;
-            let bytes = serialize_pk_and_row(&arrange_key_buf, &row, &self.column_ids)?;
-            for (key, value) in bytes {
-                match value {
-                    // TODO(Yuanxin): Implement value meta
-                    Some(val) => local.put(key, StorageValue::new_default_put(val)),
-                    None => local.delete(key),
+        let (cells, logical_bytes) = self.serialize_entries_concurrently().await?;
+        let mut kv_bytes = 0;
+        for (key, value) in cells {
+            kv_bytes += self.keyspace.key().len() + key.len();
+            match value {
+                // TODO(Yuanxin): Implement value meta
+                Some(val) => {
+                    kv_bytes += val.len();
+                    local.put(key, StorageValue::new_default_put(val))
                 }
+                None => local.delete(key),
             }
         }
         batch.ingest(epoch).await?;
-        Ok(())
+
+        Ok(WriteAmplification {
+            kv_bytes,
+            logical_bytes,
+        })
+    }
+
+    /// Serialize all entries into cells. The entries are split into disjoint sets of pks, and
+    /// serialized concurrently if `flush_parallelism` allows.
+    async fn serialize_entries_concurrently(&self) -> Result<SerializedCells> {
+        let entries = &self.entries;
+        if self.flush_parallelism == 1 || entries.len() <= 1 {
+            return Self::serialize_entries(
+                entries,
+                &self.key_serializer,
+                &self.column_ids,
+                self.time_bucket,
+                self.row_encoding,
+            );
+        }
+
+        let chunk_size = (entries.len() + self.flush_parallelism - 1) / self.flush_parallelism;
+        let tasks = (0..entries.len())
+            .step_by(chunk_size)
+            .map(|start| {
+                let entries = entries.clone();
+                let key_serializer = self.key_serializer.clone();
+                let column_ids = self.column_ids.clone();
+                let time_bucket = self.time_bucket;
+                let row_encoding = self.row_encoding;
+                tokio::task::spawn_blocking(move || {
+                    let end = (start + chunk_size).min(entries.len());
+                    Self::serialize_entries(
+                        &entries[start..end],
+                        &key_serializer,
+                        &column_ids,
+                        time_bucket,
+                        row_encoding,
+                    )
+                })
+            })
+            .collect_vec();
+
+        // Join all tasks before returning any error, so that the entries are no longer shared.
+        let mut result = vec![];
+        let mut logical_bytes = 0;
+        for task in futures::future::join_all(tasks).await {
+            let (cells, bytes) = task??;
+            result.extend(cells);
+            logical_bytes += bytes;
+        }
+        Ok((result, logical_bytes))
+    }
+
+    fn serialize_entries(
+        entries: &[CacheEntry],
+        key_serializer: &OrderedRowSerializer,
+        column_ids: &[ColumnId],
+        time_bucket: Option<TimeBucket>,
+        row_encoding: RowEncoding,
+    ) -> Result<SerializedCells> {
+        let mut result = Vec::with_capacity(entries.len() * column_ids.len());
+        let mut logical_bytes = 0;
+        for (arrange_keys, cells) in entries {
+            let row = cells.as_option().cloned();
+            let arrange_key_buf = serialize_arrange_key(arrange_keys, key_serializer, time_bucket)?;
+            let bytes = match row_encoding {
+                RowEncoding::Cell => serialize_pk_and_row(&arrange_key_buf, &row, column_ids)?,
+                RowEncoding::Whole => {
+                    let value = row.map(|row| row.serialize()).transpose()?;
+                    vec![(arrange_key_buf.clone(), value)]
+                }
+            };
+            logical_bytes += arrange_key_buf.len()
+                + bytes
+                    .iter()
+                    .map(|(_, value)| value.as_ref().map_or(0, Vec::len))
+                    .sum::<usize>();
+            result.extend(bytes);
+        }
+        Ok((result, logical_bytes))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use risingwave_common::catalog::schema_test_utils;
+    use risingwave_common::types::{DataType, NaiveDateTimeWrapper};
     use risingwave_common::util::sort_util::OrderType;
+    use risingwave_common::util::value_encoding::serialize_cell;
     use risingwave_storage::memory::MemoryStateStore;
 
     use super::*;
+    use crate::executor_v2::test_utils::FailingStateStore;
+
+    fn column_descs(data_types: &[DataType]) -> Vec<ColumnDesc> {
+        data_types
+            .iter()
+            .enumerate()
+            .map(|(i, data_type)| ColumnDesc::unnamed(ColumnId::from(i as i32), data_type.clone()))
+            .collect()
+    }
 
     #[tokio::test]
     async fn test_mview_state() {
@@ -116,23 +564,38 @@ mod tests {
 
         let mut state = ManagedMViewState::new(
             keyspace.clone(),
-            vec![0.into(), 1.into()],
+            column_descs(&[DataType::Int32, DataType::Int32]),
             vec![OrderType::Ascending],
         );
         let mut epoch: u64 = 0;
-        state.put(
-            Row(vec![Some(1_i32.into())]),
-            Row(vec![Some(1_i32.into()), Some(11_i32.into())]),
-        );
-        state.put(
-            Row(vec![Some(2_i32.into())]),
-            Row(vec![Some(2_i32.into()), Some(22_i32.into())]),
-        );
-        state.put(
-            Row(vec![Some(3_i32.into())]),
-            Row(vec![Some(3_i32.into()), Some(33_i32.into())]),
-        );
-        state.delete(Row(vec![Some(2_i32.into())]));
+        state
+            .put(
+                Row(vec![Some(1_i32.into())]),
+                Row(vec![Some(1_i32.into()), Some(11_i32.into())]),
+                epoch,
+            )
+            .await
+            .unwrap();
+        state
+            .put(
+                Row(vec![Some(2_i32.into())]),
+                Row(vec![Some(2_i32.into()), Some(22_i32.into())]),
+                epoch,
+            )
+            .await
+            .unwrap();
+        state
+            .put(
+                Row(vec![Some(3_i32.into())]),
+                Row(vec![Some(3_i32.into()), Some(33_i32.into())]),
+                epoch,
+            )
+            .await
+            .unwrap();
+        state
+            .delete(Row(vec![Some(2_i32.into())]), epoch)
+            .await
+            .unwrap();
 
         state.flush(epoch).await.unwrap();
         let data = keyspace.scan(None, epoch).await.unwrap();
@@ -140,9 +603,381 @@ mod tests {
         assert_eq!(data.len(), 6);
 
         epoch += 1;
-        state.delete(Row(vec![Some(3_i32.into())]));
+        state
+            .delete(Row(vec![Some(3_i32.into())]), epoch)
+            .await
+            .unwrap();
         state.flush(epoch).await.unwrap();
         let data = keyspace.scan(None, epoch).await.unwrap();
         assert_eq!(data.len(), 3);
     }
+
+    #[tokio::test]
+    async fn test_mview_state_parallel_flush() {
+        let column_count = 64;
+        let column_descs = column_descs(&vec![DataType::Int32; column_count as usize]);
+        let rows = (0..100)
+            .map(|pk| {
+                let pk_row = Row(vec![Some(pk.into())]);
+                let value = Row((0..column_count).map(|c| Some((pk * c).into())).collect());
+                (pk_row, value)
+            })
+            .collect_vec();
+
+        let mut results = vec![];
+        for parallelism in [1, 4] {
+            let keyspace = Keyspace::executor_root(MemoryStateStore::new(), 0x42);
+            let mut state = ManagedMViewState::new(
+                keyspace.clone(),
+                column_descs.clone(),
+                vec![OrderType::Ascending],
+            )
+            .with_flush_parallelism(parallelism);
+            for (pk, value) in rows.iter().cloned() {
+                state.put(pk, value, 0).await.unwrap();
+            }
+            state
+                .delete(Row(vec![Some(42_i32.into())]), 0)
+                .await
+                .unwrap();
+            state.flush(0).await.unwrap();
+            results.push(keyspace.scan(None, 0).await.unwrap());
+        }
+
+        // Every row has a sentinel cell besides the cells of its columns.
+        assert_eq!(results[0].len(), 99 * (column_count as usize + 1));
+        assert_eq!(results[0], results[1]);
+    }
+
+    #[tokio::test]
+    async fn test_mview_state_null_pk() {
+        let keyspace = Keyspace::executor_root(MemoryStateStore::new(), 0x42);
+        let mut state = ManagedMViewState::new(
+            keyspace,
+            column_descs(&[DataType::Int32, DataType::Int32]),
+            vec![OrderType::Ascending, OrderType::Ascending],
+        );
+
+        let err = state
+            .put(
+                Row(vec![Some(1_i32.into()), None]),
+                Row(vec![Some(1_i32.into()), None]),
+                0,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("must not be null"), "{}", err);
+        state
+            .delete(Row(vec![None, Some(1_i32.into())]), 0)
+            .await
+            .unwrap_err();
+        assert!(state.cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mview_state_write_amplification() {
+        let keyspace = Keyspace::executor_root(MemoryStateStore::new(), 0x42);
+        let order_types = vec![OrderType::Ascending];
+        let mut state = ManagedMViewState::new(
+            keyspace.clone(),
+            column_descs(&[DataType::Int32, DataType::Int32]),
+            order_types.clone(),
+        );
+        for i in 0..10_i32 {
+            state
+                .put(
+                    Row(vec![Some(i.into())]),
+                    Row(vec![Some(i.into()), Some((i * 10).into())]),
+                    0,
+                )
+                .await
+                .unwrap();
+        }
+        state.flush(0).await.unwrap();
+
+        // Every row of 2 int columns is stored as 2 cells and a sentinel cell, each of which
+        // repeats the keyspace prefix, the pk and the column id.
+        let pk_len = serialize_pk(
+            &Row(vec![Some(0_i32.into())]),
+            &OrderedRowSerializer::new(order_types),
+        )
+        .unwrap()
+        .len();
+        let cell_len = serialize_cell(&Some(0_i32.into())).unwrap().len();
+        let sentinel_len = serialize_cell(&None).unwrap().len();
+        let column_id_len = 4;
+        let key_len = keyspace.key().len() + pk_len + column_id_len;
+        let expected = WriteAmplification {
+            kv_bytes: 10 * (2 * (key_len + cell_len) + key_len + sentinel_len),
+            logical_bytes: 10 * (pk_len + 2 * cell_len + sentinel_len),
+        };
+        assert_eq!(state.write_amplification(), expected);
+        assert!(state.write_amplification().ratio() > 2.0);
+    }
+
+    #[tokio::test]
+    async fn test_mview_state_flush_retry() {
+        let state_store = FailingStateStore::new();
+        let keyspace = Keyspace::executor_root(state_store.clone(), 0x42);
+        let mut state = ManagedMViewState::new(
+            keyspace.clone(),
+            column_descs(&[DataType::Int32, DataType::Int32]),
+            vec![OrderType::Ascending],
+        )
+        .with_flush_parallelism(2);
+        for i in 0..4_i32 {
+            state
+                .put(
+                    Row(vec![Some(i.into())]),
+                    Row(vec![Some(i.into()), Some((i * 11).into())]),
+                    0,
+                )
+                .await
+                .unwrap();
+        }
+
+        state_store.fail_ingest(1);
+        state.flush(0).await.unwrap_err();
+        assert_eq!(state.cache.len(), 4);
+        assert!(keyspace.scan(None, 0).await.unwrap().is_empty());
+
+        // Mutations after the failed flush are written together with the retried ones.
+        state
+            .delete(Row(vec![Some(0_i32.into())]), 0)
+            .await
+            .unwrap();
+        state.flush(0).await.unwrap();
+        assert!(state.cache.is_empty());
+        // 3 rows of 2 cells and a sentinel cell.
+        assert_eq!(keyspace.scan(None, 0).await.unwrap().len(), 3 * (2 + 1));
+    }
+
+    #[tokio::test]
+    async fn test_mview_state_mutate_during_flush() {
+        let state_store = FailingStateStore::new();
+        let keyspace = Keyspace::executor_root(state_store.clone(), 0x42);
+        let mut state = ManagedMViewState::new(
+            keyspace.clone(),
+            column_descs(&[DataType::Int32, DataType::Int32]),
+            vec![OrderType::Ascending],
+        );
+        let row = |pk: i32, v: i32| Row(vec![Some(pk.into()), Some(v.into())]);
+        let pk = |pk: i32| Row(vec![Some(pk.into())]);
+        for i in 0..3 {
+            state.put(pk(i), row(i, i), 0).await.unwrap();
+        }
+
+        // Mutations land in a fresh cache while the snapshot is being written, and supersede the
+        // ones of the snapshot when it's merged back after the failure.
+        state_store.fail_ingest(1);
+        let snapshot = state.take_snapshot();
+        assert!(state.cache.is_empty());
+        let write = snapshot.write(0);
+        state.delete(pk(0), 0).await.unwrap();
+        state.put(pk(3), row(3, 3), 0).await.unwrap();
+        let result = write.await;
+        state.finish_flush(snapshot, result).unwrap_err();
+        assert_eq!(state.cache.len(), 4);
+        assert!(state.cache[&pk(0)].is_delete());
+
+        // Mutations during a successful write are kept for the next flush.
+        let snapshot = state.take_snapshot();
+        let write = snapshot.write(0);
+        state.delete(pk(1), 0).await.unwrap();
+        state.put(pk(1), row(1, 11), 0).await.unwrap();
+        let result = write.await;
+        state.finish_flush(snapshot, result).unwrap();
+        assert_eq!(state.cache.len(), 1);
+        state.flush(1).await.unwrap();
+
+        let rows = keyspace
+            .scan(None, 1)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect_vec();
+        let expected = [(1, 11), (2, 2), (3, 3)]
+            .into_iter()
+            .flat_map(|(pk, v)| {
+                // The sentinel cell sorts before the cells of the columns.
+                [
+                    serialize_cell(&None).unwrap(),
+                    serialize_cell(&Some(pk.into())).unwrap(),
+                    serialize_cell(&Some(v.into())).unwrap(),
+                ]
+            })
+            .collect_vec();
+        assert_eq!(rows, expected);
+    }
+
+    #[tokio::test]
+    async fn test_mview_state_read() {
+        let keyspace = Keyspace::executor_root(MemoryStateStore::new(), 0x42);
+        let mut state = ManagedMViewState::new(
+            keyspace.clone(),
+            column_descs(&[DataType::Int32, DataType::Varchar, DataType::Int64]),
+            vec![OrderType::Ascending],
+        );
+        let pk = |pk: i32| Row(vec![Some(pk.into())]);
+        let row1 = Row(vec![
+            Some(1_i32.into()),
+            Some("abc".to_string().into()),
+            Some(10_i64.into()),
+        ]);
+        let row2 = Row(vec![Some(2_i32.into()), None, None]);
+
+        state.put(pk(1), row1.clone(), 0).await.unwrap();
+        state.put(pk(2), row2.clone(), 0).await.unwrap();
+        assert_eq!(state.get(&pk(1), 0).await.unwrap(), Some(row1.clone()));
+        assert_eq!(state.get(&pk(3), 0).await.unwrap(), None);
+        state.flush(0).await.unwrap();
+
+        // Read back from storage.
+        assert_eq!(state.get(&pk(1), 0).await.unwrap(), Some(row1.clone()));
+        assert_eq!(state.get(&pk(2), 0).await.unwrap(), Some(row2.clone()));
+        assert_eq!(
+            state.iter_rows(0).await.unwrap(),
+            vec![row1.clone(), row2.clone()]
+        );
+
+        // Buffered mutations shadow the rows in storage.
+        let row3 = Row(vec![Some(3_i32.into()), None, Some(30_i64.into())]);
+        state.delete(pk(1), 0).await.unwrap();
+        state.put(pk(3), row3.clone(), 0).await.unwrap();
+        assert_eq!(state.get(&pk(1), 0).await.unwrap(), None);
+        assert_eq!(state.iter_rows(0).await.unwrap(), vec![row2, row3]);
+    }
+
+    #[tokio::test]
+    async fn test_mview_state_auto_flush() {
+        let keyspace = Keyspace::executor_root(MemoryStateStore::new(), 0x42);
+        let row_size =
+            estimate_row_size(&Row(vec![None])) + estimate_row_size(&Row(vec![None, None]));
+        let mut state = ManagedMViewState::new(
+            keyspace.clone(),
+            column_descs(&[DataType::Int32, DataType::Int32]),
+            vec![OrderType::Ascending],
+        )
+        .with_flush_threshold(row_size * 3);
+        let row = |i: i32| Row(vec![Some(i.into()), Some((i * 10).into())]);
+        let pk = |i: i32| Row(vec![Some(i.into())]);
+
+        // An insertion cancelled out by a deletion takes no memory.
+        state.put(pk(0), row(0), 0).await.unwrap();
+        state.delete(pk(0), 0).await.unwrap();
+        assert_eq!(state.approximate_size(), 0);
+
+        for i in 1..=3 {
+            state.put(pk(i), row(i), 0).await.unwrap();
+        }
+        assert_eq!(state.approximate_size(), row_size * 3);
+        assert!(keyspace.scan(None, 0).await.unwrap().is_empty());
+
+        // Crossing the threshold drains the cache without an explicit flush.
+        state.put(pk(4), row(4), 0).await.unwrap();
+        assert!(state.cache.is_empty());
+        assert_eq!(state.approximate_size(), 0);
+        assert_eq!(
+            state.iter_rows(0).await.unwrap(),
+            (1..=4).map(row).collect_vec()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mview_state_whole_row_encoding() {
+        let keyspace = Keyspace::executor_root(MemoryStateStore::new(), 0x42);
+        let mut state = ManagedMViewState::new(
+            keyspace.clone(),
+            column_descs(&[DataType::Int32, DataType::Varchar, DataType::Int64]),
+            vec![OrderType::Ascending],
+        )
+        .with_row_encoding(RowEncoding::Whole)
+        .with_flush_parallelism(2);
+        let pk = |pk: i32| Row(vec![Some(pk.into())]);
+        let rows = vec![
+            Row(vec![
+                Some(1_i32.into()),
+                Some("abc".to_string().into()),
+                Some(10_i64.into()),
+            ]),
+            Row(vec![Some(2_i32.into()), None, None]),
+            Row(vec![Some(3_i32.into()), Some("".to_string().into()), None]),
+        ];
+        for row in &rows {
+            state
+                .put(Row(vec![row[0].clone()]), row.clone(), 0)
+                .await
+                .unwrap();
+        }
+        state.flush(0).await.unwrap();
+
+        // Exactly one entry per pk, keyed by the pk itself.
+        let kvs = keyspace.scan(None, 0).await.unwrap();
+        assert_eq!(kvs.len(), rows.len());
+        let pk_key = serialize_pk(
+            &pk(1),
+            &OrderedRowSerializer::new(vec![OrderType::Ascending]),
+        )
+        .unwrap();
+        assert_eq!(kvs[0].0, [keyspace.key(), &pk_key].concat());
+
+        assert_eq!(state.get(&pk(2), 0).await.unwrap(), Some(rows[1].clone()));
+        assert_eq!(state.iter_rows(0).await.unwrap(), rows);
+
+        state.delete(pk(2), 1).await.unwrap();
+        state.flush(1).await.unwrap();
+        assert_eq!(keyspace.scan(None, 1).await.unwrap().len(), rows.len() - 1);
+        assert_eq!(state.get(&pk(2), 1).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_mview_state_time_bucket() {
+        let keyspace = Keyspace::executor_root(MemoryStateStore::new(), 0x42);
+        let mut state = ManagedMViewState::new(
+            keyspace.clone(),
+            column_descs(&[DataType::Int32, DataType::Timestamp]),
+            vec![OrderType::Ascending, OrderType::Ascending],
+        )
+        .with_time_bucket(1, Duration::from_secs(3600));
+
+        let timestamp = |secs| {
+            Some(ScalarImpl::NaiveDateTime(
+                NaiveDateTimeWrapper::new_with_secs_nsecs(secs, 0).unwrap(),
+            ))
+        };
+        // Rows 0 and 1 are in the first hour, row 2 is in the second.
+        for (id, secs) in [(0_i32, 600), (1, 1200), (2, 3900)] {
+            let pk = Row(vec![Some(id.into()), timestamp(secs)]);
+            state.put(pk.clone(), pk, 0).await.unwrap();
+        }
+        state.flush(0).await.unwrap();
+
+        let bucket_keyspace = |secs| {
+            let pk = Row(vec![Some(0_i32.into()), timestamp(secs)]);
+            keyspace.append(state.time_bucket.unwrap().key_prefix(&pk).unwrap().to_vec())
+        };
+        // Each row has 2 cells and a sentinel cell.
+        assert_eq!(bucket_keyspace(0).scan(None, 0).await.unwrap().len(), 2 * 3);
+        assert_eq!(bucket_keyspace(3600).scan(None, 0).await.unwrap().len(), 3);
+        assert_eq!(bucket_keyspace(7200).scan(None, 0).await.unwrap().len(), 0);
+
+        // Buckets are ordered by time, regardless of the rest of pk.
+        let data = keyspace.scan(None, 0).await.unwrap();
+        assert!(data
+            .last()
+            .unwrap()
+            .0
+            .starts_with(bucket_keyspace(3600).key()));
+
+        let err = state
+            .put(
+                Row(vec![Some(3_i32.into()), Some(3_i32.into())]),
+                Row(vec![None, None]),
+                0,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("must be a timestamp"), "{}", err);
+    }
 }
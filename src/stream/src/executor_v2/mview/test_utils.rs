@@ -28,25 +28,29 @@ pub async fn gen_basic_table(row_count: usize) -> CellBasedTable<MemoryStateStor
     let orderings = vec![OrderType::Ascending, OrderType::Descending];
     let keyspace = Keyspace::executor_root(state_store, 0x42);
     let column_ids = vec![0.into(), 1.into(), 2.into()];
-    let mut state = ManagedMViewState::new(
-        keyspace.clone(),
-        column_ids.clone(),
-        vec![OrderType::Ascending, OrderType::Descending],
-    );
     let column_descs = vec![
         ColumnDesc::unnamed(column_ids[0], DataType::Int32),
         ColumnDesc::unnamed(column_ids[1], DataType::Int32),
         ColumnDesc::unnamed(column_ids[2], DataType::Int32),
     ];
+    let mut state = ManagedMViewState::new(
+        keyspace.clone(),
+        column_descs.clone(),
+        vec![OrderType::Ascending, OrderType::Descending],
+    );
     let table = CellBasedTable::new_for_test(keyspace.clone(), column_descs, orderings);
     let epoch: u64 = 0;
 
     for idx in 0..row_count {
         let idx = idx as i32;
-        state.put(
-            Row(vec![Some(idx.into()), Some(idx.into())]),
-            Row(vec![Some(idx.into()), Some(idx.into()), Some(idx.into())]),
-        );
+        state
+            .put(
+                Row(vec![Some(idx.into()), Some(idx.into())]),
+                Row(vec![Some(idx.into()), Some(idx.into()), Some(idx.into())]),
+                epoch,
+            )
+            .await
+            .unwrap();
     }
     state.flush(epoch).await.unwrap();
     table
@@ -134,3 +134,147 @@ impl Executor for MockSource {
 pub fn create_in_memory_keyspace() -> Keyspace<MemoryStateStore> {
     Keyspace::executor_root(MemoryStateStore::new(), 0x2333)
 }
+
+#[cfg(test)]
+pub use failing_state_store::FailingStateStore;
+
+#[cfg(test)]
+mod failing_state_store {
+    use std::future::Future;
+    use std::ops::RangeBounds;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use risingwave_storage::hummock::HummockError;
+    use risingwave_storage::memory::MemoryStateStore;
+    use risingwave_storage::storage_value::StorageValue;
+    use risingwave_storage::store::*;
+    use risingwave_storage::{define_state_store_associated_type, StateStore};
+
+    /// A [`MemoryStateStore`] that fails the next `n` calls to `ingest_batch` after [`fail_ingest`]
+    /// is called, for testing recovery from write errors.
+    ///
+    /// [`fail_ingest`]: FailingStateStore::fail_ingest
+    #[derive(Clone, Default)]
+    pub struct FailingStateStore {
+        inner: MemoryStateStore,
+        ingest_failures: Arc<AtomicUsize>,
+        ingested_batches: Arc<AtomicUsize>,
+    }
+
+    impl FailingStateStore {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Makes the next `n` calls to `ingest_batch` fail without writing anything.
+        pub fn fail_ingest(&self, n: usize) {
+            self.ingest_failures.store(n, Ordering::SeqCst);
+        }
+
+        /// Returns the number of calls to `ingest_batch` that succeeded.
+        pub fn ingested_batches(&self) -> usize {
+            self.ingested_batches.load(Ordering::SeqCst)
+        }
+    }
+
+    impl StateStore for FailingStateStore {
+        type Iter<'a> = <MemoryStateStore as StateStore>::Iter<'a>;
+
+        define_state_store_associated_type!();
+
+        fn get<'a>(&'a self, key: &'a [u8], epoch: u64) -> Self::GetFuture<'_> {
+            async move { self.inner.get(key, epoch).await }
+        }
+
+        fn multi_get(&self, keys: Vec<Vec<u8>>, epoch: u64) -> Self::MultiGetFuture<'_> {
+            async move { self.inner.multi_get(keys, epoch).await }
+        }
+
+        fn count_prefix(&self, prefix: Vec<u8>, epoch: u64) -> Self::CountPrefixFuture<'_> {
+            async move { self.inner.count_prefix(prefix, epoch).await }
+        }
+
+        fn prefix_exists(&self, prefix: Vec<u8>, epoch: u64) -> Self::PrefixExistsFuture<'_> {
+            async move { self.inner.prefix_exists(prefix, epoch).await }
+        }
+
+        fn scan<R, B>(
+            &self,
+            key_range: R,
+            limit: Option<usize>,
+            epoch: u64,
+        ) -> Self::ScanFuture<'_, R, B>
+        where
+            R: RangeBounds<B> + Send,
+            B: AsRef<[u8]> + Send,
+        {
+            async move { self.inner.scan(key_range, limit, epoch).await }
+        }
+
+        fn reverse_scan<R, B>(
+            &self,
+            key_range: R,
+            limit: Option<usize>,
+            epoch: u64,
+        ) -> Self::ReverseScanFuture<'_, R, B>
+        where
+            R: RangeBounds<B> + Send,
+            B: AsRef<[u8]> + Send,
+        {
+            async move { self.inner.reverse_scan(key_range, limit, epoch).await }
+        }
+
+        fn ingest_batch(
+            &self,
+            kv_pairs: Vec<(Bytes, StorageValue)>,
+            epoch: u64,
+        ) -> Self::IngestBatchFuture<'_> {
+            async move {
+                let failed = self
+                    .ingest_failures
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                    .is_ok();
+                if failed {
+                    return Err(HummockError::other("injected ingest failure").into());
+                }
+                self.inner.ingest_batch(kv_pairs, epoch).await?;
+                self.ingested_batches.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+
+        fn replicate_batch(
+            &self,
+            kv_pairs: Vec<(Bytes, StorageValue)>,
+            epoch: u64,
+        ) -> Self::ReplicateBatchFuture<'_> {
+            async move { self.inner.replicate_batch(kv_pairs, epoch).await }
+        }
+
+        fn iter<R, B>(&self, key_range: R, epoch: u64) -> Self::IterFuture<'_, R, B>
+        where
+            R: RangeBounds<B> + Send,
+            B: AsRef<[u8]> + Send,
+        {
+            async move { self.inner.iter(key_range, epoch).await }
+        }
+
+        fn reverse_iter<R, B>(&self, key_range: R, epoch: u64) -> Self::ReverseIterFuture<'_, R, B>
+        where
+            R: RangeBounds<B> + Send,
+            B: AsRef<[u8]> + Send,
+        {
+            async move { self.inner.reverse_iter(key_range, epoch).await }
+        }
+
+        fn wait_epoch(&self, epoch: u64) -> Self::WaitEpochFuture<'_> {
+            async move { self.inner.wait_epoch(epoch).await }
+        }
+
+        fn sync(&self, epoch: Option<u64>) -> Self::SyncFuture<'_> {
+            async move { self.inner.sync(epoch).await }
+        }
+    }
+}
@@ -0,0 +1,5 @@
+--
+-- Run once by the schedule before all tests.
+--
+CREATE TABLE regress_setup_t (v1 INT, v2 INT);
+INSERT INTO regress_setup_t VALUES (1, 2), (3, 4);
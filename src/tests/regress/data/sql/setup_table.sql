@@ -0,0 +1,5 @@
+--
+-- SETUP_TABLE
+--
+-- The table is created by the setup script of the schedule.
+SELECT v1, v2 FROM regress_setup_t ORDER BY v1;
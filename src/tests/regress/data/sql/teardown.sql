@@ -0,0 +1,4 @@
+--
+-- Run once by the schedule after all tests.
+--
+DROP TABLE regress_setup_t;
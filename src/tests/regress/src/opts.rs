@@ -45,6 +45,18 @@ pub(crate) struct Opts {
     /// Location for customized log file.
     #[clap(long, parse(from_os_str), default_value = "config/log4rs.yaml", value_hint=ValueHint::FilePath)]
     log4rs_config: PathBuf,
+    /// Maximum number of test cases in a parallel schedule running at the same time. All test
+    /// cases of a parallel schedule run at the same time if not set.
+    #[clap(name = "JOBS", short = 'j', long = "jobs")]
+    jobs: Option<usize>,
+    /// Rewrite expected outputs of different test cases from their actual outputs, which is
+    /// useful when results are changed intentionally.
+    #[clap(long)]
+    update_golden: bool,
+    /// Number of times to re-run a failed test case before it's considered failed. Test cases
+    /// passed after retrying are reported as flaky.
+    #[clap(name = "RETRIES", long = "retries", default_value = "0")]
+    retries: usize,
 }
 
 impl Opts {
@@ -95,4 +107,16 @@ impl Opts {
     pub(crate) fn port(&self) -> u16 {
         self.pg_server_port
     }
+
+    pub(crate) fn jobs(&self) -> Option<usize> {
+        self.jobs
+    }
+
+    pub(crate) fn update_golden(&self) -> bool {
+        self.update_golden
+    }
+
+    pub(crate) fn retries(&self) -> usize {
+        self.retries
+    }
 }
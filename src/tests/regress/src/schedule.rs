@@ -14,24 +14,29 @@
 
 use std::collections::HashMap;
 use std::fs::File;
+use std::future::Future;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::{bail, Context};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use tokio::process::Command;
+use tokio::sync::Semaphore;
 
-use crate::schedule::TestResult::{Different, Same};
+use crate::schedule::TestResult::{Different, Flaky, Same};
 use crate::{init_env, FileManager, Opts, Psql};
 
 /// Result of each test case.
-#[derive(PartialEq)]
+#[derive(PartialEq, Debug)]
 enum TestResult {
     /// Execution of the test case succeeded, and results are same.
     Same,
     /// Execution of the test case succeeded, but outputs are different from expected result.
     Different,
+    /// Execution of the test case failed or outputs were different at first, but results are
+    /// same after retrying. See [`Opts::retries`].
+    Flaky,
 }
 
 struct TestCase {
@@ -41,23 +46,42 @@ struct TestCase {
     file_manager: Arc<FileManager>,
 }
 
+/// Contents of a schedule file.
+#[derive(Default)]
+struct ScheduleFile {
+    /// Script run once before all tests, declared by a `setup: <name>` line.
+    setup: Option<String>,
+    /// Script run once after all tests, declared by a `teardown: <name>` line.
+    teardown: Option<String>,
+    /// Parallel schedules, each declared by a `test: <name>...` line.
+    schedules: Vec<Vec<String>>,
+}
+
 pub(crate) struct Schedule {
     opts: Opts,
     file_manager: Arc<FileManager>,
     psql: Arc<Psql>,
+    /// Name of the script run before all tests. Its source is looked up like a test case's.
+    setup: Option<String>,
+    /// Name of the script run after all tests, even if some of them failed.
+    teardown: Option<String>,
     /// Schedules of test names.
     ///
-    /// Each item is called a parallel schedule, which runs parallel.
+    /// Each item is called a parallel schedule, which runs parallel, with at most
+    /// [`Opts::jobs`] test cases at the same time. Parallel schedules run one after another.
     schedules: Vec<Vec<String>>,
 }
 
 impl Schedule {
     pub(crate) fn new(opts: Opts) -> anyhow::Result<Self> {
+        let schedule_file = Schedule::parse_from(opts.schedule_file_path())?;
         Ok(Self {
             opts: opts.clone(),
             file_manager: Arc::new(FileManager::new(opts.clone())),
             psql: Arc::new(Psql::new(opts.clone())),
-            schedules: Schedule::parse_from(opts.schedule_file_path())?,
+            setup: schedule_file.setup,
+            teardown: schedule_file.teardown,
+            schedules: schedule_file.schedules,
         })
     }
 
@@ -70,30 +94,43 @@ impl Schedule {
         Ok(self)
     }
 
-    fn parse_from<P: AsRef<Path>>(path: P) -> anyhow::Result<Vec<Vec<String>>> {
+    fn parse_from<P: AsRef<Path>>(path: P) -> anyhow::Result<ScheduleFile> {
         let file = File::options()
             .read(true)
             .open(path.as_ref())
             .with_context(|| format!("Failed to open schedule file: {:?}", path.as_ref()))?;
 
         let reader = BufReader::new(file);
-        let mut schedules = Vec::new();
+        let mut schedule_file = ScheduleFile::default();
 
         for line in reader.lines() {
             let line = line?;
             if line.starts_with("test: ") {
-                schedules.push(
+                schedule_file.schedules.push(
                     line[5..]
                         .trim()
                         .split_whitespace()
                         .map(ToString::to_string)
                         .collect(),
                 );
-                debug!("Add one parallel schedule: {:?}", schedules.last().unwrap());
+                debug!(
+                    "Add one parallel schedule: {:?}",
+                    schedule_file.schedules.last().unwrap()
+                );
+            } else if let Some(name) = line.strip_prefix("setup: ") {
+                if schedule_file.setup.is_some() {
+                    bail!("Schedule file declares more than one setup script");
+                }
+                schedule_file.setup = Some(name.trim().to_string());
+            } else if let Some(name) = line.strip_prefix("teardown: ") {
+                if schedule_file.teardown.is_some() {
+                    bail!("Schedule file declares more than one teardown script");
+                }
+                schedule_file.teardown = Some(name.trim().to_string());
             }
         }
 
-        Ok(schedules)
+        Ok(schedule_file)
     }
 
     /// Run all test schedules.
@@ -108,6 +145,86 @@ impl Schedule {
     }
 
     async fn do_run(self) -> anyhow::Result<()> {
+        if let Some(setup) = &self.setup {
+            info!("Running setup script: {}", setup);
+            self.run_script(setup)
+                .await
+                .with_context(|| format!("Setup script {} failed, aborting", setup))?;
+        }
+
+        let ret = self.run_schedules().await;
+
+        if let Some(teardown) = &self.teardown {
+            info!("Running teardown script: {}", teardown);
+            let teardown_ret = self
+                .run_script(teardown)
+                .await
+                .with_context(|| format!("Teardown script {} failed", teardown));
+            if let Err(e) = teardown_ret {
+                error!("{:?}", e);
+                // Failures of tests are more interesting than the one of teardown.
+                return ret.and(Err(e));
+            }
+        }
+
+        ret
+    }
+
+    /// Run a setup or teardown script, and fail on the first error in it.
+    ///
+    /// Unlike test cases, the output of scripts is not compared with expected output, and is only
+    /// kept in the results directory for troubleshooting.
+    async fn run_script(&self, name: &str) -> anyhow::Result<()> {
+        let input_path = self.file_manager.source_of(name)?;
+        let output_path = self.file_manager.output_of(name)?;
+        let output_file = File::options()
+            .create_new(true)
+            .write(true)
+            .open(&output_path)
+            .with_context(|| format!("Failed to create {:?} for writing output.", output_path))?;
+
+        let mut command = Command::new("psql");
+        command.args([
+            "-X",
+            "-a",
+            "-q",
+            "-h",
+            self.opts.host().as_str(),
+            "-p",
+            format!("{}", self.opts.port()).as_str(),
+            "-d",
+            self.opts.database_name(),
+            "-v",
+            "ON_ERROR_STOP=1",
+        ]);
+        command.arg("-f").arg(&input_path);
+        command.env("PGAPPNAME", format!("risingwave_regress/{}", name));
+        command.stdout(
+            output_file
+                .try_clone()
+                .with_context(|| format!("Failed to clone output file: {:?}", output_path))?,
+        );
+        command.stderr(output_file);
+
+        let status = command
+            .spawn()
+            .with_context(|| format!("Failed to spawn child for script: {}", name))?
+            .wait()
+            .await
+            .with_context(|| format!("Failed to wait for finishing script: {}", name))?;
+
+        if !status.success() {
+            bail!(
+                "Execution of script {} failed, reason: {:?}, see {:?} for details",
+                name,
+                status,
+                output_path
+            );
+        }
+        Ok(())
+    }
+
+    async fn run_schedules(&self) -> anyhow::Result<()> {
         let mut different_tests = Vec::new();
         for parallel_schedule in &self.schedules {
             info!("Running parallel schedule: {:?}", parallel_schedule);
@@ -121,6 +238,18 @@ impl Schedule {
                 .map(|t| t.0.clone())
                 .collect::<Vec<String>>();
 
+            let flaky_test = ret
+                .iter()
+                .filter(|(_test_name, test_result)| **test_result == Flaky)
+                .map(|t| t.0.as_str())
+                .collect::<Vec<_>>();
+            if !flaky_test.is_empty() {
+                warn!(
+                    "These tests are flaky, and passed after retrying: {:?}",
+                    flaky_test
+                );
+            }
+
             if !diff_test.is_empty() {
                 error!(
                     "Parallel schedule failed, these tests are different: {:?}",
@@ -132,44 +261,17 @@ impl Schedule {
             }
         }
 
-        if !different_tests.is_empty() {
-            info!(
-                "Risingwave regress tests failed, these tests are different from expected output: {:?}",
-                different_tests
-            );
-            bail!(
-                "Risingwave regress tests failed, these tests are different from expected output: {:?}",
-                different_tests
-            )
-        } else {
-            info!("Risingwave regress tests passed.");
-            Ok(())
-        }
+        report_results(&different_tests)
     }
 
     async fn run_one_schedule(
         &self,
         tests: impl Iterator<Item = &str>,
     ) -> anyhow::Result<HashMap<String, TestResult>> {
-        let mut join_handles = HashMap::new();
-
-        for test_name in tests {
-            let test_case = self.create_test_case(test_name);
-            let join_handle = tokio::spawn(async move { test_case.run().await });
-            join_handles.insert(test_name, join_handle);
-        }
-
-        let mut result = HashMap::new();
-
-        for (test_name, join_handle) in join_handles {
-            let ret = join_handle
-                .await
-                .with_context(|| format!("Running test case {} panicked!", test_name))??;
-
-            result.insert(test_name.to_string(), ret);
-        }
-
-        Ok(result)
+        run_parallel(tests, self.opts.jobs(), |test_name| {
+            self.create_test_case(test_name).run()
+        })
+        .await
     }
 
     fn create_test_case(&self, test_name: &str) -> TestCase {
@@ -183,7 +285,22 @@ impl Schedule {
 }
 
 impl TestCase {
+    /// Run the test case, and re-run it up to [`Opts::retries`] times if it fails.
+    ///
+    /// Each run starts a new psql process with the same environment, so session state is set up
+    /// from scratch.
     async fn run(self) -> anyhow::Result<TestResult> {
+        run_with_retries(&self.test_name, self.opts.retries(), |attempt| {
+            self.run_once(attempt)
+        })
+        .await
+    }
+
+    async fn run_once(&self, attempt: usize) -> anyhow::Result<TestResult> {
+        if attempt > 0 {
+            self.clean_output()?;
+        }
+
         let mut command = Command::new("psql");
         command.args([
             "-X",
@@ -253,42 +370,381 @@ impl TestCase {
             );
         }
 
-        let expected_output =
-            std::fs::read_to_string(&expected_output_file).with_context(|| {
+        self.check_output(&expected_output_file, &output_path)
+    }
+
+    /// Remove the output and diff left by the last run.
+    fn clean_output(&self) -> anyhow::Result<()> {
+        for path in [
+            self.file_manager.output_of(&self.test_name)?,
+            self.file_manager.diff_of(&self.test_name)?,
+        ] {
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {:?}", path))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Compare the actual output with the expected one.
+    ///
+    /// On mismatch, a unified diff is logged and written to [`FileManager::diff_of`]. If
+    /// [`Opts::update_golden`] is set, the expected output is rewritten from the actual output
+    /// instead, and the test case is considered passed.
+    fn check_output(
+        &self,
+        expected_output_file: &Path,
+        output_path: &Path,
+    ) -> anyhow::Result<TestResult> {
+        let expected_output = std::fs::read_to_string(expected_output_file).with_context(|| {
+            format!(
+                "Failed to read expected output file: {:?}",
+                expected_output_file
+            )
+        })?;
+
+        let actual_output = std::fs::read_to_string(output_path)
+            .with_context(|| format!("Failed to read actual output file: {:?}", output_path))?;
+
+        if expected_output == actual_output {
+            return Ok(Same);
+        }
+
+        if self.opts.update_golden() {
+            std::fs::write(expected_output_file, &actual_output).with_context(|| {
                 format!(
-                    "Failed to read expected output file: {:?}",
+                    "Failed to update expected output file: {:?}",
                     expected_output_file
                 )
             })?;
+            info!(
+                "Updated expected output of [{}]: {:?}",
+                self.test_name, expected_output_file
+            );
+            return Ok(Same);
+        }
 
-        let actual_output = std::fs::read_to_string(&output_path)
-            .with_context(|| format!("Failed to read actual output file: {:?}", output_path))?;
+        let diff = format_diff(&expected_output, &actual_output);
+        let diff_path = self.file_manager.diff_of(&self.test_name)?;
+        std::fs::write(&diff_path, &diff)
+            .with_context(|| format!("Failed to write diff file: {:?}", diff_path))?;
+        error!(
+            "Expected output of [{}] is different from actual output, see {:?} for details.\n{}",
+            self.test_name,
+            diff_path,
+            colorize_diff(&diff)
+        );
+        Ok(Different)
+    }
+}
 
-        if expected_output == actual_output {
-            Ok(Same)
-        } else {
-            error!(
-                "Expected output of [{}] is different from actual output.\n{}",
-                self.test_name,
-                format_diff(&expected_output, &actual_output)
-            );
-            Ok(Different)
+/// Run a test case with `run` up to `1 + retries` times, until it succeeds with the same output as
+/// expected. `run` is called with the number of previous attempts.
+async fn run_with_retries<F, Fut>(
+    test_name: &str,
+    retries: usize,
+    mut run: F,
+) -> anyhow::Result<TestResult>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = anyhow::Result<TestResult>>,
+{
+    let mut attempt = 0;
+    loop {
+        let ret = run(attempt).await;
+        match &ret {
+            Ok(Same) if attempt > 0 => {
+                warn!(
+                    "Test case {} is flaky, and passed after {} retries",
+                    test_name, attempt
+                );
+                return Ok(Flaky);
+            }
+            Ok(Same) | Ok(Flaky) => return ret,
+            _ if attempt >= retries => return ret,
+            Ok(Different) => warn!(
+                "Test case {} is different from expected output, retrying ({}/{})",
+                test_name,
+                attempt + 1,
+                retries
+            ),
+            Err(e) => warn!(
+                "Test case {} failed: {:?}, retrying ({}/{})",
+                test_name,
+                e,
+                attempt + 1,
+                retries
+            ),
         }
+        attempt += 1;
     }
 }
 
-fn format_diff(expected_output: &String, actual_output: &String) -> String {
-    use similar::{ChangeTag, TextDiff};
-    let diff = TextDiff::from_lines(expected_output, actual_output);
+/// Fails if any test case is different from its expected output.
+fn report_results(different_tests: &[String]) -> anyhow::Result<()> {
+    if !different_tests.is_empty() {
+        info!(
+            "Risingwave regress tests failed, these tests are different from expected output: {:?}",
+            different_tests
+        );
+        bail!(
+            "Risingwave regress tests failed, these tests are different from expected output: {:?}",
+            different_tests
+        )
+    } else {
+        info!("Risingwave regress tests passed.");
+        Ok(())
+    }
+}
+
+/// Run `tests` of a parallel schedule with `run`, and at most `jobs` of them at the same time.
+///
+/// Results are keyed by test name, so they are attributed to the right test case no matter in
+/// which order test cases finish.
+async fn run_parallel<'a, F, Fut>(
+    tests: impl Iterator<Item = &'a str>,
+    jobs: Option<usize>,
+    run: F,
+) -> anyhow::Result<HashMap<String, TestResult>>
+where
+    F: Fn(&str) -> Fut,
+    Fut: Future<Output = anyhow::Result<TestResult>> + Send + 'static,
+{
+    let tests = tests.collect::<Vec<_>>();
+    let semaphore = Arc::new(Semaphore::new(jobs.unwrap_or(tests.len()).max(1)));
+    let mut join_handles = HashMap::new();
+
+    for test_name in tests {
+        let test_case = run(test_name);
+        let semaphore = semaphore.clone();
+        let join_handle = tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await?;
+            test_case.await
+        });
+        join_handles.insert(test_name, join_handle);
+    }
+
+    let mut result = HashMap::new();
 
-    let mut diff_str = "".to_string();
-    for change in diff.iter_all_changes() {
-        let sign = match change.tag() {
-            ChangeTag::Delete => "-",
-            ChangeTag::Insert => "+",
-            ChangeTag::Equal => " ",
+    for (test_name, join_handle) in join_handles {
+        let ret = join_handle
+            .await
+            .with_context(|| format!("Running test case {} panicked!", test_name))??;
+        info!("Test case {} finished: {:?}", test_name, ret);
+
+        result.insert(test_name.to_string(), ret);
+    }
+
+    Ok(result)
+}
+
+/// Returns the unified diff from `expected_output` to `actual_output`.
+fn format_diff(expected_output: &str, actual_output: &str) -> String {
+    similar::TextDiff::from_lines(expected_output, actual_output)
+        .unified_diff()
+        .header("expected", "actual")
+        .to_string()
+}
+
+/// Colorizes a unified diff for terminals: deleted lines in red, inserted lines in green, and
+/// hunk headers in cyan.
+fn colorize_diff(diff: &str) -> String {
+    diff.lines()
+        .map(|line| {
+            let color = match line.chars().next() {
+                Some('-') => "\x1b[31m",
+                Some('+') => "\x1b[32m",
+                Some('@') => "\x1b[36m",
+                _ => return format!("{}\n", line),
+            };
+            format!("{}{}\x1b[0m\n", color, line)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use clap::Parser;
+
+    use super::*;
+
+    async fn run_with_jobs(
+        tests: &[&str],
+        jobs: Option<usize>,
+    ) -> (HashMap<String, TestResult>, usize) {
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let result = run_parallel(tests.iter().copied(), jobs, |test_name| {
+            let running = running.clone();
+            let max_running = max_running.clone();
+            let ret = if test_name == "different" {
+                Different
+            } else {
+                Same
+            };
+            async move {
+                let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now_running, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(ret)
+            }
+        })
+        .await
+        .unwrap();
+        (result, max_running.load(Ordering::SeqCst))
+    }
+
+    fn parse_opts(dir: &Path, extra_args: &[&str]) -> Opts {
+        let input_dir = dir.join("data");
+        let output_dir = dir.join("output");
+        let schedule = input_dir.join("schedule");
+        let mut args = vec![
+            "risingwave_regress_test",
+            "-h",
+            "127.0.0.1",
+            "-p",
+            "4567",
+            "-i",
+            input_dir.to_str().unwrap(),
+            "-o",
+            output_dir.to_str().unwrap(),
+            "-s",
+            schedule.to_str().unwrap(),
+        ];
+        args.extend_from_slice(extra_args);
+        Opts::try_parse_from(args).unwrap()
+    }
+
+    /// Writes a test case whose actual output differs from the expected one, and returns the
+    /// paths of its expected and actual output.
+    fn create_mismatched_test_case(opts: &Opts, test_name: &str) -> (TestCase, PathBuf, PathBuf) {
+        let file_manager = FileManager::new(opts.clone());
+        std::fs::create_dir_all(opts.absolutized_input_dir().unwrap().join("expected")).unwrap();
+        std::fs::create_dir_all(opts.absolutized_output_dir().unwrap().join("results")).unwrap();
+
+        let expected_output_file = opts
+            .absolutized_input_dir()
+            .unwrap()
+            .join("expected")
+            .join(format!("{}.out", test_name));
+        let output_path = file_manager.output_of(test_name).unwrap();
+        std::fs::write(
+            &expected_output_file,
+            "select 1;\n ?column? \n----------\n        1\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &output_path,
+            "select 1;\n ?column? \n----------\n        2\n",
+        )
+        .unwrap();
+
+        let test_case = TestCase {
+            test_name: test_name.to_string(),
+            opts: opts.clone(),
+            psql: Arc::new(Psql::new(opts.clone())),
+            file_manager: Arc::new(file_manager),
         };
-        diff_str.push_str(&format!("{}{}", sign, change));
+        (test_case, expected_output_file, output_path)
+    }
+
+    #[test]
+    fn test_check_output_with_diff() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = parse_opts(dir.path(), &[]);
+        let (test_case, expected_output_file, output_path) =
+            create_mismatched_test_case(&opts, "mismatch");
+
+        let ret = test_case
+            .check_output(&expected_output_file, &output_path)
+            .unwrap();
+        assert_eq!(ret, Different);
+
+        let diff =
+            std::fs::read_to_string(test_case.file_manager.diff_of("mismatch").unwrap()).unwrap();
+        assert!(diff.contains("--- expected\n+++ actual\n"));
+        assert!(diff.contains("\n-        1\n+        2\n"));
+        assert!(colorize_diff(&diff).contains("\x1b[31m-        1\x1b[0m\n"));
+
+        assert!(report_results(&["mismatch".to_string()]).is_err());
+        assert!(report_results(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_check_output_with_update_golden() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = parse_opts(dir.path(), &["--update-golden"]);
+        let (test_case, expected_output_file, output_path) =
+            create_mismatched_test_case(&opts, "mismatch");
+
+        let ret = test_case
+            .check_output(&expected_output_file, &output_path)
+            .unwrap();
+        assert_eq!(ret, Same);
+        assert_eq!(
+            std::fs::read_to_string(&expected_output_file).unwrap(),
+            std::fs::read_to_string(&output_path).unwrap()
+        );
+        assert!(!test_case.file_manager.diff_of("mismatch").unwrap().exists());
+    }
+
+    /// Runs a test case which fails `failures` times and then passes, with `retries` given by
+    /// the options. Returns the result and the number of runs.
+    async fn run_flaky_test_case(failures: usize, retries: &str) -> (TestResult, usize) {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = parse_opts(dir.path(), &["--retries", retries]);
+        let runs = AtomicUsize::new(0);
+        let ret = run_with_retries("flaky", opts.retries(), |attempt| {
+            let run = runs.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(attempt, run);
+            async move {
+                match run {
+                    0 if failures > 0 => anyhow::bail!("Execution of test case flaky failed"),
+                    run if run < failures => Ok(Different),
+                    _ => Ok(Same),
+                }
+            }
+        })
+        .await;
+        (ret.unwrap_or(Different), runs.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_run_with_retries() {
+        // Fails once, then passes on retry.
+        assert_eq!(run_flaky_test_case(1, "1").await, (Flaky, 2));
+        // Fails once and isn't retried.
+        assert_eq!(run_flaky_test_case(1, "0").await, (Different, 1));
+        // Different twice, and runs out of retries.
+        assert_eq!(run_flaky_test_case(3, "2").await, (Different, 3));
+        // Passes at once.
+        assert_eq!(run_flaky_test_case(0, "2").await, (Same, 1));
+    }
+
+    #[tokio::test]
+    async fn test_run_parallel_with_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let opts = parse_opts(dir.path(), &["--jobs", "2"]);
+        assert_eq!(opts.jobs(), Some(2));
+
+        let (result, max_running) = run_with_jobs(&["same", "different"], opts.jobs()).await;
+        assert_eq!(result.len(), 2);
+        assert_eq!(result["same"], Same);
+        assert_eq!(result["different"], Different);
+        assert_eq!(max_running, 2);
+
+        let (result, max_running) = run_with_jobs(&["a", "b", "c"], Some(2)).await;
+        assert_eq!(result.len(), 3);
+        assert!(max_running <= 2);
+
+        let (result, max_running) = run_with_jobs(&["a", "b", "c"], None).await;
+        assert_eq!(result.len(), 3);
+        assert_eq!(max_running, 3);
     }
-    diff_str
 }